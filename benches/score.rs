@@ -0,0 +1,73 @@
+//! Scoring a large group's overview while it loads
+//!
+//! Run with `cargo bench --features score`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use brokaw::score::ScoreFile;
+use brokaw::types::prelude::*;
+
+const RULES: &str = "\
+kill
+    from = *@spam.example
+[comp.*,!comp.os.*]
+score 50
+    subject ~ \\brust\\b
+score -20
+    subject ~ ^re:
+    !references ~ <my-post@example\\.com>
+score -100
+    xref > 4
+kill
+    lines > 1000
+";
+
+const OVERVIEWS: usize = 100_000;
+
+fn overviews() -> Vec<Overview> {
+    (0..OVERVIEWS)
+        .map(|n| Overview {
+            number: n as ArticleNumber,
+            subject: match n % 3 {
+                0 => format!("Re: Rust release {}", n),
+                1 => format!("Question about borrowing #{}", n),
+                _ => format!("Announcing crate-{} v0.1", n),
+            },
+            from: format!("user{}@{}.example", n % 1000, ["mail", "spam"][n % 2]),
+            date: "Fri, 15 May 2015 10:00:00 +0000".to_string(),
+            message_id: format!("<{}@example.com>", n),
+            references: if n % 3 == 0 {
+                format!("<{}@example.com>", n - 1)
+            } else {
+                String::new()
+            },
+            bytes: Some(2000 + n as u64 % 500),
+            lines: Some(40 + n as u64 % 1200),
+            extra: vec![format!(
+                "Xref: news.example comp.lang.rust:{} comp.misc:{}",
+                n, n
+            )],
+        })
+        .collect()
+}
+
+fn score_overviews(c: &mut Criterion) {
+    let scorer = RULES.parse::<ScoreFile>().unwrap().scorer("comp.lang.rust");
+    let overviews = overviews();
+
+    let mut group = c.benchmark_group("score");
+    group.throughput(Throughput::Elements(OVERVIEWS as u64));
+    group.sample_size(20);
+    group.bench_function("overviews", |b| {
+        b.iter(|| {
+            overviews
+                .iter()
+                .filter(|overview| !scorer.score_overview(black_box(overview)).killed)
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, score_overviews);
+criterion_main!(benches);
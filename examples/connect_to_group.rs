@@ -30,7 +30,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         address,
         port,
         group,
-        no_tls: _,
+        no_tls,
         username,
     } = Opt::from_args();
 
@@ -44,12 +44,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config
             .authinfo_user_pass(username, password)
             .group(Some(group))
-            .connection_config(
-                ConnectionConfig::default()
-                    .read_timeout(Some(Duration::from_secs(5)))
-                    .default_tls(&address)?
-                    .to_owned(),
-            );
+            .connection_config({
+                let mut conn_config = ConnectionConfig::default();
+                conn_config.read_timeout(Some(Duration::from_secs(5)));
+                if !no_tls {
+                    conn_config.default_tls(&address)?;
+                }
+                conn_config
+            });
 
         config
     };
@@ -0,0 +1,448 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::*;
+
+use crate::client::NntpClient;
+use crate::error::{ErrorClass, Result};
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// A destination for archived articles
+///
+/// Implemented by [`MboxWriter`] and [`Maildir`].
+pub trait ArticleSink {
+    /// Write a single article
+    fn write_article(&mut self, article: &BinaryArticle) -> io::Result<()>;
+}
+
+/// Writes articles to an [mbox](https://en.wikipedia.org/wiki/Mbox) file
+///
+/// Each article is preceded by a `From ` separator line containing the address from the
+/// article's `From` header and the time it was written. Body lines that could be mistaken for a
+/// separator are quoted using the reversible *mboxrd* convention, i.e. a `>` is prepended to any
+/// line matching `^>*From `.
+///
+/// Messages are written with LF line endings, headers are written in the order they appeared in
+/// the article.
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use brokaw::archive::{ArticleSink, MboxWriter};
+/// use brokaw::types::prelude::*;
+///
+/// let resp = RawResponse::with_data_blocks(
+///     Kind::Article,
+///     "1 <a@example.com>",
+///     "From: Alice <alice@example.com>\nSubject: hi\n\nFrom here on\n",
+/// );
+/// let article = BinaryArticle::try_from(&resp).unwrap();
+///
+/// let mut mbox = MboxWriter::new(Vec::new());
+/// mbox.write_article(&article).unwrap();
+///
+/// let mbox = String::from_utf8(mbox.into_inner().unwrap()).unwrap();
+/// assert!(mbox.starts_with("From alice@example.com "));
+/// assert!(mbox.ends_with("Subject: hi\n\n>From here on\n\n"));
+/// ```
+#[derive(Debug)]
+pub struct MboxWriter<W: Write> {
+    inner: BufWriter<W>,
+}
+
+impl<W: Write> MboxWriter<W> {
+    /// Create a writer that appends articles to `w`
+    pub fn new(w: W) -> Self {
+        Self {
+            inner: BufWriter::new(w),
+        }
+    }
+
+    /// Flush any buffered output and return the underlying writer
+    pub fn into_inner(self) -> io::Result<W> {
+        self.inner.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+impl MboxWriter<fs::File> {
+    /// Open an mbox file for appending, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write> ArticleSink for MboxWriter<W> {
+    fn write_article(&mut self, article: &BinaryArticle) -> io::Result<()> {
+        let sender = article
+            .headers()
+            .get("From")
+            .and_then(|h| h.content.first())
+            .and_then(|from| envelope_sender(from))
+            .unwrap_or("MAILER-DAEMON");
+
+        writeln!(self.inner, "From {} {}", sender, asctime(SystemTime::now()))?;
+        write_message(&mut self.inner, article, true)?;
+        self.inner.write_all(b"\n")?;
+        self.inner.flush()
+    }
+}
+
+/// A [Maildir](https://cr.yp.to/proto/maildir.html) mailbox
+///
+/// Articles are written to the `tmp` directory under a unique name and then moved into `new`,
+/// so readers never see a partially written message.
+///
+/// Messages are written with LF line endings, headers are written in the order they appeared in
+/// the article.
+#[derive(Clone, Debug)]
+pub struct Maildir {
+    path: PathBuf,
+    /// The host name used in message file names, escaped per the Maildir spec
+    hostname: String,
+}
+
+impl Maildir {
+    /// Open a Maildir, creating the `tmp`, `new`, and `cur` directories if they do not exist
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        for dir in &["tmp", "new", "cur"] {
+            fs::create_dir_all(path.join(dir))?;
+        }
+        Ok(Self {
+            path,
+            hostname: hostname(),
+        })
+    }
+
+    /// The root directory of the Maildir
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Deliver an article into `new`, returning the path of the delivered message
+    pub fn deliver(&self, article: &BinaryArticle) -> io::Result<PathBuf> {
+        let name = unique_name(&self.hostname);
+        let tmp = self.path.join("tmp").join(&name);
+        let new = self.path.join("new").join(&name);
+
+        let write = || -> io::Result<()> {
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&tmp)?;
+            let mut w = BufWriter::new(file);
+            write_message(&mut w, article, false)?;
+            w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            fs::rename(&tmp, &new)
+        };
+
+        write().inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })?;
+
+        Ok(new)
+    }
+}
+
+impl ArticleSink for Maildir {
+    fn write_article(&mut self, article: &BinaryArticle) -> io::Result<()> {
+        self.deliver(article).map(|_| ())
+    }
+}
+
+/// The articles archived by [`archive_group`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ArchiveSummary {
+    /// The articles that were written to the sink
+    pub archived: ArticleSet,
+    /// The articles in the requested range that the server did not have
+    pub missing: ArticleSet,
+}
+
+/// Download a range of articles from a group into an [`ArticleSink`]
+///
+/// The range is clamped to the low and high water marks of the group. Articles that have
+/// expired or been cancelled are recorded in [`ArchiveSummary::missing`], any other error stops
+/// the archive.
+///
+/// ```no_run
+/// use brokaw::archive::{archive_group, Maildir};
+/// use brokaw::client::ClientConfig;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut client = ClientConfig::default().connect(("news.example.com", 119))?;
+/// let mut maildir = Maildir::create("archive/misc.test")?;
+///
+/// let summary = archive_group(&mut client, "misc.test", 1..=1000, &mut maildir)?;
+/// println!("Archived {} articles", summary.archived.len());
+/// # Ok(())
+/// # }
+/// ```
+pub fn archive_group(
+    client: &mut NntpClient,
+    group: impl AsRef<str>,
+    range: RangeInclusive<ArticleNumber>,
+    sink: &mut impl ArticleSink,
+) -> Result<ArchiveSummary> {
+    let group = client.select_group(group)?;
+    let mut summary = ArchiveSummary::default();
+    if group.number == 0 {
+        return Ok(summary);
+    }
+
+    let low = *range.start().max(&group.low);
+    let high = *range.end().min(&group.high);
+    debug!("Archiving {} articles {}-{}", group.name, low, high);
+
+    for number in low..=high {
+        match client.article(cmd::Article::Number(number)) {
+            Ok(article) => {
+                sink.write_article(&article)?;
+                summary.archived.insert(number);
+            }
+            Err(e) if e.class() == ErrorClass::NoSuchArticle => {
+                trace!("Article {} is missing", number);
+                summary.missing.insert(number);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Write an article's headers and body with LF line endings
+///
+/// If `quote_from` is true body lines matching `^>*From ` are quoted per mboxrd
+fn write_message(w: &mut impl Write, article: &BinaryArticle, quote_from: bool) -> io::Result<()> {
+    for (name, content) in article.headers().in_order() {
+        writeln!(w, "{}: {}", name, content.replace("\r\n", "\n"))?;
+    }
+    w.write_all(b"\n")?;
+
+    for line in article.unterminated() {
+        // the body is kept in wire format, so undo the dot-stuffing
+        let line = line.strip_prefix(b".").unwrap_or(line);
+        if quote_from && is_from_line(line) {
+            w.write_all(b">")?;
+        }
+        w.write_all(line)?;
+        w.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+fn is_from_line(line: &[u8]) -> bool {
+    let start = line.iter().position(|b| *b != b'>').unwrap_or(line.len());
+    line[start..].starts_with(b"From ")
+}
+
+/// Extract the address from a `From` header for use in an mbox separator line
+///
+/// Handles both `Name <addr>` and `addr (Name)` forms
+fn envelope_sender(from: &str) -> Option<&str> {
+    let addr = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from.split_whitespace().next().unwrap_or_default(),
+    };
+
+    if addr.is_empty() || addr.contains(char::is_whitespace) {
+        None
+    } else {
+        Some(addr)
+    }
+}
+
+/// Format a time like C's `asctime`, e.g. `Thu Jan  1 00:00:00 1970`, in UTC
+fn asctime(time: SystemTime) -> String {
+    const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let days = secs.div_euclid(86_400);
+    let secs = secs.rem_euclid(86_400);
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{} {} {:2} {:02}:{:02}:{:02} {}",
+        DAYS[(days + 4).rem_euclid(7) as usize],
+        MONTHS[month as usize - 1],
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        year
+    )
+}
+
+/// The host name for Maildir message file names
+///
+/// `/` and `:` are escaped as they are not allowed in the name.
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+        .replace('/', "\\057")
+        .replace(':', "\\072")
+}
+
+/// A unique file name for a Maildir message
+///
+/// `<seconds>.M<microseconds>P<pid>Q<counter>.<hostname>`
+fn unique_name(hostname: &str) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!(
+        "{}.M{}P{}Q{}.{}",
+        now.as_secs(),
+        now.subsec_micros(),
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        hostname
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::client::ClientConfig;
+    use crate::raw::transcript::fixtures::{exchange, reader_session};
+
+    fn article(number: ArticleNumber, body: &str) -> BinaryArticle {
+        let resp = RawResponse::with_data_blocks(
+            Kind::Article,
+            format!("{} <{}@example.com>", number, number),
+            format!(
+                "From: \"Alice\" <alice@example.com>\nMessage-ID: <{}@example.com>\n\n{}",
+                number, body
+            ),
+        );
+        BinaryArticle::try_from(&resp).unwrap()
+    }
+
+    #[test]
+    fn test_mbox_quoting() {
+        let mut mbox = MboxWriter::new(Vec::new());
+        mbox.write_article(&article(1, "From me\n>From you\n.dot\nFromage\n"))
+            .unwrap();
+        let mbox = String::from_utf8(mbox.into_inner().unwrap()).unwrap();
+
+        let (separator, message) = mbox.split_once('\n').unwrap();
+        assert!(separator.starts_with("From alice@example.com "));
+        assert_eq!(
+            message,
+            "From: \"Alice\" <alice@example.com>\nMessage-ID: <1@example.com>\n\n\
+             >From me\n>>From you\n.dot\nFromage\n\n"
+        );
+    }
+
+    #[test]
+    fn test_header_order() {
+        let resp = RawResponse::with_data_blocks(
+            Kind::Article,
+            "1 <1@example.com>",
+            "Subject: hi\nX-Trace: a\nFrom: alice@example.com\nX-Trace: b\n\nbody\n",
+        );
+        let article = BinaryArticle::try_from(&resp).unwrap();
+
+        let mut message = Vec::new();
+        write_message(&mut message, &article, false).unwrap();
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            "Subject: hi\nX-Trace: a\nFrom: alice@example.com\nX-Trace: b\n\nbody\n"
+        );
+    }
+
+    #[test]
+    fn test_envelope_sender() {
+        assert_eq!(
+            envelope_sender("Alice <alice@example.com>"),
+            Some("alice@example.com")
+        );
+        assert_eq!(
+            envelope_sender("bob@example.com (Bob)"),
+            Some("bob@example.com")
+        );
+        assert_eq!(envelope_sender(""), None);
+    }
+
+    #[test]
+    fn test_asctime() {
+        assert_eq!(asctime(UNIX_EPOCH), "Thu Jan  1 00:00:00 1970");
+        assert_eq!(
+            asctime(UNIX_EPOCH + Duration::from_secs(1_591_290_821)),
+            "Thu Jun  4 17:13:41 2020"
+        );
+    }
+
+    #[test]
+    fn test_archive_to_maildir() {
+        let mut transcript = reader_session();
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 3 1 3 misc.test\r\n",
+        );
+        exchange(
+            &mut transcript,
+            "ARTICLE 2",
+            "220 2 <2@example.com>\r\nMessage-ID: <2@example.com>\r\n\r\n..hi\r\n.\r\n",
+        );
+        exchange(
+            &mut transcript,
+            "ARTICLE 3",
+            "423 No article with that number\r\n",
+        );
+
+        let mut client = ClientConfig::default().replay(transcript).unwrap();
+        let path = std::env::temp_dir().join(format!("brokaw-maildir-{}", process::id()));
+        let mut maildir = Maildir::create(&path).unwrap();
+
+        let summary = archive_group(&mut client, "misc.test", 2..=10, &mut maildir).unwrap();
+        assert_eq!(summary.archived.to_string(), "2");
+        assert_eq!(summary.missing.to_string(), "3");
+
+        let delivered = fs::read_dir(path.join("new"))
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(delivered, vec!["Message-ID: <2@example.com>\n\n.hi\n"]);
+        assert_eq!(fs::read_dir(path.join("tmp")).unwrap().count(), 0);
+
+        fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A temporary path next to `path` that is unique to this write
+///
+/// The name includes the process ID, the thread ID, and a counter so that concurrent writers
+/// never share a temporary file.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let thread = format!("{:?}", thread::current().id())
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(format!(
+        ".tmp.{}.{}.{}",
+        process::id(),
+        thread,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    tmp.into()
+}
+
+/// Replace the file at `path` with the output of `write`
+///
+/// The contents are written and synced to a new temporary file which is then renamed over the
+/// destination, so that readers and crashes never observe a partially written file.
+pub(crate) fn write_file(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let res = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)
+        .and_then(|file| {
+            let mut file = BufWriter::new(file);
+            write(&mut file)?;
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path));
+
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_file() {
+        let dir = std::env::temp_dir().join(format!("brokaw-atomic-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state");
+
+        write_file(&path, |w| w.write_all(b"first")).unwrap();
+        write_file(&path, |w| w.write_all(b"second")).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        let err = write_file(&path, |_| Err(io::ErrorKind::InvalidData.into()));
+        assert!(err.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        // The temporary file is cleaned up
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert_ne!(temp_path(&path), temp_path(&path));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+//! A caching NNTP proxy
+//!
+//! Multiplexes many reader connections onto a bounded pool of upstream connections and caches
+//! articles on disk. See `brokaw-proxy --help` for usage.
+
+use std::env;
+use std::error::Error;
+use std::net::TcpListener;
+use std::process;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use brokaw::client::ClientConfig;
+use brokaw::proxy::ProxyConfig;
+use brokaw::ConnectionConfig;
+
+const USAGE: &str = "\
+Usage: brokaw-proxy --upstream HOST:PORT [OPTIONS]
+
+Options:
+    --upstream HOST:PORT     The upstream NNTP server
+    --listen ADDR:PORT       The address to accept readers on [default: 127.0.0.1:1119]
+    --tls                    Connect to the upstream server with TLS
+    --username USER          Authenticate with the upstream server
+                             The password is read from BROKAW_UPSTREAM_PASSWORD
+    --max-connections N      The maximum number of upstream connections [default: 4]
+    --cache-dir DIR          Cache articles in DIR
+    --reader-username USER   Require readers to authenticate
+                             The password is read from BROKAW_READER_PASSWORD
+    -h, --help               Print this message
+
+The log level is read from RUST_LOG [default: info]";
+
+#[derive(Debug)]
+struct Opt {
+    upstream: String,
+    listen: String,
+    tls: bool,
+    username: Option<String>,
+    max_connections: usize,
+    cache_dir: Option<String>,
+    reader_username: Option<String>,
+}
+
+impl Opt {
+    fn from_args() -> Result<Self, String> {
+        let mut opt = Opt {
+            upstream: String::new(),
+            listen: "127.0.0.1:1119".to_string(),
+            tls: false,
+            username: None,
+            max_connections: 4,
+            cache_dir: None,
+            reader_username: None,
+        };
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--upstream" => opt.upstream = value()?,
+                "--listen" => opt.listen = value()?,
+                "--tls" => opt.tls = true,
+                "--username" => opt.username = Some(value()?),
+                "--max-connections" => {
+                    opt.max_connections = value()?
+                        .parse()
+                        .map_err(|_| "--max-connections must be a number".to_string())?
+                }
+                "--cache-dir" => opt.cache_dir = Some(value()?),
+                "--reader-username" => opt.reader_username = Some(value()?),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                other => return Err(format!("Unknown argument `{}`", other)),
+            }
+        }
+
+        if opt.upstream.is_empty() {
+            return Err("--upstream is required".to_string());
+        }
+
+        Ok(opt)
+    }
+}
+
+/// A minimal logger that writes to stderr
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn password(var: &str) -> Result<String, String> {
+    env::var(var).map_err(|_| format!("{} must be set", var))
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
+    let host = opt
+        .upstream
+        .rsplit_once(':')
+        .map_or(opt.upstream.as_str(), |(host, _port)| host);
+
+    let mut conn_config = ConnectionConfig::default();
+    if opt.tls {
+        conn_config.default_tls(host)?;
+    }
+
+    let mut upstream = ClientConfig::default();
+    upstream.connection_config(conn_config);
+    if let Some(username) = opt.username {
+        upstream.authinfo_user_pass(username, password("BROKAW_UPSTREAM_PASSWORD")?);
+    }
+
+    let mut config = ProxyConfig::default();
+    config
+        .upstream(upstream)
+        .max_connections(opt.max_connections)
+        .cache_dir(opt.cache_dir);
+    if let Some(username) = opt.reader_username {
+        config.authinfo_user_pass(username, password("BROKAW_READER_PASSWORD")?);
+    }
+
+    let proxy = config.build(opt.upstream.as_str())?;
+    proxy.serve(TcpListener::bind(opt.listen.as_str())?)?;
+    Ok(())
+}
+
+fn main() {
+    let level = env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    log::set_logger(&StderrLogger).expect("Logger already set");
+    log::set_max_level(level);
+
+    let opt = Opt::from_args().unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    if let Err(e) = run(opt) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,719 @@
+//! A command-line NNTP client
+//!
+//! Runs a single command against a news server and writes the result to stdout so that it can
+//! be used in scripts. See `brokaw --help` for usage.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use brokaw::client::{ClientConfig, NntpClient};
+use brokaw::raw::Compression;
+use brokaw::types::command as cmd;
+use brokaw::types::prelude::*;
+use brokaw::ConnectionConfig;
+
+const USAGE: &str = "\
+Usage: brokaw --server HOST[:PORT] [OPTIONS] COMMAND [ARGS]
+
+Commands:
+    caps                     List the server's capabilities
+    groups [WILDMAT]         List the active groups, optionally matching WILDMAT
+    group NAME               Print the number of articles and the low and high water marks
+    over RANGE               Print the overview records for RANGE (e.g. 100-200, 100-, 100)
+                             Requires --group
+    article ID               Print an article by number or <message-id>
+    head ID                  Print the headers of an article by number or <message-id>
+    body ID                  Print the body of an article by number or <message-id>
+    stat ID                  Print the number and message-id of an article
+    post FILE                Post the article in FILE, or stdin if FILE is `-`
+
+Options:
+    --server HOST[:PORT]     The NNTP server [default port: 563, or 119 with --no-tls]
+    --no-tls                 Connect without TLS
+    --group NAME             Select a group before running the command
+    --netrc FILE             Read credentials for the server from FILE
+                             [default: $NETRC or ~/.netrc if it exists]
+    --compress               Enable XFEATURE COMPRESS GZIP for overviews
+    --xzver                  Use XZVER for overviews
+    --format tsv|json        The output format for `over` [default: tsv]
+    --timeout SECS           The read timeout [default: 30]
+    -h, --help               Print this message
+
+The log level is read from RUST_LOG [default: warn]";
+
+#[derive(Debug)]
+struct Opt {
+    server: String,
+    tls: bool,
+    group: Option<String>,
+    netrc: Option<PathBuf>,
+    compress: bool,
+    xzver: bool,
+    format: Format,
+    timeout: u64,
+    command: Command,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Format {
+    Tsv,
+    Json,
+}
+
+#[derive(Debug)]
+enum Command {
+    Caps,
+    Groups(Option<Wildmat>),
+    Group(String),
+    Over(String),
+    Article(Id),
+    Head(Id),
+    Body(Id),
+    Stat(Id),
+    Post(String),
+}
+
+/// An article number or message-id
+#[derive(Debug)]
+enum Id {
+    Number(ArticleNumber),
+    MessageId(String),
+}
+
+impl Id {
+    fn parse(s: &str) -> Result<Self, String> {
+        if s.starts_with('<') && s.ends_with('>') {
+            Ok(Id::MessageId(s.to_string()))
+        } else {
+            s.parse()
+                .map(Id::Number)
+                .map_err(|_| format!("`{}` is neither an article number nor a <message-id>", s))
+        }
+    }
+}
+
+impl Opt {
+    fn from_args() -> Result<Self, String> {
+        let mut server = None;
+        let mut tls = true;
+        let mut group = None;
+        let mut netrc = None;
+        let mut compress = false;
+        let mut xzver = false;
+        let mut format = Format::Tsv;
+        let mut timeout = 30;
+        let mut positional = Vec::new();
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--server" => server = Some(value()?),
+                "--no-tls" => tls = false,
+                "--group" => group = Some(value()?),
+                "--netrc" => netrc = Some(PathBuf::from(value()?)),
+                "--compress" => compress = true,
+                "--xzver" => xzver = true,
+                "--format" => {
+                    format = match value()?.as_str() {
+                        "tsv" => Format::Tsv,
+                        "json" => Format::Json,
+                        other => return Err(format!("Unknown format `{}`", other)),
+                    }
+                }
+                "--timeout" => {
+                    timeout = value()?
+                        .parse()
+                        .map_err(|_| "--timeout must be a number".to_string())?
+                }
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                other if other.starts_with("--") => {
+                    return Err(format!("Unknown argument `{}`", other))
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let server = server.ok_or_else(|| "--server is required".to_string())?;
+        let command = Command::parse(positional)?;
+        if let Command::Over(_) = command {
+            if group.is_none() {
+                return Err("`over` requires --group".to_string());
+            }
+        }
+
+        Ok(Opt {
+            server,
+            tls,
+            group,
+            netrc,
+            compress,
+            xzver,
+            format,
+            timeout,
+            command,
+        })
+    }
+}
+
+impl Command {
+    fn parse(args: Vec<String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let name = args.next().ok_or_else(|| "Missing command".to_string())?;
+        let arg = args.next();
+        if let Some(extra) = args.next() {
+            return Err(format!("Unexpected argument `{}`", extra));
+        }
+
+        let required = |what: &str| {
+            arg.clone()
+                .ok_or_else(|| format!("`{}` requires {}", name, what))
+        };
+        let command = match name.as_str() {
+            "caps" => Command::Caps,
+            "groups" => Command::Groups(
+                arg.as_deref()
+                    .map(Wildmat::new)
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+            ),
+            "group" => Command::Group(required("a group name")?),
+            "over" => Command::Over(required("a range")?),
+            "article" => Command::Article(Id::parse(&required("an ID")?)?),
+            "head" => Command::Head(Id::parse(&required("an ID")?)?),
+            "body" => Command::Body(Id::parse(&required("an ID")?)?),
+            "stat" => Command::Stat(Id::parse(&required("an ID")?)?),
+            "post" => Command::Post(required("a file")?),
+            other => return Err(format!("Unknown command `{}`", other)),
+        };
+
+        match (&command, arg) {
+            (Command::Caps, Some(extra)) => Err(format!("Unexpected argument `{}`", extra)),
+            _ => Ok(command),
+        }
+    }
+}
+
+/// A minimal logger that writes to stderr
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Credentials for a single machine in a netrc file
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Credentials {
+    login: Option<String>,
+    password: Option<String>,
+}
+
+/// Find the credentials for `host` in the contents of a netrc file
+///
+/// The first matching `machine` entry is used, falling back to the `default` entry.
+/// `macdef` definitions are skipped.
+fn netrc_lookup(contents: &str, host: &str) -> Option<Credentials> {
+    let mut found = None;
+    let mut default = None;
+    // The entry currently being parsed, and whether it is the one for `host`
+    let mut current: Option<(Credentials, Option<bool>)> = None;
+    let mut in_macdef = false;
+
+    fn finish(
+        current: Option<(Credentials, Option<bool>)>,
+        found: &mut Option<Credentials>,
+        default: &mut Option<Credentials>,
+    ) {
+        match current {
+            Some((creds, Some(true))) if found.is_none() => *found = Some(creds),
+            Some((creds, None)) if default.is_none() => *default = Some(creds),
+            _ => {}
+        }
+    }
+
+    for line in contents.lines() {
+        if in_macdef {
+            in_macdef = !line.trim().is_empty();
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        while let Some(token) = tokens.next() {
+            match token {
+                "machine" => {
+                    finish(current.take(), &mut found, &mut default);
+                    let machine = tokens.next().unwrap_or_default();
+                    current = Some((Credentials::default(), Some(machine == host)));
+                }
+                "default" => {
+                    finish(current.take(), &mut found, &mut default);
+                    current = Some((Credentials::default(), None));
+                }
+                "login" => {
+                    if let Some((creds, _)) = current.as_mut() {
+                        creds.login = tokens.next().map(ToString::to_string);
+                    }
+                }
+                "password" => {
+                    if let Some((creds, _)) = current.as_mut() {
+                        creds.password = tokens.next().map(ToString::to_string);
+                    }
+                }
+                "account" => {
+                    tokens.next();
+                }
+                "macdef" => {
+                    in_macdef = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+    finish(current, &mut found, &mut default);
+
+    found.or(default)
+}
+
+/// The netrc file to read credentials from, if any
+fn netrc_path(opt: &Opt) -> Option<PathBuf> {
+    if let Some(path) = opt.netrc.clone() {
+        return Some(path);
+    }
+
+    env::var_os("NETRC")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".netrc")))
+        .filter(|path| path.is_file())
+}
+
+/// Split `HOST[:PORT]` into the host and port
+///
+/// IPv6 addresses may be given bare (`::1`), or in brackets to specify a port (`[::1]:119`).
+fn host_port(server: &str, tls: bool) -> Result<(&str, u16), String> {
+    let default_port = if tls { 563 } else { 119 };
+    let port = |port: &str| port.parse().map_err(|_| format!("Invalid port `{}`", port));
+
+    if let Some(rest) = server.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid server `{}`", server))?;
+        return match rest {
+            "" => Ok((host, default_port)),
+            _ => match rest.strip_prefix(':') {
+                Some(p) => Ok((host, port(p)?)),
+                None => Err(format!("Invalid server `{}`", server)),
+            },
+        };
+    }
+
+    match server.split_once(':') {
+        // More than one colon is a bare IPv6 address
+        Some((_, rest)) if rest.contains(':') => Ok((server, default_port)),
+        Some((host, p)) => Ok((host, port(p)?)),
+        None => Ok((server, default_port)),
+    }
+}
+
+/// Parse an article range such as `100-200`, `100-`, or `100`
+///
+/// An open range ends at the high water mark of the group.
+fn parse_range(range: &str, group: &Group) -> Result<(ArticleNumber, ArticleNumber), String> {
+    let number = |s: &str| {
+        s.parse::<ArticleNumber>()
+            .map_err(|_| format!("Invalid range `{}`", range))
+    };
+    match range.split_once('-') {
+        Some((low, "")) => Ok((number(low)?, group.high)),
+        Some((low, high)) => Ok((number(low)?, number(high)?)),
+        None => number(range).map(|n| (n, n)),
+    }
+}
+
+/// Write an overview record as a tab separated line
+fn write_tsv(w: &mut impl Write, overview: &Overview) -> io::Result<()> {
+    let metadata = |field: Option<u64>| field.map(|n| n.to_string()).unwrap_or_default();
+    write!(
+        w,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        overview.number,
+        overview.subject,
+        overview.from,
+        overview.date,
+        overview.message_id,
+        overview.references,
+        metadata(overview.bytes),
+        metadata(overview.lines),
+    )?;
+    for field in &overview.extra {
+        write!(w, "\t{}", field)?;
+    }
+    writeln!(w)
+}
+
+/// Write an overview record as a JSON object
+fn write_json(w: &mut impl Write, overview: &Overview) -> io::Result<()> {
+    serde_json::to_writer(w, overview).map_err(io::Error::from)
+}
+
+/// Write the lines of a multi-line response with the dot-stuffing removed
+fn write_data_blocks(w: &mut impl Write, resp: &RawResponse) -> io::Result<()> {
+    if let Some(data_blocks) = resp.data_blocks() {
+        for line in data_blocks.unterminated() {
+            let line = if line.starts_with(b"..") {
+                &line[1..]
+            } else {
+                line
+            };
+            w.write_all(line)?;
+            w.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// The client configuration for the options, excluding credentials
+fn client_config(opt: &Opt, host: &str) -> Result<ClientConfig, Box<dyn Error>> {
+    let mut conn_config = ConnectionConfig::default();
+    conn_config.read_timeout(Some(Duration::from_secs(opt.timeout)));
+    if opt.tls {
+        conn_config.default_tls(host)?;
+    }
+    if opt.compress {
+        conn_config.compression(Some(Compression::XFeature));
+    }
+
+    let mut config = ClientConfig::default();
+    config
+        .connection_config(conn_config)
+        .group(opt.group.as_ref())
+        .xzver(opt.xzver);
+    Ok(config)
+}
+
+/// Enable any features requested in the options on a newly connected client
+fn init(mut client: NntpClient, opt: &Opt) -> Result<NntpClient, Box<dyn Error>> {
+    if opt.compress {
+        client.command(cmd::XFeatureCompress)?.fail_unless(290)?;
+    }
+    Ok(client)
+}
+
+fn connect(opt: &Opt) -> Result<NntpClient, Box<dyn Error>> {
+    let (host, port) = host_port(&opt.server, opt.tls)?;
+    let mut config = client_config(opt, host)?;
+
+    if let Some(path) = netrc_path(opt) {
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if let Some(Credentials {
+            login: Some(login),
+            password,
+        }) = netrc_lookup(&contents, host)
+        {
+            config.authinfo_user_pass(login, password.unwrap_or_default());
+        }
+    }
+
+    init(config.connect((host, port))?, opt)
+}
+
+fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
+    let mut client = connect(&opt)?;
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+
+    match &opt.command {
+        Command::Caps => {
+            let mut caps = client.capabilities().iter().collect::<Vec<_>>();
+            caps.sort_by(|a, b| a.name.cmp(&b.name));
+            for cap in caps {
+                let mut args = cap.args.iter().flatten().collect::<Vec<_>>();
+                args.sort();
+                write!(out, "{}", cap.name)?;
+                for arg in args {
+                    write!(out, " {}", arg)?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Command::Groups(wildmat) => {
+            let resp = client
+                .command(cmd::List::Active {
+                    wildmat: wildmat.clone(),
+                })?
+                .fail_unless(Kind::List)?;
+            write_data_blocks(&mut out, &resp)?;
+        }
+        Command::Group(name) => {
+            let group = client.select_group(name)?;
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                group.name, group.number, group.low, group.high
+            )?;
+        }
+        Command::Over(range) => {
+            let group = client.group().cloned().ok_or("No group selected")?;
+            let (low, high) = parse_range(range, &group)?;
+            let overviews = client.overview(low, high)?;
+            match opt.format {
+                Format::Tsv => {
+                    for overview in overviews.iter() {
+                        write_tsv(&mut out, overview)?;
+                    }
+                }
+                Format::Json => {
+                    writeln!(out, "[")?;
+                    for (i, overview) in overviews.iter().enumerate() {
+                        if i > 0 {
+                            writeln!(out, ",")?;
+                        }
+                        write_json(&mut out, overview)?;
+                    }
+                    writeln!(out, "\n]")?;
+                }
+            }
+        }
+        Command::Article(id) => {
+            let command = match id {
+                Id::Number(n) => cmd::Article::Number(*n),
+                Id::MessageId(id) => cmd::Article::MessageId(id.clone()),
+            };
+            let resp = client.command(command)?.fail_unless(Kind::Article)?;
+            write_data_blocks(&mut out, &resp)?;
+        }
+        Command::Head(id) => {
+            let command = match id {
+                Id::Number(n) => cmd::Head::Number(*n),
+                Id::MessageId(id) => cmd::Head::MessageId(id.clone()),
+            };
+            let resp = client.command(command)?.fail_unless(Kind::Head)?;
+            write_data_blocks(&mut out, &resp)?;
+        }
+        Command::Body(id) => {
+            let command = match id {
+                Id::Number(n) => cmd::Body::Number(*n),
+                Id::MessageId(id) => cmd::Body::MessageId(id.clone()),
+            };
+            let resp = client.command(command)?.fail_unless(Kind::Body)?;
+            write_data_blocks(&mut out, &resp)?;
+        }
+        Command::Stat(id) => {
+            let command = match id {
+                Id::Number(n) => cmd::Stat::Number(*n),
+                Id::MessageId(id) => cmd::Stat::MessageId(id.clone()),
+            };
+            let stat = client.stat(command)?.ok_or("No such article")?;
+            writeln!(out, "{}\t{}", stat.number, stat.message_id)?;
+        }
+        Command::Post(path) => {
+            let mut article = Vec::new();
+            if path == "-" {
+                io::stdin().read_to_end(&mut article)?;
+            } else {
+                article = fs::read(path)?;
+            }
+            let resp = client.post(article)?;
+            writeln!(out, "{}", resp.first_line_to_utf8_lossy().trim_end())?;
+        }
+    }
+    out.flush()?;
+    drop(out);
+
+    client.close()?;
+    Ok(())
+}
+
+fn main() {
+    let level = env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Warn);
+    log::set_logger(&StderrLogger).expect("Logger already set");
+    log::set_max_level(level);
+
+    let opt = Opt::from_args().unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    if let Err(e) = run(opt) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use brokaw::raw::{Record, Transcript};
+
+    /// The library's transcript fixtures, which are not available outside of its own tests
+    mod fixtures {
+        include!("../raw/transcript/fixtures.rs");
+    }
+
+    use fixtures::{exchange, reader_session};
+
+    #[test]
+    fn test_netrc_lookup() {
+        let netrc = "\
+machine news.example.com login alice password secret
+macdef init
+machine news.example.com login mallory password nope
+
+machine other.example.com
+    login bob
+    password hunter2
+default login anonymous password guest
+";
+        let creds = |login: &str, password: &str| Credentials {
+            login: Some(login.to_string()),
+            password: Some(password.to_string()),
+        };
+
+        assert_eq!(
+            netrc_lookup(netrc, "news.example.com"),
+            Some(creds("alice", "secret"))
+        );
+        assert_eq!(
+            netrc_lookup(netrc, "other.example.com"),
+            Some(creds("bob", "hunter2"))
+        );
+        assert_eq!(
+            netrc_lookup(netrc, "unknown.example.com"),
+            Some(creds("anonymous", "guest"))
+        );
+        assert_eq!(netrc_lookup("machine a login b", "c"), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let group = Group {
+            number: 10,
+            low: 5,
+            high: 14,
+            name: "misc.test".to_string(),
+        };
+        assert_eq!(parse_range("6-8", &group), Ok((6, 8)));
+        assert_eq!(parse_range("6-", &group), Ok((6, 14)));
+        assert_eq!(parse_range("7", &group), Ok((7, 7)));
+        assert!(parse_range("x-8", &group).is_err());
+    }
+
+    #[test]
+    fn test_host_port() {
+        assert_eq!(
+            host_port("news.example.com", true),
+            Ok(("news.example.com", 563))
+        );
+        assert_eq!(
+            host_port("news.example.com", false),
+            Ok(("news.example.com", 119))
+        );
+        assert_eq!(
+            host_port("news.example.com:8119", true),
+            Ok(("news.example.com", 8119))
+        );
+        assert_eq!(host_port("::1", false), Ok(("::1", 119)));
+        assert_eq!(host_port("2001:db8::1", true), Ok(("2001:db8::1", 563)));
+        assert_eq!(host_port("[2001:db8::1]", true), Ok(("2001:db8::1", 563)));
+        assert_eq!(host_port("[::1]:8119", true), Ok(("::1", 8119)));
+        assert!(host_port("news.example.com:nntp", true).is_err());
+        assert!(host_port("[::1", true).is_err());
+        assert!(host_port("[::1]8119", true).is_err());
+    }
+
+    #[test]
+    fn test_write_json() {
+        let overview = Overview {
+            number: 1,
+            subject: "a\"b\\c\td\u{1}".to_string(),
+            from: String::new(),
+            date: String::new(),
+            message_id: "<1@example.com>".to_string(),
+            references: String::new(),
+            bytes: Some(10),
+            lines: None,
+            extra: vec!["Xref: example misc.test:1".to_string()],
+        };
+        let mut buf = Vec::new();
+        write_json(&mut buf, &overview).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"{"number":1,"subject":"a\"b\\c\td\u0001","from":"","date":"","message_id":"<1@example.com>","references":"","bytes":10,"lines":null,"extra":["Xref: example misc.test:1"]}"#
+        );
+    }
+
+    #[test]
+    fn test_compress() {
+        let compressed = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/xover_resp_xfeature_compress"
+        ));
+        // The fixture contains two responses, only the first is used
+        let second = compressed
+            .windows(4)
+            .skip(1)
+            .position(|w| w == b"224 ")
+            .unwrap();
+        let compressed = &compressed[..second + 1];
+
+        let mut transcript = reader_session();
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 1 461197 461197 misc.test\r\n",
+        );
+        exchange(
+            &mut transcript,
+            "XFEATURE COMPRESS GZIP TERMINATOR",
+            "290 feature enabled\r\n",
+        );
+        exchange(&mut transcript, "XOVER 461197-461197", compressed);
+
+        let opt = Opt {
+            server: "news.example.com".to_string(),
+            tls: false,
+            group: Some("misc.test".to_string()),
+            netrc: None,
+            compress: true,
+            xzver: false,
+            format: Format::Tsv,
+            timeout: 30,
+            command: Command::Over("461197".to_string()),
+        };
+        let config = client_config(&opt, "news.example.com").unwrap();
+        let mut client = init(config.replay(transcript).unwrap(), &opt).unwrap();
+
+        let overviews = client.overview(461197, 461197).unwrap();
+        assert_eq!(overviews.len(), 1);
+        let overview = overviews.iter().next().unwrap();
+        assert_eq!(overview.message_id, "<ht23eb.3ta.ln@gadfly.meow.org>");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::client::NntpClient;
+use crate::error::Result;
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// The direction a [`Cursor`] walks through a group
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// Walk towards higher article numbers using `NEXT`
+    Forward,
+    /// Walk towards lower article numbers using `LAST`
+    Backward,
+}
+
+/// An iterator that walks the selected group one article at a time
+///
+/// Created by [`NntpClient::cursor`].
+///
+/// 1. The first item is the client's current article (retrieved with `STAT`)
+/// 2. Subsequent items are retrieved with `NEXT` or `LAST` depending on the [`Direction`]
+/// 3. The iterator ends cleanly when the server reports there is no current article (420),
+///    no next article (421), or no previous article (422)
+/// 4. Any other failure is yielded as an error after which the iterator is exhausted
+///
+/// The client's [`current_article`](NntpClient::current_article) is kept up to date as the
+/// cursor moves, so a walk can be interrupted and resumed with a new cursor.
+///
+/// # Example
+///
+/// ```no_run
+/// use brokaw::client::{ClientConfig, Direction};
+///
+/// fn main() -> brokaw::error::Result<()> {
+///     let mut client = ClientConfig::default()
+///         .group(Some("misc.test"))
+///         .connect(("news.example.com", 119))?;
+///
+///     for stat in client.cursor(Direction::Forward) {
+///         let stat = stat?;
+///         println!("{} {}", stat.number, stat.message_id);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    client: &'a mut NntpClient,
+    direction: Direction,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(client: &'a mut NntpClient, direction: Direction) -> Self {
+        Self {
+            client,
+            direction,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// The direction the cursor is walking
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    fn step(&mut self) -> Result<Option<Stat>> {
+        if !self.started {
+            self.started = true;
+            return self.client.stat(cmd::Stat::Current);
+        }
+
+        match self.direction {
+            Direction::Forward => self.client.next_article(),
+            Direction::Backward => self.client.last_article(),
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = Result<Stat>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.step().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::ClientConfig;
+    use crate::raw::transcript::fixtures::{exchange, reader_session};
+
+    fn client(exchanges: &[(&str, &str)]) -> NntpClient {
+        let mut transcript = reader_session();
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 3 1 3 misc.test\r\n",
+        );
+        for (command, response) in exchanges {
+            exchange(&mut transcript, command, response);
+        }
+        ClientConfig::default()
+            .group(Some("misc.test"))
+            .replay(transcript)
+            .unwrap()
+    }
+
+    fn numbers(cursor: Cursor<'_>) -> Vec<Result<ArticleNumber>> {
+        cursor.map(|stat| stat.map(|stat| stat.number)).collect()
+    }
+
+    #[test]
+    fn test_forward() {
+        let mut client = client(&[
+            ("STAT", "223 1 <1@example.com>\r\n"),
+            ("NEXT", "223 2 <2@example.com>\r\n"),
+            ("NEXT", "223 3 <3@example.com>\r\n"),
+            ("NEXT", "421 No next article\r\n"),
+        ]);
+
+        let cursor = client.cursor(Direction::Forward);
+        assert_eq!(cursor.direction(), Direction::Forward);
+        let numbers = numbers(cursor)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        assert_eq!(client.current_article(), Some(3));
+    }
+
+    #[test]
+    fn test_backward() {
+        let mut client = client(&[
+            ("STAT 2", "223 2 <2@example.com>\r\n"),
+            ("STAT", "223 2 <2@example.com>\r\n"),
+            ("LAST", "223 1 <1@example.com>\r\n"),
+            ("LAST", "422 No previous article\r\n"),
+        ]);
+        client.stat(cmd::Stat::Number(2)).unwrap();
+
+        let numbers = numbers(client.cursor(Direction::Backward))
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(numbers, vec![2, 1]);
+        assert_eq!(client.current_article(), Some(1));
+    }
+
+    #[test]
+    fn test_no_current_article() {
+        let mut client = client(&[("STAT", "420 No current article\r\n")]);
+        assert!(client.cursor(Direction::Forward).next().is_none());
+    }
+
+    #[test]
+    fn test_error_ends_cursor() {
+        let mut client = client(&[
+            ("STAT", "223 1 <1@example.com>\r\n"),
+            ("NEXT", "412 No newsgroup selected\r\n"),
+        ]);
+
+        let mut cursor = client.cursor(Direction::Forward);
+        assert_eq!(cursor.next().unwrap().unwrap().number, 1);
+        assert!(cursor.next().unwrap().is_err());
+        assert!(cursor.next().is_none());
+    }
+}
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use log::*;
+
+use crate::client::{ClientConfig, NntpClient};
+use crate::error::{Error, ErrorClass, Result};
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// Configuration for a [`FailoverClient`]
+#[derive(Clone, Debug)]
+pub struct FailoverConfig {
+    servers: Vec<ServerConfig>,
+    max_failures: u32,
+    down_time: Duration,
+}
+
+#[derive(Clone, Debug)]
+struct ServerConfig {
+    addr: String,
+    config: ClientConfig,
+    priority: u32,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            max_failures: 3,
+            down_time: Duration::from_secs(60),
+        }
+    }
+}
+
+impl FailoverConfig {
+    /// Add a server
+    ///
+    /// `addr` is a `host:port` pair. Servers are tried in order of ascending `priority`,
+    /// servers with the same priority are tried in the order they were added.
+    pub fn server(
+        &mut self,
+        addr: impl Into<String>,
+        config: ClientConfig,
+        priority: u32,
+    ) -> &mut Self {
+        self.servers.push(ServerConfig {
+            addr: addr.into(),
+            config,
+            priority,
+        });
+        self
+    }
+
+    /// The number of consecutive connection failures before a server is marked down
+    ///
+    /// Defaults to 3
+    pub fn max_failures(&mut self, n: u32) -> &mut Self {
+        self.max_failures = n.max(1);
+        self
+    }
+
+    /// How long a server is skipped after it is marked down
+    ///
+    /// Defaults to 60 seconds
+    pub fn down_time(&mut self, dur: Duration) -> &mut Self {
+        self.down_time = dur;
+        self
+    }
+
+    /// Create a client from the config
+    ///
+    /// Connections are opened lazily, the first time a server is needed.
+    pub fn build(&self) -> FailoverClient {
+        let mut servers = self
+            .servers
+            .iter()
+            .cloned()
+            .map(|config| Server {
+                config,
+                client: None,
+                connected_before: false,
+                failures: 0,
+                down_until: None,
+            })
+            .collect::<Vec<_>>();
+        // n.b. the sort is stable so servers with the same priority keep their order
+        servers.sort_by_key(|s| s.config.priority);
+
+        FailoverClient {
+            servers,
+            locations: HashMap::new(),
+            max_failures: self.max_failures,
+            down_time: self.down_time,
+        }
+    }
+}
+
+/// A client that spreads requests across several servers
+///
+/// Articles requested by message-id are fetched from the highest priority server that has
+/// them: if a server responds with "no such article" (codes 423/430) the next server is tried.
+/// This makes it possible to backfill articles missing from a primary provider from a backup
+/// (e.g. a block account).
+///
+/// * Which servers do (and do not) have an article is remembered for the life of the client, so
+///   repeated requests for the same article go straight to the right server.
+/// * Servers with repeated connection failures are marked down for a while, see
+///   [`FailoverConfig::max_failures`] and [`FailoverConfig::down_time`].
+/// * Article numbers are only meaningful relative to a single server, so requests by number
+///   (or for the current article) are sent to the highest priority server that is up and do not
+///   fail over.
+///
+/// # Example
+///
+/// ```no_run
+/// use brokaw::client::{ClientConfig, FailoverConfig};
+/// use brokaw::types::command::Body;
+///
+/// fn main() -> brokaw::error::Result<()> {
+///     let mut client = FailoverConfig::default()
+///         .server("news.primary.example:119", ClientConfig::default(), 0)
+///         .server("news.backup.example:119", ClientConfig::default(), 10)
+///         .build();
+///
+///     let body = client.body(Body::MessageId("<part1of10@example.com>".to_string()))?;
+///     println!("{} bytes", body.body().len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FailoverClient {
+    servers: Vec<Server>,
+    /// Which servers have (or lack) an article, keyed by message-id
+    locations: HashMap<String, Location>,
+    max_failures: u32,
+    down_time: Duration,
+}
+
+#[derive(Debug)]
+struct Server {
+    config: ServerConfig,
+    client: Option<NntpClient>,
+    connected_before: bool,
+    failures: u32,
+    down_until: Option<Instant>,
+}
+
+impl Server {
+    fn is_up(&self) -> bool {
+        self.down_until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    fn client(&mut self) -> Result<&mut NntpClient> {
+        if self.client.is_none() {
+            debug!("Connecting to {}", self.config.addr);
+            let client = self.config.config.connect(self.config.addr.as_str())?;
+            if self.connected_before {
+                client.metrics().record_reconnect();
+            }
+            self.connected_before = true;
+            self.client = Some(client);
+        }
+
+        Ok(self.client.as_mut().unwrap())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Location {
+    found: Option<usize>,
+    missing: Vec<usize>,
+}
+
+/// The state of a server in a [`FailoverClient`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerStatus {
+    /// The address of the server
+    pub addr: String,
+    /// The priority of the server, lower is tried first
+    pub priority: u32,
+    /// False if the server has been marked down
+    pub up: bool,
+    /// The number of consecutive connection failures
+    pub failures: u32,
+}
+
+impl FailoverClient {
+    /// Retrieve an article, falling back to other servers if it is requested by message-id
+    pub fn article(&mut self, article: cmd::Article) -> Result<BinaryArticle> {
+        let message_id = match &article {
+            cmd::Article::MessageId(id) => Some(id.clone()),
+            _ => None,
+        };
+        self.fetch(message_id, |c| c.article(article.clone()), |_| false)
+    }
+
+    /// Retrieve the body of an article, falling back to other servers if it is requested by
+    /// message-id
+    pub fn body(&mut self, body: cmd::Body) -> Result<Body> {
+        let message_id = match &body {
+            cmd::Body::MessageId(id) => Some(id.clone()),
+            _ => None,
+        };
+        self.fetch(message_id, |c| c.body(body.clone()), |_| false)
+    }
+
+    /// Retrieve the headers of an article, falling back to other servers if it is requested by
+    /// message-id
+    pub fn head(&mut self, head: cmd::Head) -> Result<Head> {
+        let message_id = match &head {
+            cmd::Head::MessageId(id) => Some(id.clone()),
+            _ => None,
+        };
+        self.fetch(message_id, |c| c.head(head.clone()), |_| false)
+    }
+
+    /// Retrieve the status of an article, falling back to other servers if it is requested by
+    /// message-id
+    ///
+    /// Returns `None` if no server has the article
+    pub fn stat(&mut self, stat: cmd::Stat) -> Result<Option<Stat>> {
+        let message_id = match &stat {
+            cmd::Stat::MessageId(id) => Some(id.clone()),
+            _ => None,
+        };
+        match self.fetch(message_id, |c| c.stat(stat.clone()), Option::is_none) {
+            Err(e) if e.class() == ErrorClass::NoSuchArticle => Ok(None),
+            result => result,
+        }
+    }
+
+    /// The status of each server, in the order they are tried
+    pub fn servers(&self) -> Vec<ServerStatus> {
+        self.servers
+            .iter()
+            .map(|s| ServerStatus {
+                addr: s.config.addr.clone(),
+                priority: s.config.priority,
+                up: s.is_up(),
+                failures: s.failures,
+            })
+            .collect()
+    }
+
+    /// The client for the highest priority server that is up, connecting if necessary
+    ///
+    /// Requests made through the returned client do not fail over.
+    pub fn primary(&mut self) -> Result<&mut NntpClient> {
+        let i = self
+            .servers
+            .iter()
+            .position(Server::is_up)
+            .ok_or_else(all_servers_down)?;
+
+        match self.servers[i].client() {
+            Ok(_) => Ok(self.servers[i].client.as_mut().unwrap()),
+            Err(e) => {
+                self.record_failure(i, &e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Run `f` against each server in turn until one has the article
+    ///
+    /// A server does not have the article if `f` fails with [`ErrorClass::NoSuchArticle`] or
+    /// `is_missing` returns true. If no server has it, the last result is returned.
+    fn fetch<T>(
+        &mut self,
+        message_id: Option<String>,
+        mut f: impl FnMut(&mut NntpClient) -> Result<T>,
+        is_missing: impl Fn(&T) -> bool,
+    ) -> Result<T> {
+        let order = self.order(message_id.as_deref());
+        let mut last = None;
+
+        for i in order {
+            let result = self.servers[i].client().and_then(&mut f);
+
+            match result {
+                Ok(t) if message_id.is_some() && is_missing(&t) => {
+                    self.record_success(i);
+                    self.record_missing(message_id.as_deref(), i);
+                    last = Some(Ok(t));
+                }
+                Ok(t) => {
+                    self.record_success(i);
+                    if let Some(id) = message_id.as_ref() {
+                        self.locations.entry(id.clone()).or_default().found = Some(i);
+                    }
+                    return Ok(t);
+                }
+                Err(e) if message_id.is_some() && e.class() == ErrorClass::NoSuchArticle => {
+                    self.record_success(i);
+                    self.record_missing(message_id.as_deref(), i);
+                    last = Some(Err(e));
+                }
+                Err(e)
+                    if matches!(
+                        e.class(),
+                        ErrorClass::Connection
+                            | ErrorClass::Unavailable
+                            | ErrorClass::PermanentlyUnavailable
+                    ) =>
+                {
+                    self.record_failure(i, &e);
+                    last = Some(Err(e));
+                }
+                Err(e) => return Err(e),
+            }
+
+            // Requests by number only make sense on one server
+            if message_id.is_none() {
+                break;
+            }
+        }
+
+        match (last, message_id) {
+            (Some(result), _) => result,
+            // Every server that is up is known not to have the article
+            (None, Some(id)) if self.locations.get(&id).is_some_and(|l| l.found.is_none()) => {
+                Err(Error::failure(RawResponse::new(
+                    Kind::NoArticleWithMessageId,
+                    "No such article",
+                )))
+            }
+            (None, _) => Err(all_servers_down()),
+        }
+    }
+
+    /// The indices of the servers to try, in order
+    fn order(&self, message_id: Option<&str>) -> Vec<usize> {
+        let location = message_id.and_then(|id| self.locations.get(id));
+        let found = location.and_then(|l| l.found);
+
+        let mut order = found.into_iter().collect::<Vec<_>>();
+        order.extend(
+            (0..self.servers.len())
+                .filter(|i| Some(*i) != found)
+                .filter(|i| location.is_none_or(|l| !l.missing.contains(i))),
+        );
+        order.retain(|i| self.servers[*i].is_up());
+        order
+    }
+
+    fn record_missing(&mut self, message_id: Option<&str>, i: usize) {
+        if let Some(id) = message_id {
+            debug!("{} does not have {}", self.servers[i].config.addr, id);
+            let location = self.locations.entry(id.to_string()).or_default();
+            location.missing.push(i);
+            if location.found == Some(i) {
+                location.found = None;
+            }
+        }
+    }
+
+    fn record_success(&mut self, i: usize) {
+        self.servers[i].failures = 0;
+    }
+
+    fn record_failure(&mut self, i: usize, e: &Error) {
+        let max_failures = self.max_failures;
+        let down_time = self.down_time;
+        let server = &mut self.servers[i];
+
+        warn!("Request to {} failed -- {}", server.config.addr, e);
+        // The connection may be in an unknown state, reconnect next time
+        server.client = None;
+        server.failures += 1;
+        if server.failures >= max_failures {
+            warn!(
+                "Marking {} down for {:?} after {} failures",
+                server.config.addr, down_time, server.failures
+            );
+            server.down_until = Some(Instant::now() + down_time);
+            server.failures = 0;
+        }
+    }
+}
+
+fn all_servers_down() -> Error {
+    Error::Connection(crate::raw::error::Error::Io(std::io::Error::new(
+        std::io::ErrorKind::NotConnected,
+        "All servers are down",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use super::*;
+    use crate::types::command::{AnyCommand, Decode};
+
+    /// A stand-in server that has articles with the given message-ids
+    fn server(ids: &'static [&'static str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                thread::spawn(move || serve(stream.unwrap(), ids));
+            }
+        });
+
+        addr
+    }
+
+    fn serve(stream: TcpStream, ids: &[&str]) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+
+        writer.write_all(b"200 stand-in ready\r\n").unwrap();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).unwrap() == 0 {
+                return;
+            }
+
+            let resp = match AnyCommand::decode(&line).unwrap() {
+                AnyCommand::Capabilities(_) => RawResponse::with_data_blocks(
+                    Kind::Capabilities,
+                    "Capability list:",
+                    "VERSION 2\r\nREADER\r\n",
+                ),
+                AnyCommand::Stat(cmd::Stat::MessageId(id)) if ids.contains(&id.as_str()) => {
+                    RawResponse::new(Kind::ArticleExists, format!("0 {}", id))
+                }
+                AnyCommand::Stat(_) => RawResponse::new(Kind::NoArticleWithMessageId, "Nope"),
+                AnyCommand::Body(cmd::Body::MessageId(id)) if ids.contains(&id.as_str()) => {
+                    RawResponse::with_data_blocks(Kind::Body, format!("0 {}", id), "body\r\n")
+                }
+                AnyCommand::Body(_) => RawResponse::new(Kind::NoArticleWithMessageId, "Nope"),
+                _ => RawResponse::new(Kind::UnknownCommand, "What?"),
+            };
+            writer.write_all(&resp.to_wire()).unwrap();
+        }
+    }
+
+    /// A stand-in server that closes every connection before sending a greeting
+    fn dead_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                drop(stream);
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_backfill() {
+        let mut client = FailoverConfig::default()
+            .server(
+                server(&["<backup@example.com>"]),
+                ClientConfig::default(),
+                10,
+            )
+            .server(server(&["<both@example.com>"]), ClientConfig::default(), 0)
+            .build();
+        let id = |s: &str| s.to_string();
+
+        let body = client.body(cmd::Body::MessageId(id("<backup@example.com>")));
+        assert_eq!(body.unwrap().message_id, "<backup@example.com>");
+        assert_eq!(client.locations["<backup@example.com>"].found, Some(1));
+        assert_eq!(client.locations["<backup@example.com>"].missing, vec![0]);
+        assert_eq!(client.order(Some("<backup@example.com>")), vec![1]);
+
+        let stat = client.stat(cmd::Stat::MessageId(id("<both@example.com>")));
+        assert!(stat.unwrap().is_some());
+
+        let stat = client.stat(cmd::Stat::MessageId(id("<nowhere@example.com>")));
+        assert!(stat.unwrap().is_none());
+
+        let err = client
+            .body(cmd::Body::MessageId(id("<nowhere@example.com>")))
+            .unwrap_err();
+        assert_eq!(err.class(), ErrorClass::NoSuchArticle);
+    }
+
+    #[test]
+    fn test_mark_down() {
+        let mut client = FailoverConfig::default()
+            .server(dead_server(), ClientConfig::default(), 0)
+            .server(
+                server(&["<a@example.com>", "<b@example.com>"]),
+                ClientConfig::default(),
+                1,
+            )
+            .max_failures(2)
+            .build();
+
+        for id in &["<a@example.com>", "<b@example.com>"] {
+            let stat = client.stat(cmd::Stat::MessageId(id.to_string()));
+            assert!(stat.unwrap().is_some());
+        }
+
+        let servers = client.servers();
+        assert!(!servers[0].up);
+        assert!(servers[1].up);
+        assert_eq!(client.order(None), vec![1]);
+    }
+}
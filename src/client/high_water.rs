@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::atomic;
+use crate::error::Result;
+use crate::types::prelude::*;
+
+/// Persistent storage for per-group high water marks
+///
+/// A high water mark is the highest article number that has been processed in a group on a
+/// particular server. Marks are keyed by both the server and group as article numbers are only
+/// meaningful relative to the server that assigned them.
+///
+/// Brokaw provides a [`FileStore`] and a [`MemoryStore`], but any storage backend can be used by
+/// implementing this trait.
+pub trait HighWaterMarkStore {
+    /// Get the high water mark for a group
+    fn get(&self, server: &str, group: &str) -> io::Result<Option<ArticleNumber>>;
+
+    /// Record the high water mark for a group
+    fn set(&mut self, server: &str, group: &str, mark: ArticleNumber) -> io::Result<()>;
+}
+
+/// A [`HighWaterMarkStore`] that only lives as long as the process
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    marks: BTreeMap<(String, String), ArticleNumber>,
+}
+
+impl MemoryStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl HighWaterMarkStore for MemoryStore {
+    fn get(&self, server: &str, group: &str) -> io::Result<Option<ArticleNumber>> {
+        Ok(self
+            .marks
+            .get(&(server.to_string(), group.to_string()))
+            .copied())
+    }
+
+    fn set(&mut self, server: &str, group: &str, mark: ArticleNumber) -> io::Result<()> {
+        self.marks
+            .insert((server.to_string(), group.to_string()), mark);
+        Ok(())
+    }
+}
+
+/// A [`HighWaterMarkStore`] backed by a plain text file
+///
+/// The file contains one `server<TAB>group<TAB>mark` entry per line. Every call to
+/// [`set`](HighWaterMarkStore::set) rewrites the file by writing to a temporary file in the same
+/// directory and renaming it over the original, so the file is never left half-written.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    inner: MemoryStore,
+}
+
+impl FileStore {
+    /// Open a store, loading any existing marks
+    ///
+    /// The file will be created on the first call to [`set`](HighWaterMarkStore::set)
+    /// if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut inner = MemoryStore::new();
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for (i, line) in contents.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let (server, group, mark) = parse_line(line).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Invalid high water mark on line {}", i + 1),
+                        )
+                    })?;
+                    inner.set(server, group, mark)?;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("High water mark file {} does not exist", path.display())
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self { path, inner })
+    }
+
+    /// The path to the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        atomic::write_file(&self.path, |w| {
+            for ((server, group), mark) in &self.inner.marks {
+                writeln!(w, "{}\t{}\t{}", server, group, mark)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn parse_line(line: &str) -> Option<(&str, &str, ArticleNumber)> {
+    let mut fields = line.split('\t');
+    let server = fields.next()?;
+    let group = fields.next()?;
+    let mark = fields.next()?.trim().parse().ok()?;
+    Some((server, group, mark))
+}
+
+impl HighWaterMarkStore for FileStore {
+    fn get(&self, server: &str, group: &str) -> io::Result<Option<ArticleNumber>> {
+        self.inner.get(server, group)
+    }
+
+    fn set(&mut self, server: &str, group: &str, mark: ArticleNumber) -> io::Result<()> {
+        self.inner.set(server, group, mark)?;
+        self.persist()
+    }
+}
+
+/// The articles that have arrived in a group since the last recorded high water mark
+///
+/// Created by [`NntpClient::new_articles`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewArticles {
+    /// The server the mark belongs to
+    pub server: String,
+    /// The group as returned by `GROUP`
+    pub group: Group,
+    /// The previously recorded high water mark, if any
+    pub previous: Option<ArticleNumber>,
+    /// The range of articles that have not been seen, or `None` if there is nothing new
+    pub range: Option<RangeInclusive<ArticleNumber>>,
+    /// True if the server's high water mark is lower than the recorded one
+    ///
+    /// This happens when a server renumbers or resets a group. When this occurs the
+    /// recorded mark is meaningless and the entire group is considered new.
+    pub renumbered: bool,
+}
+
+impl NewArticles {
+    pub(crate) fn new(server: &str, group: Group, previous: Option<ArticleNumber>) -> Self {
+        let renumbered = matches!(previous, Some(mark) if group.high < mark);
+
+        let low = match previous {
+            Some(mark) if !renumbered => group.low.max(mark.saturating_add(1)),
+            _ => group.low,
+        };
+
+        let range = if group.number > 0 && low <= group.high {
+            Some(low..=group.high)
+        } else {
+            None
+        };
+
+        Self {
+            server: server.to_string(),
+            group,
+            previous,
+            range,
+            renumbered,
+        }
+    }
+
+    /// Record the group's current high water mark in the store
+    ///
+    /// Call this once the articles in [`range`](Self::range) have been processed.
+    pub fn commit(&self, store: &mut impl HighWaterMarkStore) -> Result<()> {
+        store.set(&self.server, &self.group.name, self.group.high)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(number: ArticleNumber, low: ArticleNumber, high: ArticleNumber) -> Group {
+        Group {
+            number,
+            low,
+            high,
+            name: "misc.test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_new_articles() {
+        let new = NewArticles::new("srv", group(100, 1, 100), None);
+        assert_eq!(new.range, Some(1..=100));
+
+        let new = NewArticles::new("srv", group(100, 1, 100), Some(90));
+        assert_eq!(new.range, Some(91..=100));
+        assert!(!new.renumbered);
+
+        let new = NewArticles::new("srv", group(100, 1, 100), Some(100));
+        assert_eq!(new.range, None);
+
+        // Articles below the low water mark have expired
+        let new = NewArticles::new("srv", group(10, 50, 60), Some(20));
+        assert_eq!(new.range, Some(50..=60));
+
+        let new = NewArticles::new("srv", group(10, 1, 10), Some(500));
+        assert_eq!(new.range, Some(1..=10));
+        assert!(new.renumbered);
+
+        let new = NewArticles::new("srv", group(0, 11, 10), None);
+        assert_eq!(new.range, None);
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("brokaw-marks-{}.tsv", std::process::id()));
+
+        let mut store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("srv", "misc.test").unwrap(), None);
+        store.set("srv", "misc.test", 42).unwrap();
+        store.set("other", "misc.test", 7).unwrap();
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("srv", "misc.test").unwrap(), Some(42));
+        assert_eq!(store.get("other", "misc.test").unwrap(), Some(7));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,994 @@
+use std::convert::TryFrom;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+
+use log::*;
+
+use crate::error::{Error, Result};
+
+use crate::raw::connection::{ConnectionConfig, NntpConnection};
+use crate::raw::interceptor::Interceptor;
+use crate::raw::metrics::Metrics;
+use crate::raw::response::{RawResponse, RawResponseRef};
+use crate::raw::transcript::Transcript;
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// Iterators that walk a newsgroup with `NEXT`/`LAST`
+mod cursor;
+
+/// Requests spread across several servers with failover
+mod failover;
+
+/// Whole-group overview synchronization
+mod overview;
+
+/// Per-group high water marks for incremental fetching
+mod high_water;
+
+/// Article transfer between peers with `IHAVE` and streaming feeds
+mod transit;
+
+pub use cursor::{Cursor, Direction};
+pub use failover::{FailoverClient, FailoverConfig, ServerStatus};
+pub use high_water::{FileStore, HighWaterMarkStore, MemoryStore, NewArticles};
+pub use overview::{OverviewSync, OverviewSyncConfig};
+pub use transit::{StreamCommand, StreamReply, StreamingFeed, TransferStatus};
+
+/// A client that returns typed responses and provides state management
+///
+/// `NntpClient` is built on top of [`NntpConnection`] and offers several niceties:
+///
+/// 1. Responses from the server are typed and semantically validated
+/// 2. Management of the connection state (e.g. current group, known capabilities)
+///
+/// In exchange for these niceties, `NntpClient` does not provide the low-allocation guarantees
+/// that `NntpConnection` does. If you are really concerned about memory management,
+/// you may want to use the [`NntpConnection`].
+#[derive(Debug)]
+pub struct NntpClient {
+    conn: NntpConnection,
+    config: ClientConfig,
+    capabilities: Capabilities,
+    group: Option<Group>,
+    current_article: Option<ArticleNumber>,
+}
+
+impl NntpClient {
+    /// Get the raw [`NntpConnection`] for the client
+    ///
+    /// # Usage
+    ///
+    /// NNTP is a **STATEFUL PROTOCOL** and misusing the underlying connection may mess up the
+    /// state in the client that owns the connection.
+    ///
+    /// For example, manually sending a `GROUP`  command would leave change the group of
+    /// the connection but will not update the NntpClient's internal record.
+    ///
+    /// Caveat emptor!
+    pub fn conn(&mut self) -> &mut NntpConnection {
+        &mut self.conn
+    }
+
+    /// Send a command
+    ///
+    /// This is useful if you want to use a command you have implemented or one that is not
+    /// provided by a client method
+    ///
+    /// # Example
+    ///
+    /// Say we have a server that uses mode switching for whatever reason. Brokaw implements
+    /// a [`ModeReader`](cmd::ModeReader) command but it does not provide a return type.
+    /// We implement one in the following example
+    /// <details><summary>MOTD</summary>
+    ///
+    /// ```no_run
+    /// use std::convert::{TryFrom, TryInto};
+    /// use brokaw::types::prelude::*;
+    /// use brokaw::types::command as cmd;
+    ///
+    /// struct Motd {
+    ///     posting_allowed: bool,
+    ///     motd: String,
+    /// }
+    ///
+    /// impl TryFrom<RawResponse> for Motd {
+    ///     type Error = String;
+    ///
+    ///     fn try_from(resp: RawResponse) -> Result<Self, Self::Error> {
+    ///         let posting_allowed = match resp.code() {
+    ///             ResponseCode::Known(Kind::PostingAllowed) => true,
+    ///             ResponseCode::Known(Kind::PostingNotPermitted) => false,
+    ///             ResponseCode::Known(Kind::PermanentlyUnavailable) => {
+    ///                 return Err("Server is gone forever".to_string());
+    ///             }
+    ///             ResponseCode::Known(Kind::TemporarilyUnavailable) => {
+    ///                 return Err("Server is down?".to_string());
+    ///             }
+    ///             code => return Err(format!("Unexpected {:?}", code))
+    ///         };
+    ///         let mut motd = String::from_utf8_lossy(resp.first_line_without_code())
+    ///             .to_string();
+    ///
+    ///         Ok(Motd { posting_allowed, motd })
+    ///     }
+    /// }
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     use brokaw::client::{NntpClient, ClientConfig};
+    ///     let mut client = ClientConfig::default()
+    ///         .connect(("news.modeswitching.notreal", 119))?;
+    ///
+    ///     let resp: Motd = client.command(cmd::ModeReader)?.try_into()?;
+    ///     println!("Motd: {}", resp.motd);
+    ///     Ok(())
+    /// }
+    /// ```
+    /// </details>
+    pub fn command(&mut self, c: impl NntpCommand) -> Result<RawResponse> {
+        let resp = self.exchange(&c, None)?;
+        Ok(resp)
+    }
+
+    /// Send a command and read the response, re-authenticating if required
+    ///
+    /// If the server responds with `480 Authentication required` and credentials are configured,
+    /// the client authenticates, restores the selected group and current article, and retries
+    /// the command once.
+    fn exchange<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        is_multiline: Option<bool>,
+    ) -> Result<RawResponse> {
+        self.conn.send(command)?;
+        let resp = self.conn.read_response(is_multiline)?;
+
+        match (resp.code(), self.config.authinfo.as_ref()) {
+            (ResponseCode::Known(Kind::AuthenticationRequired), Some(_)) => {
+                debug!("Server requires authentication, re-authenticating...");
+                self.reauthenticate()?;
+                self.conn.send(command)?;
+                Ok(self.conn.read_response(is_multiline)?)
+            }
+            _ => Ok(resp),
+        }
+    }
+
+    /// Send a command and parse the response without copying it out of the connection
+    ///
+    /// This re-authenticates like [`exchange`](Self::exchange), `parse` is only called with the
+    /// final response.
+    fn exchange_borrowed<C: NntpCommand, T>(
+        &mut self,
+        command: &C,
+        parse: impl FnOnce(RawResponseRef<'_>) -> Result<T>,
+    ) -> Result<T> {
+        let mut reauthenticated = false;
+        loop {
+            self.conn.send(command)?;
+            let resp = self.conn.read_response_borrowed(None)?;
+
+            match (resp.code(), self.config.authinfo.as_ref()) {
+                (ResponseCode::Known(Kind::AuthenticationRequired), Some(_))
+                    if !reauthenticated => {}
+                _ => return parse(resp),
+            }
+
+            debug!("Server requires authentication, re-authenticating...");
+            self.reauthenticate()?;
+            reauthenticated = true;
+        }
+    }
+
+    /// Authenticate and restore the session state
+    fn reauthenticate(&mut self) -> Result<()> {
+        if let Some((username, password)) = &self.config.authinfo {
+            authenticate(&mut self.conn, username, password)?;
+        }
+
+        // Capabilities may change after authenticating (RFC 4643 Section 2.3.1)
+        self.capabilities = get_capabilities(&mut self.conn)?;
+
+        if let Some(name) = self.group.as_ref().map(|g| g.name.clone()) {
+            debug!("Restoring group {}", name);
+            let group = select_group(&mut self.conn, &name)?;
+            let first = first_article(&group);
+            self.group = Some(group);
+
+            match self.current_article {
+                Some(number) if Some(number) != first => {
+                    debug!("Restoring current article {}", number);
+                    let resp = self.conn.command(&cmd::Stat::Number(number))?;
+                    if resp.code() != ResponseCode::Known(Kind::ArticleExists) {
+                        // The article is gone, the pointer is wherever GROUP left it
+                        self.current_article = first;
+                    }
+                }
+                _ => self.current_article = first,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Consume the client and return the underlying [`NntpConnection`]
+    ///
+    /// The connection is left in whatever state the client last put it in.
+    pub fn into_conn(self) -> NntpConnection {
+        self.conn
+    }
+
+    /// The metrics collected by the underlying connection
+    ///
+    /// See [`ConnectionConfig::metrics`] to share metrics between clients.
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        self.conn.metrics()
+    }
+
+    /// Register an [`Interceptor`] on the underlying connection
+    ///
+    /// The interceptor is added after any interceptors that are already registered
+    pub fn add_interceptor(&mut self, interceptor: Arc<dyn Interceptor>) -> &mut Self {
+        self.conn.add_interceptor(interceptor);
+        self
+    }
+
+    /// Get the currently selected group
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Get the last selected group
+    pub fn group(&self) -> Option<&Group> {
+        self.group.as_ref()
+    }
+
+    /// Select a newsgroup
+    pub fn select_group(&mut self, name: impl AsRef<str>) -> Result<Group> {
+        let resp = self.exchange(&cmd::Group(name.as_ref().to_string()), None)?;
+
+        match resp.code() {
+            ResponseCode::Known(Kind::GroupSelected) => {
+                let group = Group::try_from(&resp)?;
+                self.current_article = first_article(&group);
+                self.group = Some(group.clone());
+                Ok(group)
+            }
+            ResponseCode::Known(Kind::NoSuchNewsgroup) => Err(Error::failure(resp)),
+            code => Err(Error::Failure {
+                code,
+                msg: Some(format!("{}", resp.first_line_to_utf8_lossy())),
+                resp,
+            }),
+        }
+    }
+
+    /// List the article numbers in a newsgroup
+    ///
+    /// Like [`select_group`](Self::select_group), this selects the group and resets the
+    /// current article.
+    pub fn list_group(&mut self, list_group: cmd::ListGroup) -> Result<ListGroup> {
+        let resp = self
+            .exchange(&list_group, Some(true))?
+            .fail_unless(Kind::GroupSelected)?;
+
+        let list_group = ListGroup::try_from(&resp)?;
+        self.current_article = first_article(&list_group.group);
+        self.group = Some(list_group.group.clone());
+        Ok(list_group)
+    }
+
+    /// Get the current article number
+    ///
+    /// This mirrors the server's current article pointer. It is updated when a group is selected,
+    /// when an article is retrieved by number, and by [`next_article`](Self::next_article)
+    /// and [`last_article`](Self::last_article).
+    ///
+    /// Returns `None` if no group is selected or the selected group is empty.
+    pub fn current_article(&self) -> Option<ArticleNumber> {
+        self.current_article
+    }
+
+    /// Advance the current article pointer to the next article in the group
+    ///
+    /// Returns `None` if there is no next article (response code 421)
+    pub fn next_article(&mut self) -> Result<Option<Stat>> {
+        let resp = self.exchange(&cmd::Next, None)?;
+        self.move_current_article(resp, Kind::NoNextArticle)
+    }
+
+    /// Move the current article pointer to the previous article in the group
+    ///
+    /// Returns `None` if there is no previous article (response code 422)
+    pub fn last_article(&mut self) -> Result<Option<Stat>> {
+        let resp = self.exchange(&cmd::Last, None)?;
+        self.move_current_article(resp, Kind::NoPreviousArticle)
+    }
+
+    /// Walk the selected group one article at a time, starting from the current article
+    ///
+    /// See [`Cursor`] for more details.
+    pub fn cursor(&mut self, direction: Direction) -> Cursor<'_> {
+        Cursor::new(self, direction)
+    }
+
+    /// Update the current article from a `NEXT`/`LAST` response
+    fn move_current_article(&mut self, resp: RawResponse, edge: Kind) -> Result<Option<Stat>> {
+        match resp.code() {
+            ResponseCode::Known(Kind::ArticleExists) => {
+                let stat = Stat::try_from(&resp)?;
+                self.current_article = Some(stat.number);
+                Ok(Some(stat))
+            }
+            ResponseCode::Known(kind) if kind == edge => Ok(None),
+            _ => Err(Error::failure(resp)),
+        }
+    }
+
+    /// Retrieve the overview records for a range of articles in the selected group
+    ///
+    /// `XZVER` is used if it is enabled via [`ClientConfig::xzver`]. Otherwise `OVER` is used if
+    /// the server advertises it in its capabilities, and `XOVER` if it does not.
+    /// An empty collection is returned if there are no articles in the range.
+    pub fn overview(&mut self, low: ArticleNumber, high: ArticleNumber) -> Result<Overviews> {
+        self.overview_with_size(low, high)
+            .map(|(overviews, _bytes)| overviews)
+    }
+
+    /// Select a group and iterate over the overview records for every article in it
+    ///
+    /// See [`OverviewSync`] for more details.
+    pub fn overview_sync(
+        &mut self,
+        group: impl AsRef<str>,
+        config: OverviewSyncConfig,
+    ) -> Result<OverviewSync<'_>> {
+        let group = self.select_group(group)?;
+        Ok(OverviewSync::new(self, group, config))
+    }
+
+    /// Retrieve overview records along with the size of the response payload in bytes
+    fn overview_with_size(
+        &mut self,
+        low: ArticleNumber,
+        high: ArticleNumber,
+    ) -> Result<(Overviews, usize)> {
+        let parse = |resp: RawResponseRef<'_>| match resp.code() {
+            ResponseCode::Known(Kind::Overview) => {
+                let bytes = resp.data_blocks().map_or(0, |db| db.payload().len());
+                Ok((Overviews::try_from(&resp)?, bytes))
+            }
+            // There are no articles in the range (e.g. they have all expired)
+            ResponseCode::Known(Kind::NoArticleWithNumber) => Ok((Overviews::default(), 0)),
+            _ => Err(Error::failure(resp.into_owned())),
+        };
+
+        if self.config.xzver {
+            self.exchange_borrowed(&cmd::XZVer::Range { low, high }, parse)
+        } else if self.capabilities.get("OVER").is_some() {
+            self.exchange_borrowed(&cmd::Over::Range { low, high }, parse)
+        } else {
+            self.exchange_borrowed(&cmd::XOver::Range { low, high }, parse)
+        }
+    }
+
+    /// Select a group and determine which articles are new since the last recorded mark
+    ///
+    /// The `server` is used to key the mark in the store and should uniquely identify the server
+    /// (e.g. `news.example.com:563`).
+    ///
+    /// This does not update the store, call [`NewArticles::commit`] after the new articles
+    /// have been processed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use brokaw::client::{ClientConfig, FileStore};
+    ///
+    /// fn main() -> brokaw::error::Result<()> {
+    ///     let mut client = ClientConfig::default().connect(("news.example.com", 119))?;
+    ///     let mut store = FileStore::open("marks.tsv")?;
+    ///
+    ///     let new = client.new_articles(&store, "news.example.com", "misc.test")?;
+    ///     if let Some(range) = &new.range {
+    ///         let overviews = client.overview(*range.start(), *range.end())?;
+    ///         println!("{} new articles", overviews.len());
+    ///     }
+    ///     new.commit(&mut store)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new_articles(
+        &mut self,
+        store: &impl HighWaterMarkStore,
+        server: impl AsRef<str>,
+        group: impl AsRef<str>,
+    ) -> Result<NewArticles> {
+        let server = server.as_ref();
+        let group = self.select_group(group)?;
+        let previous = store.get(server, &group.name)?;
+
+        let new = NewArticles::new(server, group, previous);
+        if new.renumbered {
+            warn!(
+                "Group {} on {} was renumbered (high {} < mark {:?})",
+                new.group.name, server, new.group.high, new.previous
+            );
+        }
+        Ok(new)
+    }
+
+    /// The capabilities cached in the client
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Retrieve updated capabilities from the server
+    pub fn update_capabilities(&mut self) -> Result<&Capabilities> {
+        let resp = self
+            .exchange(&cmd::Capabilities, None)?
+            .fail_unless(Kind::Capabilities)?;
+
+        let capabilities = Capabilities::try_from(&resp)?;
+
+        self.capabilities = capabilities;
+
+        Ok(&self.capabilities)
+    }
+
+    /// Retrieve an article from the server
+    ///
+    ///
+    /// # Text Articles
+    ///
+    /// Binary articles can be converted to text using the [`to_text`](BinaryArticle::to_text)
+    /// and [`to_text_lossy`](BinaryArticle::to_text) methods. Note that the former is fallible
+    /// as it will validate that the body of the article is UTF-8.
+    ///
+    /// ```
+    /// use brokaw::client::NntpClient;
+    /// use brokaw::error::Result;
+    /// use brokaw::types::prelude::*;
+    /// use brokaw::types::command::Article;
+    ///
+    /// fn checked_conversion(client: &mut NntpClient) -> Result<TextArticle> {
+    ///     client.article(Article::Number(42))
+    ///         .and_then(|b| b.to_text())
+    /// }
+    ///
+    /// fn lossy_conversion(client: &mut NntpClient) -> Result<TextArticle> {
+    ///     client.article(Article::Number(42))
+    ///         .map(|b| b.to_text_lossy())
+    /// }
+    ///
+    /// ```
+    pub fn article(&mut self, article: cmd::Article) -> Result<BinaryArticle> {
+        let by_number = !matches!(article, cmd::Article::MessageId(_));
+        let article = self.exchange_borrowed(&article, |resp| {
+            BinaryArticle::try_from(&resp.fail_unless(Kind::Article)?)
+        })?;
+
+        if by_number {
+            self.current_article = Some(article.number());
+        }
+        Ok(article)
+    }
+
+    /// Retrieve the body for an article
+    pub fn body(&mut self, body: cmd::Body) -> Result<Body> {
+        let by_number = !matches!(body, cmd::Body::MessageId(_));
+        let body =
+            self.exchange_borrowed(&body, |resp| Body::try_from(&resp.fail_unless(Kind::Body)?))?;
+
+        if by_number {
+            self.current_article = Some(body.number);
+        }
+        Ok(body)
+    }
+
+    /// Retrieve the headers for an article
+    pub fn head(&mut self, head: cmd::Head) -> Result<Head> {
+        let by_number = !matches!(head, cmd::Head::MessageId(_));
+        let head =
+            self.exchange_borrowed(&head, |resp| Head::try_from(&resp.fail_unless(Kind::Head)?))?;
+
+        if by_number {
+            self.current_article = Some(head.number);
+        }
+        Ok(head)
+    }
+
+    /// Retrieve the status of an article
+    pub fn stat(&mut self, stat: cmd::Stat) -> Result<Option<Stat>> {
+        let by_number = !matches!(stat, cmd::Stat::MessageId(_));
+        let resp = self.exchange(&stat, None)?;
+        match resp.code() {
+            ResponseCode::Known(Kind::ArticleExists) => {
+                let stat = Stat::try_from(&resp)?;
+                if by_number {
+                    self.current_article = Some(stat.number);
+                }
+                Ok(Some(stat))
+            }
+            ResponseCode::Known(Kind::NoArticleWithMessageId)
+            | ResponseCode::Known(Kind::InvalidCurrentArticleNumber)
+            | ResponseCode::Known(Kind::NoArticleWithNumber) => Ok(None),
+            _ => Err(Error::failure(resp)),
+        }
+    }
+
+    /// Post an article with `POST`
+    ///
+    /// The article should contain the headers and body separated by a blank line, without the
+    /// terminating `.` line. It will be dot-stuffed before it is sent. Articles and control
+    /// messages can be assembled with an [`ArticleBuilder`](crate::post::ArticleBuilder).
+    ///
+    /// This fails if the server does not permit posting (response code 440) or rejects the
+    /// article (response code 441).
+    pub fn post(&mut self, article: impl AsRef<[u8]>) -> Result<RawResponse> {
+        self.exchange(&cmd::Post, None)?
+            .fail_unless(Kind::PostSendArticle)?;
+
+        self.conn.send_data_blocks(article.as_ref())?;
+        let resp = self
+            .conn
+            .read_response_auto()?
+            .fail_unless(Kind::ArticleReceived)?;
+
+        Ok(resp)
+    }
+
+    /// Offer an article to the server with `IHAVE`, sending it if the server wants it
+    ///
+    /// The article should contain the headers and body separated by a blank line, without the
+    /// terminating `.` line. It will be dot-stuffed before it is sent.
+    ///
+    /// Returns one of [`Accepted`](TransferStatus::Accepted),
+    /// [`NotWanted`](TransferStatus::NotWanted), [`Deferred`](TransferStatus::Deferred),
+    /// or [`Rejected`](TransferStatus::Rejected).
+    pub fn ihave(
+        &mut self,
+        message_id: impl AsRef<str>,
+        article: impl AsRef<[u8]>,
+    ) -> Result<TransferStatus> {
+        let message_id = message_id.as_ref();
+        let offer = self.exchange(&cmd::IHave(message_id.to_string()), None)?;
+        transit::ihave(&mut self.conn, offer, message_id, article.as_ref())
+    }
+
+    /// Switch to streaming mode and return a [`StreamingFeed`]
+    ///
+    /// This fails if the server does not permit streaming (response code 203).
+    pub fn mode_stream(&mut self) -> Result<StreamingFeed<'_>> {
+        self.exchange(&cmd::ModeStream, None)?
+            .fail_unless(Kind::StreamingPermitted)?;
+        Ok(StreamingFeed::new(&mut self.conn))
+    }
+
+    /// Close the connection to the server
+    pub fn close(&mut self) -> Result<RawResponse> {
+        let resp = self
+            .conn
+            .command(&cmd::Quit)?
+            .fail_unless(Kind::ConnectionClosing)?;
+
+        Ok(resp)
+    }
+}
+
+/// Configuration for an [`NntpClient`]
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    authinfo: Option<(String, String)>,
+    group: Option<String>,
+    conn_config: ConnectionConfig,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    xzver: bool,
+}
+
+impl ClientConfig {
+    /// Perform an AUTHINFO USER/PASS authentication after connecting to the server
+    ///
+    /// If the server later responds to a command with `480 Authentication required` (e.g.
+    /// because the session expired), the client authenticates again, restores the selected
+    /// group and current article, and retries the command once.
+    ///
+    /// https://tools.ietf.org/html/rfc4643#section-2.3
+    pub fn authinfo_user_pass(
+        &mut self,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> &mut Self {
+        self.authinfo = Some((username.as_ref().to_string(), password.as_ref().to_string()));
+        self
+    }
+
+    /// Join a group upon connection
+    ///
+    /// If this is set to None then no `GROUP` command will be sent when the client is initialized
+    pub fn group(&mut self, name: Option<impl AsRef<str>>) -> &mut Self {
+        self.group = name.map(|s| s.as_ref().to_string());
+        self
+    }
+
+    /// Retrieve overviews with the compressed `XZVER` command
+    ///
+    /// Servers do not advertise `XZVER` in their capabilities, so this must be enabled manually
+    /// for servers known to support it.
+    pub fn xzver(&mut self, enabled: bool) -> &mut Self {
+        self.xzver = enabled;
+        self
+    }
+
+    /// Set the configuration of the underlying [`NntpConnection`]
+    pub fn connection_config(&mut self, config: ConnectionConfig) -> &mut Self {
+        self.conn_config = config;
+        self
+    }
+
+    /// Register an [`Interceptor`] on clients created from this config
+    ///
+    /// These interceptors are registered after any in the
+    /// [`connection_config`](Self::connection_config), see [`Interceptor`] for details.
+    pub fn interceptor(&mut self, interceptor: Arc<dyn Interceptor>) -> &mut Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Resolves the configuration into a client
+    pub fn connect(&self, addr: impl ToSocketAddrs) -> Result<NntpClient> {
+        let (conn, conn_response) = NntpConnection::connect(addr, self.conn_config())?;
+        self.init(conn, conn_response)
+    }
+
+    /// Resolves the configuration into a client that plays back a recorded session
+    ///
+    /// This is useful for regression tests, see [`Transcript`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use brokaw::client::ClientConfig;
+    /// use brokaw::raw::Transcript;
+    /// use brokaw::types::command as cmd;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let transcript = Transcript::load("tests/mozilla.transcript")?;
+    ///     let mut client = ClientConfig::default()
+    ///         .group(Some("mozilla.dev.platform"))
+    ///         .replay(transcript)?;
+    ///
+    ///     let article = client.article(cmd::Article::Number(47661))?;
+    ///     assert_eq!(
+    ///         article.message_id(),
+    ///         "<b976e951-174a-4aba-9cd6-628b9b3418dd@googlegroups.com>"
+    ///     );
+    ///     client.close()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn replay(&self, transcript: Transcript) -> Result<NntpClient> {
+        let (conn, conn_response) = NntpConnection::replay(transcript, self.conn_config())?;
+        self.init(conn, conn_response)
+    }
+
+    /// The connection config including any interceptors registered on the client config
+    fn conn_config(&self) -> ConnectionConfig {
+        let mut config = self.conn_config.clone();
+        self.interceptors.iter().for_each(|interceptor| {
+            config.interceptor(interceptor.clone());
+        });
+        config
+    }
+
+    /// Authenticate, retrieve capabilities, and join the initial group
+    fn init(&self, mut conn: NntpConnection, conn_response: RawResponse) -> Result<NntpClient> {
+        debug!(
+            "Connected. Server returned `{}`",
+            conn_response.first_line_to_utf8_lossy()
+        );
+
+        // FIXME(ux) check capabilities before attempting auth info
+        if let Some((username, password)) = &self.authinfo {
+            if self.conn_config.tls_config.is_none() {
+                warn!("TLS is not enabled, credentials will be sent in the clear!");
+            }
+            debug!("Authenticating with AUTHINFO USER/PASS");
+            authenticate(&mut conn, username, password)?;
+        }
+
+        debug!("Retrieving capabilities...");
+        let capabilities = get_capabilities(&mut conn)?;
+
+        let group = if let Some(name) = &self.group {
+            debug!("Connecting to group {}...", name);
+            select_group(&mut conn, name)?.into()
+        } else {
+            debug!("No initial group specified");
+            None
+        };
+
+        let current_article = group.as_ref().and_then(first_article);
+
+        Ok(NntpClient {
+            conn,
+            config: self.clone(),
+            capabilities,
+            group,
+            current_article,
+        })
+    }
+}
+
+/// Perform an AUTHINFO USER/PASS exchange
+fn authenticate(
+    conn: &mut NntpConnection,
+    username: impl AsRef<str>,
+    password: impl AsRef<str>,
+) -> Result<()> {
+    debug!("Sending AUTHINFO USER");
+    let user_resp = conn.command(&cmd::AuthInfo::User(username.as_ref().to_string()))?;
+
+    if user_resp.code != ResponseCode::from(381) {
+        return Err(Error::Failure {
+            code: user_resp.code,
+            resp: user_resp,
+            msg: Some("AUTHINFO USER failed".to_string()),
+        });
+    }
+
+    debug!("Sending AUTHINFO PASS");
+    let pass_resp = conn.command(&cmd::AuthInfo::Pass(password.as_ref().to_string()))?;
+
+    if pass_resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+        return Err(Error::Failure {
+            code: pass_resp.code,
+            resp: pass_resp,
+            msg: Some("AUTHINFO PASS failed".to_string()),
+        });
+    }
+    debug!("Successfully authenticated");
+
+    Ok(())
+}
+
+fn get_capabilities(conn: &mut NntpConnection) -> Result<Capabilities> {
+    let resp = conn.command(&cmd::Capabilities)?;
+
+    if resp.code() != ResponseCode::Known(Kind::Capabilities) {
+        Err(Error::failure(resp))
+    } else {
+        Capabilities::try_from(&resp)
+    }
+}
+
+/// The article pointer set by a successful `GROUP` command
+///
+/// Per [RFC 3977 6.1.1](https://tools.ietf.org/html/rfc3977#section-6.1.1) the current article
+/// is set to the first article in the group, or left invalid if the group is empty.
+pub(crate) fn first_article(group: &Group) -> Option<ArticleNumber> {
+    if group.number > 0 {
+        Some(group.low)
+    } else {
+        None
+    }
+}
+
+fn select_group(conn: &mut NntpConnection, group: impl AsRef<str>) -> Result<Group> {
+    let resp = conn.command(&cmd::Group(group.as_ref().to_string()))?;
+
+    match resp.code() {
+        ResponseCode::Known(Kind::GroupSelected) => Group::try_from(&resp),
+        ResponseCode::Known(Kind::NoSuchNewsgroup) => Err(Error::failure(resp)),
+        code => Err(Error::Failure {
+            code,
+            msg: Some(format!("{}", resp.first_line_to_utf8_lossy())),
+            resp,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::raw::transcript::fixtures::{exchange, greeting, reader_session, session};
+
+    fn authenticate(transcript: &mut Transcript) {
+        exchange(
+            transcript,
+            "AUTHINFO USER user",
+            "381 Password required\r\n",
+        );
+        exchange(transcript, "AUTHINFO PASS pass", "281 Welcome\r\n");
+        exchange(
+            transcript,
+            "CAPABILITIES",
+            "101 Capability list:\r\nVERSION 2\r\nREADER\r\n.\r\n",
+        );
+    }
+
+    #[test]
+    fn test_reauthenticate() {
+        let mut transcript = greeting();
+        authenticate(&mut transcript);
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 3 1 3 misc.test\r\n",
+        );
+        exchange(&mut transcript, "NEXT", "223 2 <2@example.com>\r\n");
+
+        // The session expires
+        exchange(&mut transcript, "HEAD", "480 Authentication required\r\n");
+        authenticate(&mut transcript);
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 3 1 3 misc.test\r\n",
+        );
+        exchange(&mut transcript, "STAT 2", "223 2 <2@example.com>\r\n");
+        exchange(
+            &mut transcript,
+            "HEAD",
+            "221 2 <2@example.com>\r\nMessage-ID: <2@example.com>\r\n.\r\n",
+        );
+
+        let mut client = ClientConfig::default()
+            .authinfo_user_pass("user", "pass")
+            .group(Some("misc.test"))
+            .replay(transcript)
+            .unwrap();
+        client.next_article().unwrap();
+
+        let head = client.head(cmd::Head::Current).unwrap();
+        assert_eq!(head.number, 2);
+        assert_eq!(client.current_article(), Some(2));
+        assert_eq!(client.group().unwrap().name, "misc.test");
+    }
+
+    fn client_in_group(transcript: Transcript) -> NntpClient {
+        ClientConfig::default()
+            .group(Some("misc.test"))
+            .replay(transcript)
+            .unwrap()
+    }
+
+    fn group_transcript() -> Transcript {
+        let mut transcript = reader_session();
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 3 1 3 misc.test\r\n",
+        );
+        transcript
+    }
+
+    #[test]
+    fn test_next_and_last_article() {
+        let mut transcript = group_transcript();
+        exchange(&mut transcript, "NEXT", "223 2 <2@example.com>\r\n");
+        exchange(&mut transcript, "NEXT", "223 3 <3@example.com>\r\n");
+        exchange(&mut transcript, "NEXT", "421 No next article\r\n");
+        exchange(&mut transcript, "LAST", "223 2 <2@example.com>\r\n");
+        exchange(&mut transcript, "LAST", "223 1 <1@example.com>\r\n");
+        exchange(&mut transcript, "LAST", "422 No previous article\r\n");
+        exchange(&mut transcript, "NEXT", "412 No newsgroup selected\r\n");
+
+        let mut client = client_in_group(transcript);
+        assert_eq!(client.current_article(), Some(1));
+
+        let stat = client.next_article().unwrap().unwrap();
+        assert_eq!(stat.number, 2);
+        assert_eq!(stat.message_id, "<2@example.com>");
+        assert_eq!(client.next_article().unwrap().unwrap().number, 3);
+        assert_eq!(client.current_article(), Some(3));
+
+        // Running off the end leaves the pointer where it was
+        assert!(client.next_article().unwrap().is_none());
+        assert_eq!(client.current_article(), Some(3));
+
+        assert_eq!(client.last_article().unwrap().unwrap().number, 2);
+        assert_eq!(client.last_article().unwrap().unwrap().number, 1);
+        assert!(client.last_article().unwrap().is_none());
+        assert_eq!(client.current_article(), Some(1));
+
+        assert!(client.next_article().is_err());
+        assert_eq!(client.current_article(), Some(1));
+    }
+
+    #[test]
+    fn test_current_article() {
+        let mut transcript = group_transcript();
+        exchange(&mut transcript, "STAT 3", "223 3 <3@example.com>\r\n");
+        exchange(
+            &mut transcript,
+            "STAT <1@example.com>",
+            "223 0 <1@example.com>\r\n",
+        );
+        exchange(
+            &mut transcript,
+            "GROUP misc.empty",
+            "211 0 0 0 misc.empty\r\n",
+        );
+
+        let mut client = client_in_group(transcript);
+        assert_eq!(client.current_article(), Some(1));
+
+        client.stat(cmd::Stat::Number(3)).unwrap();
+        assert_eq!(client.current_article(), Some(3));
+
+        // Retrieving by Message-ID does not move the pointer
+        client
+            .stat(cmd::Stat::MessageId("<1@example.com>".to_string()))
+            .unwrap();
+        assert_eq!(client.current_article(), Some(3));
+
+        client.select_group("misc.empty").unwrap();
+        assert_eq!(client.current_article(), None);
+    }
+
+    #[test]
+    fn test_list_group_range_from() {
+        let mut transcript = group_transcript();
+        exchange(
+            &mut transcript,
+            "LISTGROUP misc.test 2-",
+            "211 3 1 3 misc.test\r\n2\r\n3\r\n.\r\n",
+        );
+        exchange(
+            &mut transcript,
+            "LISTGROUP misc.missing 2-",
+            "411 No such newsgroup\r\n",
+        );
+
+        let mut client = client_in_group(transcript);
+        let list_group = client
+            .list_group(cmd::ListGroup::RangeFrom {
+                group: "misc.test".to_string(),
+                low: 2,
+            })
+            .unwrap();
+        assert_eq!(
+            list_group.articles.ranges().collect::<Vec<_>>(),
+            vec![2..=3]
+        );
+
+        assert!(client
+            .list_group(cmd::ListGroup::RangeFrom {
+                group: "misc.missing".to_string(),
+                low: 2,
+            })
+            .is_err());
+        assert_eq!(client.group().unwrap().name, "misc.test");
+    }
+
+    #[test]
+    fn test_no_credentials() {
+        let mut transcript = session(&["VERSION 2"]);
+        exchange(&mut transcript, "DATE", "480 Authentication required\r\n");
+
+        let mut client = ClientConfig::default().replay(transcript).unwrap();
+        let resp = client.command(cmd::Date).unwrap();
+        assert_eq!(resp.code(), Kind::AuthenticationRequired.into());
+    }
+
+    #[test]
+    fn test_post() {
+        let mut transcript = session(&["VERSION 2", "POST"]);
+        exchange(&mut transcript, "POST", "340 Send article\r\n");
+        exchange(
+            &mut transcript,
+            "Newsgroups: misc.test\r\n\r\n..dot\r\n.",
+            "240 Article received OK\r\n",
+        );
+        exchange(&mut transcript, "POST", "440 Posting not permitted\r\n");
+
+        let mut client = ClientConfig::default().replay(transcript).unwrap();
+        let resp = client
+            .post("Newsgroups: misc.test\r\n\r\n.dot\r\n")
+            .unwrap();
+        assert_eq!(resp.code(), Kind::ArticleReceived.into());
+        assert!(client.post("Newsgroups: misc.test\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::*;
+
+use crate::client::NntpClient;
+use crate::error::Result;
+use crate::types::prelude::*;
+
+/// Configuration for an [`OverviewSync`]
+///
+/// The sync starts with a batch of [`initial_batch_size`](Self::initial_batch_size) articles.
+/// After every batch the size is adjusted:
+///
+/// * If the batch took longer than the target duration or returned more than the target
+///   number of bytes, the batch size is halved
+/// * If the batch took less than half the target duration and returned less than half the
+///   target bytes, the batch size is doubled
+///
+/// The batch size is always kept between the configured minimum and maximum.
+#[derive(Copy, Clone, Debug)]
+pub struct OverviewSyncConfig {
+    pub(crate) initial_batch_size: ArticleNumber,
+    pub(crate) min_batch_size: ArticleNumber,
+    pub(crate) max_batch_size: ArticleNumber,
+    pub(crate) target_duration: Duration,
+    pub(crate) target_bytes: usize,
+}
+
+impl Default for OverviewSyncConfig {
+    fn default() -> Self {
+        OverviewSyncConfig {
+            initial_batch_size: 1_000,
+            min_batch_size: 100,
+            max_batch_size: 100_000,
+            target_duration: Duration::from_secs(2),
+            target_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl OverviewSyncConfig {
+    /// Create a new config with the default settings
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the number of articles requested in the first batch
+    pub fn initial_batch_size(&mut self, size: ArticleNumber) -> &mut Self {
+        self.initial_batch_size = size;
+        self
+    }
+
+    /// Set the smallest number of articles requested in a batch
+    pub fn min_batch_size(&mut self, size: ArticleNumber) -> &mut Self {
+        self.min_batch_size = size;
+        self
+    }
+
+    /// Set the largest number of articles requested in a batch
+    pub fn max_batch_size(&mut self, size: ArticleNumber) -> &mut Self {
+        self.max_batch_size = size;
+        self
+    }
+
+    /// Set how long a single batch should take
+    pub fn target_duration(&mut self, dur: Duration) -> &mut Self {
+        self.target_duration = dur;
+        self
+    }
+
+    /// Set the number of response bytes a single batch should return
+    pub fn target_bytes(&mut self, bytes: usize) -> &mut Self {
+        self.target_bytes = bytes;
+        self
+    }
+
+    fn clamp(&self, size: ArticleNumber) -> ArticleNumber {
+        let min = self.min_batch_size.max(1);
+        size.max(min).min(self.max_batch_size.max(min))
+    }
+}
+
+/// An iterator over the overview records of an entire group
+///
+/// Created by [`NntpClient::overview_sync`].
+///
+/// The iterator walks from the group's low water mark to its high water mark in batches, using
+/// `OVER` if the server advertises it and `XOVER` otherwise. The batch size adapts to the
+/// response time and size of previous batches, see [`OverviewSyncConfig`].
+///
+/// # Checkpoints
+///
+/// [`checkpoint`](Self::checkpoint) returns the lowest article number that has not been yielded.
+/// It can be persisted and passed to [`resume_from`](Self::resume_from) to pick up where a
+/// previous sync left off.
+///
+/// # Example
+///
+/// ```no_run
+/// use brokaw::client::{ClientConfig, OverviewSyncConfig};
+///
+/// fn main() -> brokaw::error::Result<()> {
+///     let mut client = ClientConfig::default().connect(("news.example.com", 119))?;
+///
+///     let mut sync = client
+///         .overview_sync("misc.test", OverviewSyncConfig::default())?
+///         .resume_from(3_000_000);
+///
+///     while let Some(overview) = sync.next() {
+///         let overview = overview?;
+///         println!("{} {}", overview.number, overview.subject);
+///     }
+///     println!("Synced up to {}", sync.checkpoint());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct OverviewSync<'a> {
+    client: &'a mut NntpClient,
+    config: OverviewSyncConfig,
+    next: ArticleNumber,
+    high: ArticleNumber,
+    batch_size: ArticleNumber,
+    buffer: VecDeque<Overview>,
+    done: bool,
+}
+
+impl<'a> OverviewSync<'a> {
+    pub(crate) fn new(
+        client: &'a mut NntpClient,
+        group: Group,
+        config: OverviewSyncConfig,
+    ) -> Self {
+        let batch_size = config.clamp(config.initial_batch_size);
+        Self {
+            client,
+            config,
+            next: group.low,
+            high: group.high,
+            batch_size,
+            buffer: VecDeque::new(),
+            done: group.number == 0,
+        }
+    }
+
+    /// Start the sync from a previously saved [`checkpoint`](Self::checkpoint)
+    ///
+    /// Checkpoints below the group's low water mark are ignored.
+    pub fn resume_from(mut self, checkpoint: ArticleNumber) -> Self {
+        self.next = self.next.max(checkpoint);
+        self.done = self.done || self.next > self.high;
+        self
+    }
+
+    /// The lowest article number that has not been yielded yet
+    pub fn checkpoint(&self) -> ArticleNumber {
+        self.buffer
+            .front()
+            .map(|overview| overview.number)
+            .unwrap_or(self.next)
+    }
+
+    /// The number of articles that will be requested in the next batch
+    pub fn batch_size(&self) -> ArticleNumber {
+        self.batch_size
+    }
+
+    /// Fetch the next batch of records into the buffer
+    fn fetch_batch(&mut self) -> Result<()> {
+        let low = self.next;
+        let high = low.saturating_add(self.batch_size - 1).min(self.high);
+
+        let start = Instant::now();
+        let (overviews, bytes) = self.client.overview_with_size(low, high)?;
+        let elapsed = start.elapsed();
+
+        trace!(
+            "Retrieved {} overviews for {}-{} ({} bytes) in {:?}",
+            overviews.len(),
+            low,
+            high,
+            bytes,
+            elapsed
+        );
+
+        self.adjust_batch_size(elapsed, bytes);
+        self.buffer.extend(overviews);
+
+        self.next = high.saturating_add(1);
+        self.done = high >= self.high;
+        Ok(())
+    }
+
+    fn adjust_batch_size(&mut self, elapsed: Duration, bytes: usize) {
+        let target_duration = self.config.target_duration;
+        let target_bytes = self.config.target_bytes;
+
+        let new_size = if elapsed > target_duration || bytes > target_bytes {
+            self.batch_size / 2
+        } else if elapsed < target_duration / 2 && bytes < target_bytes / 2 {
+            self.batch_size.saturating_mul(2)
+        } else {
+            self.batch_size
+        };
+
+        let new_size = self.config.clamp(new_size);
+        if new_size != self.batch_size {
+            debug!(
+                "Adjusting overview batch size from {} to {}",
+                self.batch_size, new_size
+            );
+        }
+        self.batch_size = new_size;
+    }
+}
+
+impl Iterator for OverviewSync<'_> {
+    type Item = Result<Overview>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(overview) = self.buffer.pop_front() {
+                return Some(Ok(overview));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            if let Err(e) = self.fetch_batch() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::ClientConfig;
+    use crate::raw::transcript::fixtures::{exchange, reader_session};
+
+    fn overviews(numbers: std::ops::RangeInclusive<ArticleNumber>) -> String {
+        let mut resp = "224 Overview information follows\r\n".to_string();
+        for n in numbers {
+            resp.push_str(&format!(
+                "{}\tsubject {}\tfrom\tdate\t<{}@example.com>\t\t100\t10\r\n",
+                n, n, n
+            ));
+        }
+        resp.push_str(".\r\n");
+        resp
+    }
+
+    /// A client that will select a group of articles 1-10 and answer the given XOVER ranges
+    fn replay_client(ranges: &[std::ops::RangeInclusive<ArticleNumber>]) -> NntpClient {
+        let mut transcript = reader_session();
+        exchange(
+            &mut transcript,
+            "GROUP misc.test",
+            "211 10 1 10 misc.test\r\n",
+        );
+        for range in ranges {
+            exchange(
+                &mut transcript,
+                &format!("XOVER {}-{}", range.start(), range.end()),
+                overviews(range.clone()),
+            );
+        }
+        ClientConfig::default().replay(transcript).unwrap()
+    }
+
+    fn numbers(sync: OverviewSync<'_>) -> Vec<ArticleNumber> {
+        sync.map(|overview| overview.unwrap().number).collect()
+    }
+
+    #[test]
+    fn test_batch_size_grows() {
+        let mut client = replay_client(&[1..=2, 3..=6, 7..=10]);
+        let sync = client
+            .overview_sync(
+                "misc.test",
+                *OverviewSyncConfig::new()
+                    .initial_batch_size(2)
+                    .min_batch_size(1)
+                    .max_batch_size(4)
+                    .target_duration(Duration::from_secs(3600)),
+            )
+            .unwrap();
+        assert_eq!(sync.batch_size(), 2);
+        assert_eq!(numbers(sync), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_batch_size_shrinks() {
+        let mut client = replay_client(&[1..=4, 5..=6, 7..=7, 8..=8, 9..=9, 10..=10]);
+        let sync = client
+            .overview_sync(
+                "misc.test",
+                *OverviewSyncConfig::new()
+                    .initial_batch_size(4)
+                    .min_batch_size(1)
+                    .target_bytes(1),
+            )
+            .unwrap();
+        assert_eq!(numbers(sync), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut client = replay_client(&[1..=5, 6..=10]);
+        let mut sync = client
+            .overview_sync(
+                "misc.test",
+                *OverviewSyncConfig::new()
+                    .initial_batch_size(5)
+                    .min_batch_size(5)
+                    .max_batch_size(5),
+            )
+            .unwrap();
+        assert_eq!(sync.checkpoint(), 1);
+
+        assert_eq!(sync.next().unwrap().unwrap().number, 1);
+        assert_eq!(sync.checkpoint(), 2);
+        for _ in 2..=5 {
+            sync.next().unwrap().unwrap();
+        }
+        assert_eq!(sync.checkpoint(), 6);
+
+        assert_eq!(numbers(sync), (6..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_resume_from() {
+        let mut client = replay_client(&[7..=10]);
+        let sync = client
+            .overview_sync("misc.test", OverviewSyncConfig::default())
+            .unwrap()
+            .resume_from(7);
+        assert_eq!(sync.checkpoint(), 7);
+        assert_eq!(numbers(sync), (7..=10).collect::<Vec<_>>());
+
+        // Checkpoints outside of the group are clamped to the water marks
+        let mut client = replay_client(&[1..=10]);
+        let sync = client
+            .overview_sync("misc.test", OverviewSyncConfig::default())
+            .unwrap()
+            .resume_from(0);
+        assert_eq!(numbers(sync), (1..=10).collect::<Vec<_>>());
+
+        let mut client = replay_client(&[]);
+        let mut sync = client
+            .overview_sync("misc.test", OverviewSyncConfig::default())
+            .unwrap()
+            .resume_from(11);
+        assert!(sync.next().is_none());
+        assert_eq!(sync.checkpoint(), 11);
+    }
+
+    #[test]
+    fn test_error_ends_sync() {
+        let mut client = replay_client(&[1..=2]);
+        let mut sync = client
+            .overview_sync(
+                "misc.test",
+                *OverviewSyncConfig::new()
+                    .initial_batch_size(2)
+                    .min_batch_size(2)
+                    .max_batch_size(2),
+            )
+            .unwrap();
+        assert_eq!(sync.next().unwrap().unwrap().number, 1);
+        assert_eq!(sync.next().unwrap().unwrap().number, 2);
+        // The transcript has no reply for the next batch
+        assert!(sync.next().unwrap().is_err());
+        assert!(sync.next().is_none());
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::VecDeque;
+
+use log::*;
+
+use crate::error::{Error, Result};
+use crate::raw::connection::NntpConnection;
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// The outcome of offering or sending an article to a peer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransferStatus {
+    /// The peer wants the article (`CHECK` 238)
+    Wanted,
+    /// The article was transferred successfully (`IHAVE` 235, `TAKETHIS` 239)
+    Accepted,
+    /// The peer does not want the article, do not retry (`IHAVE` 435, `CHECK` 438)
+    NotWanted,
+    /// The transfer was not possible, try again later (`IHAVE` 436, `CHECK` 431)
+    Deferred,
+    /// The peer rejected the article, do not retry (`IHAVE` 437, `TAKETHIS` 439)
+    Rejected,
+}
+
+impl TransferStatus {
+    /// The status associated with a transfer response code, if any
+    fn from_code(code: ResponseCode) -> Option<Self> {
+        let status = match code {
+            ResponseCode::Known(Kind::CheckSendArticle) => TransferStatus::Wanted,
+            ResponseCode::Known(Kind::ArticleTransferredOk)
+            | ResponseCode::Known(Kind::TakeThisTransferredOk) => TransferStatus::Accepted,
+            ResponseCode::Known(Kind::ArticleNotWanted)
+            | ResponseCode::Known(Kind::CheckNotWanted) => TransferStatus::NotWanted,
+            ResponseCode::Known(Kind::TransferFailed)
+            | ResponseCode::Known(Kind::CheckTryLater) => TransferStatus::Deferred,
+            ResponseCode::Known(Kind::TransferRejected)
+            | ResponseCode::Known(Kind::TakeThisRejected) => TransferStatus::Rejected,
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+/// The command a [`StreamReply`] is responding to
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StreamCommand {
+    /// `CHECK`
+    Check,
+    /// `TAKETHIS`
+    TakeThis,
+}
+
+/// A response to a pipelined `CHECK` or `TAKETHIS` command
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamReply {
+    /// The command that was sent
+    pub command: StreamCommand,
+    /// The message-id of the article
+    pub message_id: String,
+    /// The outcome
+    pub status: TransferStatus,
+}
+
+/// An [RFC 4644](https://tools.ietf.org/html/rfc4644) streaming feed
+///
+/// Created by [`NntpClient::mode_stream`](crate::client::NntpClient::mode_stream).
+///
+/// `CHECK` and `TAKETHIS` commands are pipelined: they are written to the server without waiting
+/// for a response. The feed tracks the outstanding commands and matches responses to them by
+/// message-id.
+///
+/// To avoid deadlocking with a server that is blocked writing responses, at most
+/// [`window`](Self::window) commands are kept in flight. Once the window is full, sending another
+/// command reads a response first and buffers it to be returned by [`poll`](Self::poll).
+///
+/// # Example
+///
+/// ```no_run
+/// use brokaw::client::{ClientConfig, StreamCommand, TransferStatus};
+///
+/// fn main() -> brokaw::error::Result<()> {
+///     let mut client = ClientConfig::default().connect(("peer.example.com", 119))?;
+///     let articles = vec![("<1@example.com>", b"Subject: one\r\n\r\nbody\r\n".to_vec())];
+///
+///     let mut feed = client.mode_stream()?;
+///     for (id, _) in &articles {
+///         feed.check(*id)?;
+///     }
+///
+///     while let Some(reply) = feed.poll()? {
+///         if reply.command == StreamCommand::Check && reply.status == TransferStatus::Wanted {
+///             let (id, article) = articles.iter().find(|(id, _)| *id == reply.message_id).unwrap();
+///             feed.take_this(*id, article)?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct StreamingFeed<'a> {
+    conn: &'a mut NntpConnection,
+    pending: VecDeque<(StreamCommand, String)>,
+    ready: VecDeque<StreamReply>,
+    window: usize,
+}
+
+impl<'a> StreamingFeed<'a> {
+    pub(crate) fn new(conn: &'a mut NntpConnection) -> Self {
+        Self {
+            conn,
+            pending: VecDeque::new(),
+            ready: VecDeque::new(),
+            window: 64,
+        }
+    }
+
+    /// The maximum number of commands that may be in flight
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Set the maximum number of commands that may be in flight
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.max(1);
+    }
+
+    /// The number of commands that have not received a response
+    pub fn outstanding(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Ask the peer whether it wants an article
+    pub fn check(&mut self, message_id: impl AsRef<str>) -> Result<()> {
+        self.make_room()?;
+        let message_id = message_id.as_ref().to_string();
+        self.conn.send(&cmd::Check(message_id.clone()))?;
+        self.pending.push_back((StreamCommand::Check, message_id));
+        Ok(())
+    }
+
+    /// Send an article to the peer
+    ///
+    /// The article will be dot-stuffed, see
+    /// [`NntpConnection::send_data_blocks`](crate::raw::connection::NntpConnection::send_data_blocks).
+    pub fn take_this(&mut self, message_id: impl AsRef<str>, article: &[u8]) -> Result<()> {
+        self.make_room()?;
+        let message_id = message_id.as_ref().to_string();
+        self.conn.send(&cmd::TakeThis(message_id.clone()))?;
+        self.conn.send_data_blocks(article)?;
+        self.pending
+            .push_back((StreamCommand::TakeThis, message_id));
+        Ok(())
+    }
+
+    /// Return the next response
+    ///
+    /// This blocks if no responses have been buffered and there are outstanding commands.
+    /// Returns `None` once every command has received a response.
+    pub fn poll(&mut self) -> Result<Option<StreamReply>> {
+        if let Some(reply) = self.ready.pop_front() {
+            return Ok(Some(reply));
+        }
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        self.read_reply().map(Some)
+    }
+
+    /// Wait for every outstanding command to receive a response
+    pub fn finish(&mut self) -> Result<Vec<StreamReply>> {
+        let mut replies = Vec::with_capacity(self.ready.len() + self.pending.len());
+        while let Some(reply) = self.poll()? {
+            replies.push(reply);
+        }
+        Ok(replies)
+    }
+
+    fn make_room(&mut self) -> Result<()> {
+        while self.pending.len() >= self.window {
+            let reply = self.read_reply()?;
+            self.ready.push_back(reply);
+        }
+        Ok(())
+    }
+
+    fn read_reply(&mut self) -> Result<StreamReply> {
+        let resp = self.conn.read_response_auto()?;
+
+        let status = match TransferStatus::from_code(resp.code()) {
+            Some(status) => status,
+            None => {
+                // Error responses may not carry a message-id, so they are attributed to the
+                // oldest outstanding command
+                self.pending.pop_front();
+                return Err(Error::failure(resp));
+            }
+        };
+
+        let message_id = resp
+            .first_line_to_utf8_lossy()
+            .split_whitespace()
+            .nth(1)
+            .map(ToString::to_string)
+            .ok_or_else(|| Error::missing_field("message-id"))?;
+
+        // Responses should arrive in order but we match on the message-id to be safe
+        let idx = self
+            .pending
+            .iter()
+            .position(|(_, id)| *id == message_id)
+            .ok_or_else(|| {
+                Error::de(format!(
+                    "Received a response for unknown article {}",
+                    message_id
+                ))
+            })?;
+        if idx != 0 {
+            warn!("Response for {} arrived out of order", message_id);
+        }
+        let (command, message_id) = self.pending.remove(idx).unwrap();
+
+        Ok(StreamReply {
+            command,
+            message_id,
+            status,
+        })
+    }
+}
+
+/// Send an article offered with `IHAVE` if the peer wants it
+///
+/// `resp` is the peer's response to the `IHAVE` command
+pub(crate) fn ihave(
+    conn: &mut NntpConnection,
+    resp: RawResponse,
+    message_id: &str,
+    article: &[u8],
+) -> Result<TransferStatus> {
+    match resp.code() {
+        ResponseCode::Known(Kind::IHaveSendArticle) => {}
+        code => {
+            return match TransferStatus::from_code(code) {
+                Some(status @ TransferStatus::NotWanted)
+                | Some(status @ TransferStatus::Deferred) => Ok(status),
+                _ => Err(Error::failure(resp)),
+            }
+        }
+    }
+
+    debug!("Sending article {}", message_id);
+    conn.send_data_blocks(article)?;
+    let resp = conn.read_response_auto()?;
+
+    match TransferStatus::from_code(resp.code()) {
+        Some(status @ TransferStatus::Accepted)
+        | Some(status @ TransferStatus::Deferred)
+        | Some(status @ TransferStatus::Rejected) => Ok(status),
+        _ => Err(Error::failure(resp)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::{ClientConfig, NntpClient};
+    use crate::raw::transcript::fixtures::session;
+    use crate::raw::transcript::{Record, Transcript};
+
+    const ARTICLE: &str = "Message-ID: <1@example.com>\r\n\r\nbody\r\n";
+
+    fn send(transcript: &mut Transcript, command: &str) {
+        transcript.push(Record::Sent(format!("{}\r\n", command).into_bytes()));
+    }
+
+    fn receive(transcript: &mut Transcript, response: &str) {
+        transcript.push(Record::Received(format!("{}\r\n", response).into_bytes()));
+    }
+
+    fn client(transcript: Transcript) -> NntpClient {
+        ClientConfig::default().replay(transcript).unwrap()
+    }
+
+    fn transcript() -> Transcript {
+        session(&["VERSION 2", "IHAVE", "STREAMING"])
+    }
+
+    fn streaming_transcript() -> Transcript {
+        let mut transcript = transcript();
+        send(&mut transcript, "MODE STREAM");
+        receive(&mut transcript, "203 Streaming permitted");
+        transcript
+    }
+
+    fn reply(command: StreamCommand, message_id: &str, status: TransferStatus) -> StreamReply {
+        StreamReply {
+            command,
+            message_id: message_id.to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_ihave() {
+        let mut transcript = transcript();
+        send(&mut transcript, "IHAVE <1@example.com>");
+        receive(&mut transcript, "335 Send it");
+        send(&mut transcript, &format!("{}.", ARTICLE));
+        receive(&mut transcript, "235 Article transferred OK");
+
+        send(&mut transcript, "IHAVE <2@example.com>");
+        receive(&mut transcript, "435 Article not wanted");
+
+        send(&mut transcript, "IHAVE <3@example.com>");
+        receive(
+            &mut transcript,
+            "436 Transfer not possible; try again later",
+        );
+
+        send(&mut transcript, "IHAVE <4@example.com>");
+        receive(&mut transcript, "335 Send it");
+        send(&mut transcript, &format!("{}.", ARTICLE));
+        receive(&mut transcript, "437 Transfer rejected; do not retry");
+
+        send(&mut transcript, "IHAVE <5@example.com>");
+        receive(&mut transcript, "502 Permission denied");
+
+        let mut client = client(transcript);
+        let status = |client: &mut NntpClient, id| client.ihave(id, ARTICLE);
+        assert_eq!(
+            status(&mut client, "<1@example.com>").unwrap(),
+            TransferStatus::Accepted
+        );
+        assert_eq!(
+            status(&mut client, "<2@example.com>").unwrap(),
+            TransferStatus::NotWanted
+        );
+        assert_eq!(
+            status(&mut client, "<3@example.com>").unwrap(),
+            TransferStatus::Deferred
+        );
+        assert_eq!(
+            status(&mut client, "<4@example.com>").unwrap(),
+            TransferStatus::Rejected
+        );
+        assert!(status(&mut client, "<5@example.com>").is_err());
+    }
+
+    #[test]
+    fn test_ihave_reauthenticates() {
+        let authenticate = |transcript: &mut Transcript| {
+            send(transcript, "AUTHINFO USER feeder");
+            receive(transcript, "381 Password required");
+            send(transcript, "AUTHINFO PASS secret");
+            receive(transcript, "281 Authentication accepted");
+            send(transcript, "CAPABILITIES");
+            receive(
+                transcript,
+                "101 Capability list:\r\nVERSION 2\r\nIHAVE\r\n.",
+            );
+        };
+
+        let mut transcript = Transcript::new();
+        receive(&mut transcript, "200 ready");
+        authenticate(&mut transcript);
+        // The session expires
+        send(&mut transcript, "IHAVE <1@example.com>");
+        receive(&mut transcript, "480 Authentication required");
+        authenticate(&mut transcript);
+        send(&mut transcript, "IHAVE <1@example.com>");
+        receive(&mut transcript, "335 Send it");
+        send(&mut transcript, &format!("{}.", ARTICLE));
+        receive(&mut transcript, "235 Article transferred OK");
+
+        let mut client = ClientConfig::default()
+            .authinfo_user_pass("feeder", "secret")
+            .replay(transcript)
+            .unwrap();
+        assert_eq!(
+            client.ihave("<1@example.com>", ARTICLE).unwrap(),
+            TransferStatus::Accepted
+        );
+    }
+
+    #[test]
+    fn test_pipelining() {
+        let mut transcript = streaming_transcript();
+        send(&mut transcript, "CHECK <1@example.com>");
+        send(&mut transcript, "CHECK <2@example.com>");
+        // The window is full, a response is read before the next command is sent
+        receive(&mut transcript, "238 <1@example.com>");
+        send(&mut transcript, "CHECK <3@example.com>");
+        receive(&mut transcript, "438 <2@example.com>");
+        send(&mut transcript, "TAKETHIS <1@example.com>");
+        send(&mut transcript, &format!("{}.", ARTICLE));
+        // Responses may arrive out of order
+        receive(&mut transcript, "239 <1@example.com>");
+        receive(&mut transcript, "431 <3@example.com>");
+
+        let mut client = client(transcript);
+        let mut feed = client.mode_stream().unwrap();
+        feed.set_window(2);
+
+        feed.check("<1@example.com>").unwrap();
+        feed.check("<2@example.com>").unwrap();
+        assert_eq!(feed.outstanding(), 2);
+        feed.check("<3@example.com>").unwrap();
+        assert_eq!(feed.outstanding(), 2);
+
+        assert_eq!(
+            feed.poll().unwrap(),
+            Some(reply(
+                StreamCommand::Check,
+                "<1@example.com>",
+                TransferStatus::Wanted
+            ))
+        );
+        feed.take_this("<1@example.com>", ARTICLE.as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            feed.finish().unwrap(),
+            vec![
+                reply(
+                    StreamCommand::Check,
+                    "<2@example.com>",
+                    TransferStatus::NotWanted
+                ),
+                reply(
+                    StreamCommand::TakeThis,
+                    "<1@example.com>",
+                    TransferStatus::Accepted
+                ),
+                reply(
+                    StreamCommand::Check,
+                    "<3@example.com>",
+                    TransferStatus::Deferred
+                ),
+            ]
+        );
+        assert_eq!(feed.poll().unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejected_replies() {
+        let mut transcript = streaming_transcript();
+        send(&mut transcript, "TAKETHIS <1@example.com>");
+        send(&mut transcript, &format!("{}.", ARTICLE));
+        send(&mut transcript, "CHECK <2@example.com>");
+        receive(&mut transcript, "439 <1@example.com>");
+        receive(&mut transcript, "501 Syntax error");
+
+        let mut client = client(transcript);
+        let mut feed = client.mode_stream().unwrap();
+        feed.take_this("<1@example.com>", ARTICLE.as_bytes())
+            .unwrap();
+        feed.check("<2@example.com>").unwrap();
+
+        assert_eq!(
+            feed.poll().unwrap(),
+            Some(reply(
+                StreamCommand::TakeThis,
+                "<1@example.com>",
+                TransferStatus::Rejected
+            ))
+        );
+        // An error response still accounts for the command it answers
+        assert!(feed.poll().is_err());
+        assert_eq!(feed.outstanding(), 0);
+        assert_eq!(feed.poll().unwrap(), None);
+    }
+}
@@ -1,3 +1,4 @@
+use std::io;
 use std::str::Utf8Error;
 
 use crate::types::prelude::*;
@@ -30,9 +31,61 @@ pub enum Error {
     /// An error deserializing bytes as UTF-8
     #[error("{0}")]
     Utf8(#[from] Utf8Error),
+    /// An I/O error that did not originate from the connection
+    ///
+    /// For example, failing to read or write a state file. Errors that indicate a lost
+    /// connection (e.g. a reset or a timeout) are classified as [`ErrorClass::Connection`].
+    #[error("IO {0}")]
+    Io(#[from] io::Error),
 }
 
 impl Error {
+    /// Classify the error
+    ///
+    /// ```
+    /// use brokaw::error::{Error, ErrorClass};
+    /// use brokaw::types::prelude::*;
+    ///
+    /// fn should_retry(e: &Error) -> bool {
+    ///     match e.class() {
+    ///         // Try another server instead
+    ///         ErrorClass::NoSuchArticle => false,
+    ///         class => class.is_retryable(),
+    ///     }
+    /// }
+    /// ```
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::Failure { code, .. } => ErrorClass::from(*code),
+            Error::Connection(_) => ErrorClass::Connection,
+            Error::Io(e) if is_connection_error(e) => ErrorClass::Connection,
+            Error::Deserialization(_) | Error::Utf8(_) | Error::Io(_) => ErrorClass::Other,
+        }
+    }
+
+    /// Returns true if the operation that caused the error may succeed if it is retried
+    ///
+    /// See [`ErrorClass::is_retryable`]
+    pub fn is_retryable(&self) -> bool {
+        self.class().is_retryable()
+    }
+
+    /// The response code returned by the server, if the error is a [`Failure`](Error::Failure)
+    pub fn code(&self) -> Option<ResponseCode> {
+        match self {
+            Error::Failure { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// The response returned by the server, if the error is a [`Failure`](Error::Failure)
+    pub fn response(&self) -> Option<&RawResponse> {
+        match self {
+            Error::Failure { resp, .. } => Some(resp),
+            _ => None,
+        }
+    }
+
     pub(crate) fn failure(resp: RawResponse) -> Self {
         Error::Failure {
             code: resp.code(),
@@ -62,5 +115,173 @@ impl Error {
     }
 }
 
+/// Returns true if the I/O error indicates that a connection was lost or could not be made
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::WouldBlock
+    )
+}
+
+/// A coarse classification of [`Error`]s
+///
+/// Failures returned by the server are classified by their response code, which makes it
+/// possible to handle them without matching on individual [`Kind`]s.
+///
+/// | Class | Response codes | Retryable |
+/// |-------|----------------|-----------|
+/// | [`AuthenticationRequired`](Self::AuthenticationRequired) | 480 | yes |
+/// | [`AuthenticationFailed`](Self::AuthenticationFailed) | 481, 482 | no |
+/// | [`NoSuchGroup`](Self::NoSuchGroup) | 411 | no |
+/// | [`NoSuchArticle`](Self::NoSuchArticle) | 423, 430 | no |
+/// | [`PostingFailed`](Self::PostingFailed) | 440, 441 | no |
+/// | [`Unavailable`](Self::Unavailable) | 400, 403 | yes |
+/// | [`PermanentlyUnavailable`](Self::PermanentlyUnavailable) | 502 | no |
+/// | [`Unsupported`](Self::Unsupported) | 500, 501, 503 | no |
+/// | [`Connection`](Self::Connection) | - | yes |
+/// | [`Other`](Self::Other) | all others | no |
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorClass {
+    /// The server requires authentication before the command can be used
+    ///
+    /// The command may be retried after authenticating
+    AuthenticationRequired,
+    /// The server rejected the credentials or the authentication exchange
+    AuthenticationFailed,
+    /// The newsgroup does not exist
+    NoSuchGroup,
+    /// The article does not exist (or is not available on this server)
+    NoSuchArticle,
+    /// The server did not accept an article that was posted
+    PostingFailed,
+    /// The service is temporarily unavailable
+    ///
+    /// Servers commonly return this when a connection limit is hit, during maintenance, or after
+    /// an internal fault, so it is worth retrying with a backoff (or on another server).
+    Unavailable,
+    /// The service is permanently unavailable, or the command is not permitted
+    ///
+    /// Retrying on the same server will not help, though another server may allow it.
+    PermanentlyUnavailable,
+    /// The server did not understand or does not support the command
+    Unsupported,
+    /// The connection failed (e.g. an I/O or TLS error)
+    ///
+    /// The command may be retried on a new connection
+    Connection,
+    /// Any other error
+    Other,
+}
+
+impl ErrorClass {
+    /// Returns true if the failed operation may succeed if it is retried
+    ///
+    /// Note that retrying may first require reconnecting or authenticating, see the variant
+    /// documentation for details.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorClass::AuthenticationRequired | ErrorClass::Unavailable | ErrorClass::Connection
+        )
+    }
+
+    /// Returns true if retrying the failed operation will not help
+    pub fn is_permanent(&self) -> bool {
+        !self.is_retryable()
+    }
+}
+
+impl From<ResponseCode> for ErrorClass {
+    fn from(code: ResponseCode) -> Self {
+        match u16::from(code) {
+            480 => ErrorClass::AuthenticationRequired,
+            481 | 482 => ErrorClass::AuthenticationFailed,
+            411 => ErrorClass::NoSuchGroup,
+            423 | 430 => ErrorClass::NoSuchArticle,
+            440 | 441 => ErrorClass::PostingFailed,
+            400 | 403 => ErrorClass::Unavailable,
+            502 => ErrorClass::PermanentlyUnavailable,
+            500 | 501 | 503 => ErrorClass::Unsupported,
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
 /// A result type returned by the library
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class() {
+        let err = Error::failure(RawResponse::new(
+            Kind::NoArticleWithMessageId,
+            "No such article",
+        ));
+        assert_eq!(err.class(), ErrorClass::NoSuchArticle);
+        assert!(!err.is_retryable());
+        assert_eq!(err.code(), Some(Kind::NoArticleWithMessageId.into()));
+
+        let err = Error::failure(RawResponse::new(Kind::TemporarilyUnavailable, "Try later"));
+        assert_eq!(err.class(), ErrorClass::Unavailable);
+        assert!(err.is_retryable());
+
+        let err = Error::from(crate::raw::error::Error::Parse);
+        assert_eq!(err.class(), ErrorClass::Connection);
+        assert_eq!(err.code(), None);
+
+        let err = Error::from(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert_eq!(err.class(), ErrorClass::Connection);
+        assert!(err.is_retryable());
+
+        let err = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(err.class(), ErrorClass::Other);
+
+        assert_eq!(ErrorClass::from(ResponseCode::from(599)), ErrorClass::Other);
+        assert!(ErrorClass::Unsupported.is_permanent());
+    }
+
+    #[test]
+    fn test_class_by_code() {
+        let classes = [
+            (ErrorClass::AuthenticationRequired, &[480][..]),
+            (ErrorClass::AuthenticationFailed, &[481, 482]),
+            (ErrorClass::NoSuchGroup, &[411]),
+            (ErrorClass::NoSuchArticle, &[423, 430]),
+            (ErrorClass::PostingFailed, &[440, 441]),
+            (ErrorClass::Unavailable, &[400, 403]),
+            (ErrorClass::PermanentlyUnavailable, &[502]),
+            (ErrorClass::Unsupported, &[500, 501, 503]),
+            (
+                ErrorClass::Other,
+                &[412, 420, 421, 422, 435, 436, 437, 483, 499],
+            ),
+        ];
+
+        for (class, codes) in classes.iter() {
+            for code in codes.iter() {
+                let code = ResponseCode::from(*code);
+                assert_eq!(ErrorClass::from(code), *class, "{}", code);
+                assert_eq!(
+                    class.is_retryable(),
+                    matches!(
+                        class,
+                        ErrorClass::AuthenticationRequired | ErrorClass::Unavailable
+                    ),
+                    "{}",
+                    code
+                );
+            }
+        }
+    }
+}
@@ -13,9 +13,9 @@
 //! Brokaw provides two primary APIs for interacting with NNTP servers:
 //!
 //! 1. The [`NntpClient`] provides a higher-level that provides a a config
-//! based builder and automatic deserialization of responses into different types.
+//!    based builder and automatic deserialization of responses into different types.
 //! 2. The [`NntpConnection`] provides a lower-level abstraction that
-//! only provides validation that messages adhere to NNTP's wire format.
+//!    only provides validation that messages adhere to NNTP's wire format.
 //!
 //! Brokaw additionally provides strongly typed [commands](types::command),
 //! [responses](types::response), and the [`NntpCommand`](types::NntpCommand)
@@ -28,12 +28,33 @@
 #[cfg(doctest)]
 doc_comment::doctest!("../README.md");
 
+/// Exporting articles to mbox and Maildir
+pub mod archive;
+
+/// Atomically replacing files on disk
+pub(crate) mod atomic;
+
 /// The high-level client and configuration API
 pub mod client;
 
 /// Error and Result types returned by the Brokaw
 pub mod error;
 
+/// Reading and writing `.newsrc` files
+pub mod newsrc;
+
+/// Building articles and control messages for posting
+pub mod post;
+
+/// A caching proxy that multiplexes readers onto a pool of upstream connections
+pub mod proxy;
+
+/// Kill files and article scoring
+///
+/// Requires the `score` feature.
+#[cfg(feature = "score")]
+pub mod score;
+
 /// Low level connection/stream APIs
 ///
 /// These deal with raw NNTP connections and byte responses.
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::atomic;
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+
+/// A `.newsrc` file
+///
+/// A `.newsrc` records which groups a reader is subscribed to and which articles have been read
+/// in each group. It is shared by most Unix newsreaders (e.g. slrn, tin, trn).
+///
+/// ```text
+/// comp.lang.rust: 1-500,502,510-600
+/// misc.test! 1-20
+/// ```
+///
+/// * A `:` after the group name indicates the group is subscribed, a `!` indicates it is not
+/// * The list of read articles is stored as an [`ArticleSet`]
+/// * The order of the groups is preserved when the file is written back out
+/// * A leading `options` line (used by rn-style readers) is preserved as-is
+///
+/// # Example
+///
+/// ```
+/// use brokaw::newsrc::Newsrc;
+/// use brokaw::types::prelude::*;
+///
+/// let mut newsrc: Newsrc = "comp.lang.rust: 1-500,502\nmisc.test! 1-20\n".parse().unwrap();
+///
+/// let group = Group {
+///     number: 505,
+///     low: 1,
+///     high: 505,
+///     name: "comp.lang.rust".to_string(),
+/// };
+///
+/// let entry = newsrc.get("comp.lang.rust").unwrap();
+/// assert!(entry.subscribed);
+/// assert_eq!(entry.unread_count(&group), 4);
+/// assert_eq!(entry.unread(&group).to_string(), "501,503-505");
+///
+/// newsrc.mark_read("comp.lang.rust", 501);
+/// assert_eq!(newsrc.to_string(), "comp.lang.rust: 1-502\nmisc.test! 1-20\n");
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Newsrc {
+    options: Option<String>,
+    entries: Vec<NewsrcEntry>,
+    index: HashMap<String, usize>,
+}
+
+/// The state of a single group within a [`Newsrc`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewsrcEntry {
+    /// The name of the group
+    pub name: String,
+    /// Whether or not the reader is subscribed to the group
+    pub subscribed: bool,
+    /// The articles that have been read
+    pub read: ArticleSet,
+}
+
+impl NewsrcEntry {
+    /// Create an entry with no read articles
+    pub fn new(name: impl AsRef<str>, subscribed: bool) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            subscribed,
+            read: ArticleSet::new(),
+        }
+    }
+
+    /// The articles in the group that have not been read
+    ///
+    /// The group should be the result of selecting the group on the server
+    /// (e.g. with [`NntpClient::select_group`](crate::client::NntpClient::select_group)).
+    pub fn unread(&self, group: &Group) -> ArticleSet {
+        if group.number == 0 {
+            ArticleSet::new()
+        } else {
+            self.read.gaps(group.low..=group.high)
+        }
+    }
+
+    /// The number of articles in the group that have not been read
+    ///
+    /// Note that this is an upper bound as servers may have gaps in their article numbers.
+    pub fn unread_count(&self, group: &Group) -> u64 {
+        self.unread(group).len()
+    }
+
+    /// Mark every article up to and including the group's high water mark as read
+    pub fn catch_up(&mut self, group: &Group) {
+        self.read.insert_range(1..=group.high);
+    }
+}
+
+impl fmt::Display for NewsrcEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.subscribed { ':' } else { '!' };
+        write!(f, "{}{}", self.name, marker)?;
+        if !self.read.is_empty() {
+            write!(f, " {}", self.read)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NewsrcEntry {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let idx = line
+            .find([':', '!'])
+            .ok_or_else(|| Error::missing_field("subscription marker"))?;
+
+        let name = line[..idx].trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(Error::parse_error("newsgroup"));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            subscribed: &line[idx..idx + 1] == ":",
+            read: line[idx + 1..].parse()?,
+        })
+    }
+}
+
+impl Newsrc {
+    /// Create an empty `.newsrc`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Read a `.newsrc` from disk
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Write the `.newsrc` to disk
+    ///
+    /// The contents are written to a temporary file which is then renamed over the destination
+    /// so that a crash never leaves a truncated file behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        atomic::write_file(path.as_ref(), |w| write!(w, "{}", self))?;
+        Ok(())
+    }
+
+    /// The `options` line, if present
+    pub fn options(&self) -> Option<&str> {
+        self.options.as_deref()
+    }
+
+    /// Get the entry for a group
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&NewsrcEntry> {
+        self.index.get(name.as_ref()).map(|&i| &self.entries[i])
+    }
+
+    /// Get a mutable reference to the entry for a group
+    pub fn get_mut(&mut self, name: impl AsRef<str>) -> Option<&mut NewsrcEntry> {
+        let idx = *self.index.get(name.as_ref())?;
+        Some(&mut self.entries[idx])
+    }
+
+    /// Get the entry for a group, appending an unsubscribed entry if it does not exist
+    pub fn entry(&mut self, name: impl AsRef<str>) -> &mut NewsrcEntry {
+        let name = name.as_ref();
+        let idx = match self.index.get(name) {
+            Some(&idx) => idx,
+            None => {
+                self.push(NewsrcEntry::new(name, false));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[idx]
+    }
+
+    /// Subscribe to a group, adding it if necessary
+    pub fn subscribe(&mut self, name: impl AsRef<str>) {
+        self.entry(name).subscribed = true;
+    }
+
+    /// Unsubscribe from a group
+    ///
+    /// The group's read articles are kept so that re-subscribing restores them.
+    pub fn unsubscribe(&mut self, name: impl AsRef<str>) {
+        if let Some(entry) = self.get_mut(name) {
+            entry.subscribed = false;
+        }
+    }
+
+    /// Mark an article as read, adding the group if necessary
+    pub fn mark_read(&mut self, name: impl AsRef<str>, number: ArticleNumber) {
+        self.entry(name).read.insert(number);
+    }
+
+    /// An iterator over every entry in file order
+    pub fn iter(&self) -> std::slice::Iter<'_, NewsrcEntry> {
+        self.entries.iter()
+    }
+
+    /// An iterator over the subscribed groups in file order
+    pub fn subscribed(&self) -> impl Iterator<Item = &NewsrcEntry> {
+        self.entries.iter().filter(|entry| entry.subscribed)
+    }
+
+    /// The number of groups in the file
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no groups
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(&mut self, entry: NewsrcEntry) {
+        match self.index.get(&entry.name) {
+            // Duplicate entries are merged into the first occurrence
+            Some(&idx) => {
+                let existing = &mut self.entries[idx];
+                existing.subscribed |= entry.subscribed;
+                entry
+                    .read
+                    .ranges()
+                    .for_each(|range| existing.read.insert_range(range));
+            }
+            None => {
+                self.index.insert(entry.name.clone(), self.entries.len());
+                self.entries.push(entry);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Newsrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(options) = &self.options {
+            writeln!(f, "{}", options)?;
+        }
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Newsrc {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut newsrc = Newsrc::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            if i == 0 && line.starts_with("options") {
+                newsrc.options = Some(line.to_string());
+                continue;
+            }
+
+            let entry = line.parse::<NewsrcEntry>().map_err(|e| {
+                Error::Deserialization(format!("Invalid .newsrc line {} -- {}", i + 1, e))
+            })?;
+            newsrc.push(entry);
+        }
+
+        Ok(newsrc)
+    }
+}
+
+impl<'a> IntoIterator for &'a Newsrc {
+    type Item = &'a NewsrcEntry;
+    type IntoIter = std::slice::Iter<'a, NewsrcEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEWSRC: &str = "options -n all !alt.*\n\
+                          comp.lang.rust: 1-500,502,510-600\n\
+                          alt.test! 1-20\n\
+                          misc.test:\n\
+                          \n\
+                          news.software.nntp: 1-3\n";
+
+    #[test]
+    fn test_round_trip() {
+        let newsrc: Newsrc = NEWSRC.parse().unwrap();
+
+        assert_eq!(newsrc.options(), Some("options -n all !alt.*"));
+        assert_eq!(newsrc.len(), 4);
+        assert_eq!(
+            newsrc
+                .subscribed()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["comp.lang.rust", "misc.test", "news.software.nntp"]
+        );
+        assert!(newsrc.get("misc.test").unwrap().read.is_empty());
+        assert!(!newsrc.get("alt.test").unwrap().subscribed);
+
+        assert_eq!(newsrc.to_string(), NEWSRC.replace("\n\n", "\n"));
+    }
+
+    #[test]
+    fn test_unread() {
+        let newsrc: Newsrc = NEWSRC.parse().unwrap();
+        let group = Group {
+            number: 150,
+            low: 450,
+            high: 620,
+            name: "comp.lang.rust".to_string(),
+        };
+
+        let entry = newsrc.get("comp.lang.rust").unwrap();
+        assert_eq!(entry.unread(&group).to_string(), "501,503-509,601-620");
+        assert_eq!(entry.unread_count(&group), 28);
+
+        // A corrupt group or .newsrc must not overflow the count
+        let newsrc: Newsrc = "misc.test: 5-18446744073709551615\n".parse().unwrap();
+        let entry = newsrc.get("misc.test").unwrap();
+        let group = Group {
+            low: 0,
+            high: u64::MAX,
+            ..group
+        };
+        assert_eq!(entry.unread_count(&group), 5);
+        assert_eq!(
+            NewsrcEntry::new("misc.test", true).unread_count(&group),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_mutation() {
+        let mut newsrc: Newsrc = NEWSRC.parse().unwrap();
+        newsrc.subscribe("alt.new");
+        newsrc.mark_read("alt.new", 3);
+        newsrc.unsubscribe("comp.lang.rust");
+        newsrc.mark_read("misc.test", 1);
+
+        assert_eq!(
+            newsrc.to_string(),
+            "options -n all !alt.*\n\
+             comp.lang.rust! 1-500,502,510-600\n\
+             alt.test! 1-20\n\
+             misc.test: 1\n\
+             news.software.nntp: 1-3\n\
+             alt.new: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("comp.lang.rust 1-5\n".parse::<Newsrc>().is_err());
+        assert!("comp.lang.rust: 1-x\n".parse::<Newsrc>().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+const SCHEME: &str = "sha256";
+
+/// Generates [RFC 8315](https://tools.ietf.org/html/rfc8315) `Cancel-Lock` and `Cancel-Key`
+/// header values
+///
+/// A `Cancel-Lock` is added to an article when it is posted. Later, a cancel or superseding
+/// article that carries the matching `Cancel-Key` proves that it was sent by the original
+/// poster. Keys are derived from a secret and the Message-ID as recommended by
+/// [RFC 8315 Section 4](https://tools.ietf.org/html/rfc8315#section-4), so no state needs to be
+/// kept between posting and cancelling.
+///
+/// Only the `sha256` scheme is supported.
+///
+/// ```
+/// use brokaw::post::CancelLock;
+///
+/// let lock = CancelLock::new("correct horse battery staple");
+/// let mid = "<12345@example.com>";
+///
+/// let cancel_lock = lock.lock(mid);
+/// let cancel_key = lock.key(mid);
+/// assert!(cancel_lock.starts_with("sha256:"));
+/// assert!(CancelLock::verify(&cancel_key, &cancel_lock));
+/// assert!(!CancelLock::verify(&lock.key("<other@example.com>"), &cancel_lock));
+/// ```
+#[derive(Clone)]
+pub struct CancelLock {
+    secret: Vec<u8>,
+}
+
+impl CancelLock {
+    /// Create a generator from a secret
+    ///
+    /// The secret should be long, random, and kept private. Anyone who knows it can cancel
+    /// articles locked with it.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+        }
+    }
+
+    /// The `Cancel-Key` header value for the article with the given Message-ID
+    pub fn key(&self, message_id: &str) -> String {
+        format!("{}:{}", SCHEME, self.c_key(message_id))
+    }
+
+    /// The `Cancel-Lock` header value for the article with the given Message-ID
+    pub fn lock(&self, message_id: &str) -> String {
+        format!("{}:{}", SCHEME, lock_for(&self.c_key(message_id)))
+    }
+
+    /// Check whether any element of a `Cancel-Key` header unlocks any element of a
+    /// `Cancel-Lock` header
+    ///
+    /// Elements using a scheme other than `sha256` are ignored.
+    pub fn verify(cancel_key: &str, cancel_lock: &str) -> bool {
+        let locks = elements(cancel_lock).collect::<Vec<_>>();
+        elements(cancel_key).any(|key| locks.contains(&lock_for(key).as_str()))
+    }
+
+    fn c_key(&self, message_id: &str) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(message_id.as_bytes());
+        BASE64.encode(mac.finalize().into_bytes())
+    }
+}
+
+impl fmt::Debug for CancelLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelLock")
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The lock for a Base64 encoded key
+fn lock_for(c_key: &str) -> String {
+    BASE64.encode(Sha256::digest(c_key.as_bytes()))
+}
+
+/// The values of the `sha256` elements of a `Cancel-Lock` or `Cancel-Key` header
+fn elements(header: &str) -> impl Iterator<Item = &str> {
+    header.split_whitespace().filter_map(|element| {
+        let (scheme, value) = element.split_once(':')?;
+        if scheme.eq_ignore_ascii_case(SCHEME) {
+            Some(value)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_and_lock() {
+        let lock = CancelLock::new("secret");
+        let mid = "<12345@example.com>";
+
+        let key = lock.key(mid);
+        assert_eq!(key, "sha256:Ub1pY7AxmdkQOVEllQV/CaC9v9pe6EfjQmUs+7WjBMc=");
+        assert_eq!(
+            lock.lock(mid),
+            "sha256:vqxmrAjAqhoM6NIJhYKTvYuxWxPfqTtP606ROlxNQr4="
+        );
+        assert_ne!(CancelLock::new("other").key(mid), key);
+    }
+
+    #[test]
+    fn test_verify_multiple_elements() {
+        let lock = CancelLock::new("secret");
+        let mid = "<12345@example.com>";
+
+        let cancel_lock = format!("sha1:bNXHc6ohSmeHaRHHW56BIWZJt+4= {}", lock.lock(mid));
+        let cancel_key = format!("sha1:aaaBBBcccDDDeeeFFF {}", lock.key(mid));
+        assert!(CancelLock::verify(&cancel_key, &cancel_lock));
+        assert!(!CancelLock::verify("sha1:aaaBBBcccDDDeeeFFF", &cancel_lock));
+        assert!(!CancelLock::verify(&cancel_key, ""));
+    }
+}
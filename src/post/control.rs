@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// The value of a `Control` header
+///
+/// See [RFC 5537 Section 5](https://tools.ietf.org/html/rfc5537#section-5) for the semantics of
+/// each control message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Control {
+    /// Withdraw the article with the given Message-ID
+    Cancel(String),
+    /// Create a newsgroup or change its moderation status
+    Newgroup {
+        /// The name of the group
+        group: String,
+        /// Whether the group is moderated
+        moderated: bool,
+    },
+    /// Remove a newsgroup
+    Rmgroup(String),
+    /// Announce the authoritative list of groups in a hierarchy
+    Checkgroups {
+        /// The hierarchies the list applies to (e.g. `example.*`)
+        scope: Option<String>,
+        /// A serial number that must increase with each checkgroups message for the scope
+        serial: Option<u64>,
+    },
+}
+
+impl Control {
+    /// The Message-ID this control message refers to, if any
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Control::Cancel(message_id) => Some(message_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Control::Cancel(message_id) => write!(f, "cancel {}", message_id),
+            Control::Newgroup { group, moderated } => {
+                write!(f, "newgroup {}", group)?;
+                if *moderated {
+                    write!(f, " moderated")?;
+                }
+                Ok(())
+            }
+            Control::Rmgroup(group) => write!(f, "rmgroup {}", group),
+            Control::Checkgroups { scope, serial } => {
+                write!(f, "checkgroups")?;
+                if let Some(scope) = scope {
+                    write!(f, " {}", scope)?;
+                }
+                if let Some(serial) = serial {
+                    write!(f, " #{}", serial)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
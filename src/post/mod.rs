@@ -0,0 +1,513 @@
+//! Articles are assembled with an [`ArticleBuilder`] and submitted with
+//! [`NntpClient::post`](crate::client::NntpClient::post).
+//!
+//! * Control articles (cancel, newgroup, rmgroup, checkgroups) are built from a [`Control`]
+//! * Superseding articles carry a `Supersedes` header naming the article they replace
+//! * A [`CancelLock`] adds [RFC 8315](https://tools.ietf.org/html/rfc8315) `Cancel-Lock` headers
+//!   to new articles and the matching `Cancel-Key` to cancels and supersedes, so servers can
+//!   verify that only the original poster withdraws an article
+//!
+//! # Example
+//!
+//! ```no_run
+//! use brokaw::client::ClientConfig;
+//! use brokaw::post::{ArticleBuilder, CancelLock};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut client = ClientConfig::default().connect(("news.example.com", 119))?;
+//!     let lock = CancelLock::new("correct horse battery staple");
+//!
+//!     let article = ArticleBuilder::new()
+//!         .from("Bot <bot@example.com>")
+//!         .newsgroups("example.test")
+//!         .subject("Status")
+//!         .message_id("<status.1@example.com>")
+//!         .body("All systems nominal\n")
+//!         .cancel_lock(lock.clone())
+//!         .build()?;
+//!     client.post(article)?;
+//!
+//!     let cancel = ArticleBuilder::cancel("<status.1@example.com>")
+//!         .from("Bot <bot@example.com>")
+//!         .newsgroups("example.test")
+//!         .cancel_lock(lock)
+//!         .build()?;
+//!     client.post(cancel)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt::Write;
+
+/// RFC 8315 Cancel-Lock and Cancel-Key generation
+mod cancel_lock;
+
+/// `Control` header values
+mod control;
+
+#[doc(inline)]
+pub use cancel_lock::CancelLock;
+#[doc(inline)]
+pub use control::Control;
+
+/// An error building an article
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ArticleError {
+    /// A header required by [RFC 5536](https://tools.ietf.org/html/rfc5536#section-3.1) is
+    /// missing
+    #[error("Missing required header `{0}`")]
+    MissingHeader(&'static str),
+    /// A Message-ID is not of the form `<id@domain>`
+    #[error("Invalid message-id `{0}`")]
+    InvalidMessageId(String),
+    /// A header name or value contains characters that are not permitted
+    #[error("Invalid header `{0}`")]
+    InvalidHeader(String),
+    /// A header that must be set with a dedicated method (e.g.
+    /// [`ArticleBuilder::control`]) was set with [`ArticleBuilder::header`]
+    #[error("Header `{0}` cannot be set directly")]
+    ReservedHeader(String),
+}
+
+/// Headers that are generated from the builder's other settings so they are validated
+const RESERVED_HEADERS: [&str; 4] = ["Control", "Supersedes", "Cancel-Lock", "Cancel-Key"];
+
+/// A builder for articles to submit with `POST`
+///
+/// `From` and `Newsgroups` are required, as is `Subject` unless the article is a control
+/// message (which defaults to `cmsg <control>`). Servers fill in `Date`, `Path`, and, if it is
+/// not set, `Message-ID`.
+///
+/// Headers are written in the order they were first set. Setting a header again replaces its
+/// value.
+///
+/// ```
+/// use brokaw::post::ArticleBuilder;
+///
+/// let article = ArticleBuilder::new()
+///     .from("Alice <alice@example.com>")
+///     .newsgroups("example.test")
+///     .subject("Hello")
+///     .header("Approved", "moderator@example.com")
+///     .body("Hi!\n")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(article).unwrap(),
+///     "From: Alice <alice@example.com>\r\n\
+///      Newsgroups: example.test\r\n\
+///      Subject: Hello\r\n\
+///      Approved: moderator@example.com\r\n\
+///      \r\n\
+///      Hi!\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArticleBuilder {
+    headers: Vec<(String, String)>,
+    control: Option<Control>,
+    supersedes: Option<String>,
+    body: String,
+    cancel_lock: Option<CancelLock>,
+}
+
+impl ArticleBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A control article that cancels the article with the given Message-ID
+    ///
+    /// The `Newsgroups` header should match that of the cancelled article.
+    pub fn cancel(message_id: impl AsRef<str>) -> Self {
+        let message_id = message_id.as_ref();
+        let mut builder = Self::new();
+        builder
+            .control(Control::Cancel(message_id.to_string()))
+            .body(format!("Cancel {}\n", message_id));
+        builder
+    }
+
+    /// A control article that creates a group
+    ///
+    /// The body lists the group and its description in the conventional
+    /// `For your newsgroups file:` format. Hierarchy administrators will usually need to add an
+    /// `Approved` header.
+    pub fn newgroup(group: impl AsRef<str>, description: impl AsRef<str>, moderated: bool) -> Self {
+        let group = group.as_ref();
+        let mut builder = Self::new();
+        builder
+            .control(Control::Newgroup {
+                group: group.to_string(),
+                moderated,
+            })
+            .newsgroups(group)
+            .body(format!(
+                "For your newsgroups file:\n{}\t{}\n",
+                group,
+                description.as_ref()
+            ));
+        builder
+    }
+
+    /// A control article that removes a group
+    pub fn rmgroup(group: impl AsRef<str>) -> Self {
+        let group = group.as_ref();
+        let mut builder = Self::new();
+        builder
+            .control(Control::Rmgroup(group.to_string()))
+            .newsgroups(group)
+            .body(format!("Please remove {}\n", group));
+        builder
+    }
+
+    /// A control article listing every group and its description in a hierarchy
+    ///
+    /// Use [`control`](Self::control) to set a serial number.
+    pub fn checkgroups<G, D>(scope: Option<&str>, groups: impl IntoIterator<Item = (G, D)>) -> Self
+    where
+        G: AsRef<str>,
+        D: AsRef<str>,
+    {
+        let mut body = String::new();
+        for (group, description) in groups {
+            let _ = writeln!(body, "{}\t{}", group.as_ref(), description.as_ref());
+        }
+
+        let mut builder = Self::new();
+        builder
+            .control(Control::Checkgroups {
+                scope: scope.map(ToString::to_string),
+                serial: None,
+            })
+            .body(body);
+        builder
+    }
+
+    /// Set the `From` header
+    pub fn from(&mut self, from: impl AsRef<str>) -> &mut Self {
+        self.header("From", from)
+    }
+
+    /// Set the `Newsgroups` header, a comma separated list of groups
+    pub fn newsgroups(&mut self, newsgroups: impl AsRef<str>) -> &mut Self {
+        self.header("Newsgroups", newsgroups)
+    }
+
+    /// Set the `Subject` header
+    pub fn subject(&mut self, subject: impl AsRef<str>) -> &mut Self {
+        self.header("Subject", subject)
+    }
+
+    /// Set the `Message-ID` header
+    ///
+    /// A Message-ID is required to add a `Cancel-Lock` to the article.
+    pub fn message_id(&mut self, message_id: impl AsRef<str>) -> &mut Self {
+        self.header("Message-ID", message_id)
+    }
+
+    /// Set the `Control` header
+    pub fn control(&mut self, control: Control) -> &mut Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Set the `Supersedes` header, replacing the article with the given Message-ID
+    pub fn supersedes(&mut self, message_id: impl AsRef<str>) -> &mut Self {
+        self.supersedes = Some(message_id.as_ref().to_string());
+        self
+    }
+
+    /// Add `Cancel-Lock` and `Cancel-Key` headers generated by `lock`
+    ///
+    /// * A `Cancel-Lock` for the article's own Message-ID so it can be cancelled later. This
+    ///   requires a [`message_id`](Self::message_id) unless the article is a cancel
+    /// * A `Cancel-Key` for the cancelled or superseded article, if any
+    pub fn cancel_lock(&mut self, lock: CancelLock) -> &mut Self {
+        self.cancel_lock = Some(lock);
+        self
+    }
+
+    /// Set a header, replacing any previous value
+    ///
+    /// `Control`, `Supersedes`, `Cancel-Lock`, and `Cancel-Key` must be set with
+    /// [`control`](Self::control), [`supersedes`](Self::supersedes), and
+    /// [`cancel_lock`](Self::cancel_lock). Setting them here causes [`build`](Self::build) to
+    /// fail with [`ArticleError::ReservedHeader`].
+    pub fn header(&mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> &mut Self {
+        let (name, value) = (name.as_ref(), value.as_ref().to_string());
+        match self
+            .headers
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some((_, v)) => *v = value,
+            None => self.headers.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    /// Set the body
+    ///
+    /// Lines may end in either LF or CRLF. The body will be dot-stuffed when it is posted.
+    pub fn body(&mut self, body: impl AsRef<str>) -> &mut Self {
+        self.body = body.as_ref().to_string();
+        self
+    }
+
+    /// Build the article, ready to be passed to
+    /// [`NntpClient::post`](crate::client::NntpClient::post)
+    pub fn build(&self) -> Result<Vec<u8>, ArticleError> {
+        let mut headers = self.headers.clone();
+        let get = |name: &str| {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+
+        if let Some((name, _)) = self.headers.iter().find(|(name, _)| {
+            RESERVED_HEADERS
+                .iter()
+                .any(|reserved| name.eq_ignore_ascii_case(reserved))
+        }) {
+            return Err(ArticleError::ReservedHeader(name.clone()));
+        }
+        for required in &["From", "Newsgroups"] {
+            get(required).ok_or(ArticleError::MissingHeader(required))?;
+        }
+        if let Some(control) = &self.control {
+            if let Some(target) = control.target() {
+                validate_message_id(target)?;
+            }
+            if get("Subject").is_none() {
+                headers.push(("Subject".to_string(), format!("cmsg {}", control)));
+            }
+            headers.push(("Control".to_string(), control.to_string()));
+        } else {
+            get("Subject").ok_or(ArticleError::MissingHeader("Subject"))?;
+        }
+        if let Some(message_id) = &self.supersedes {
+            validate_message_id(message_id)?;
+            headers.push(("Supersedes".to_string(), message_id.clone()));
+        }
+
+        let message_id = get("Message-ID");
+        if let Some(message_id) = message_id {
+            validate_message_id(message_id)?;
+        }
+        if let Some(lock) = &self.cancel_lock {
+            let cancelled = self.control.as_ref().and_then(Control::target);
+            // A cancel is never cancelled itself, so it doesn't need a lock of its own
+            match message_id {
+                Some(message_id) => {
+                    headers.push(("Cancel-Lock".to_string(), lock.lock(message_id)))
+                }
+                None if cancelled.is_none() => {
+                    return Err(ArticleError::MissingHeader("Message-ID"))
+                }
+                None => {}
+            }
+
+            if let Some(target) = cancelled.or(self.supersedes.as_deref()) {
+                headers.push(("Cancel-Key".to_string(), lock.key(target)));
+            }
+        }
+
+        let mut article = String::new();
+        for (name, value) in headers {
+            validate_header(&name, &value)?;
+            let _ = write!(article, "{}: {}\r\n", name, value);
+        }
+        article.push_str("\r\n");
+        article.push_str(&self.body);
+
+        Ok(article.into_bytes())
+    }
+}
+
+/// Check that a Message-ID is of the form `<id@domain>`
+fn validate_message_id(message_id: &str) -> Result<(), ArticleError> {
+    let valid = message_id
+        .strip_prefix('<')
+        .and_then(|id| id.strip_suffix('>'))
+        .and_then(|id| id.split_once('@'))
+        .is_some_and(|(left, right)| {
+            !left.is_empty()
+                && !right.is_empty()
+                && !right.contains('@')
+                && message_id[1..message_id.len() - 1]
+                    .chars()
+                    .all(|c| c.is_ascii_graphic() && c != '<' && c != '>')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ArticleError::InvalidMessageId(message_id.to_string()))
+    }
+}
+
+/// Check that a header cannot inject other headers or terminate the header section
+fn validate_header(name: &str, value: &str) -> Result<(), ArticleError> {
+    let name_ok = !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic() && c != ':');
+    let value_ok = !value.contains(['\r', '\n']);
+
+    if name_ok && value_ok {
+        Ok(())
+    } else {
+        Err(ArticleError::InvalidHeader(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(builder: &ArticleBuilder) -> String {
+        String::from_utf8(builder.build().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_cancel() {
+        let lock = CancelLock::new("secret");
+        let article = build(
+            ArticleBuilder::cancel("<1@example.com>")
+                .from("bot@example.com")
+                .newsgroups("example.test")
+                .message_id("<cancel.1@example.com>")
+                .cancel_lock(lock.clone()),
+        );
+
+        assert_eq!(
+            article,
+            format!(
+                "From: bot@example.com\r\n\
+                 Newsgroups: example.test\r\n\
+                 Message-ID: <cancel.1@example.com>\r\n\
+                 Subject: cmsg cancel <1@example.com>\r\n\
+                 Control: cancel <1@example.com>\r\n\
+                 Cancel-Lock: {}\r\n\
+                 Cancel-Key: {}\r\n\
+                 \r\n\
+                 Cancel <1@example.com>\n",
+                lock.lock("<cancel.1@example.com>"),
+                lock.key("<1@example.com>"),
+            )
+        );
+    }
+
+    #[test]
+    fn test_supersedes() {
+        let lock = CancelLock::new("secret");
+        let article = build(
+            ArticleBuilder::new()
+                .from("bot@example.com")
+                .newsgroups("example.test")
+                .subject("Status (updated)")
+                .message_id("<status.2@example.com>")
+                .supersedes("<status.1@example.com>")
+                .cancel_lock(lock.clone()),
+        );
+
+        assert!(article.contains("Supersedes: <status.1@example.com>\r\n"));
+        assert!(article.contains(&format!(
+            "Cancel-Key: {}\r\n",
+            lock.key("<status.1@example.com>")
+        )));
+        assert!(article.contains(&format!(
+            "Cancel-Lock: {}\r\n",
+            lock.lock("<status.2@example.com>")
+        )));
+    }
+
+    #[test]
+    fn test_group_control() {
+        let newgroup = build(
+            ArticleBuilder::newgroup("example.new", "A new group", true).from("admin@example.com"),
+        );
+        assert!(newgroup.starts_with(
+            "Newsgroups: example.new\r\n\
+             From: admin@example.com\r\n\
+             Subject: cmsg newgroup example.new moderated\r\n\
+             Control: newgroup example.new moderated\r\n"
+        ));
+        assert!(newgroup.ends_with("\r\nFor your newsgroups file:\nexample.new\tA new group\n"));
+
+        let checkgroups = build(
+            ArticleBuilder::checkgroups(
+                Some("example.*"),
+                vec![("example.a", "Group A"), ("example.b", "Group B")],
+            )
+            .from("admin@example.com")
+            .newsgroups("example.admin")
+            .control(Control::Checkgroups {
+                scope: Some("example.*".to_string()),
+                serial: Some(42),
+            }),
+        );
+        assert!(checkgroups.contains("Control: checkgroups example.* #42\r\n"));
+        assert!(checkgroups.ends_with("\r\n\r\nexample.a\tGroup A\nexample.b\tGroup B\n"));
+
+        assert_eq!(
+            Control::Rmgroup("example.old".to_string()).to_string(),
+            "rmgroup example.old"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut builder = ArticleBuilder::new();
+        builder.from("a@example.com").subject("hi");
+        assert_eq!(
+            builder.build(),
+            Err(ArticleError::MissingHeader("Newsgroups"))
+        );
+
+        builder
+            .newsgroups("example.test")
+            .cancel_lock(CancelLock::new("s"));
+        assert_eq!(
+            builder.build(),
+            Err(ArticleError::MissingHeader("Message-ID"))
+        );
+
+        builder.message_id("no-angle-brackets@example.com");
+        assert!(matches!(
+            builder.build(),
+            Err(ArticleError::InvalidMessageId(_))
+        ));
+
+        builder
+            .message_id("<1@example.com>")
+            .subject("hi\r\nControl: rmgroup example.test");
+        assert_eq!(
+            builder.build(),
+            Err(ArticleError::InvalidHeader("Subject".to_string()))
+        );
+
+        assert!(ArticleBuilder::cancel("<bad")
+            .from("a@example.com")
+            .newsgroups("example.test")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_reserved_headers() {
+        for name in &["Control", "supersedes", "Cancel-Lock", "CANCEL-KEY"] {
+            let mut builder = ArticleBuilder::new();
+            builder
+                .from("a@example.com")
+                .newsgroups("example.test")
+                .subject("hi")
+                .header(name, "cancel <bad");
+            assert_eq!(
+                builder.build(),
+                Err(ArticleError::ReservedHeader(name.to_string()))
+            );
+        }
+    }
+}
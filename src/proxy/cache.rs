@@ -0,0 +1,183 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::atomic;
+use crate::raw::connection::read_raw_response;
+use crate::types::prelude::*;
+
+/// An on-disk cache of article responses keyed by Message-ID
+///
+/// Articles are immutable once posted, so `ARTICLE`, `HEAD`, and `BODY` responses can be cached
+/// indefinitely. Each response is stored in wire format under a directory for its response code.
+///
+/// Message-IDs may contain characters that are not valid in file names, so files are named after
+/// a hash of the Message-ID. The Message-ID is stored in the response itself and checked on read
+/// so hash collisions result in a cache miss rather than the wrong article.
+#[derive(Clone, Debug)]
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub(crate) fn new(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns true if responses with this code may be cached
+    pub(crate) fn is_cacheable(code: ResponseCode) -> bool {
+        matches!(
+            code,
+            ResponseCode::Known(Kind::Article)
+                | ResponseCode::Known(Kind::Head)
+                | ResponseCode::Known(Kind::Body)
+        )
+    }
+
+    /// Look up a cached response
+    ///
+    /// The article number in the returned response is always 0 as the article may not be in the
+    /// currently selected group
+    pub(crate) fn get(&self, kind: Kind, message_id: &str) -> io::Result<Option<RawResponse>> {
+        let path = self.path(kind, message_id);
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let resp = read_raw_response(&mut io::BufReader::new(file), true)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        if response_message_id(&resp) == Some(message_id) {
+            trace!("Cache hit for {} {}", u16::from(kind), message_id);
+            Ok(Some(resp))
+        } else {
+            trace!("Cache collision for {} {}", u16::from(kind), message_id);
+            Ok(None)
+        }
+    }
+
+    /// Store a response
+    ///
+    /// Responses that are not cacheable or lack a Message-ID are ignored
+    pub(crate) fn put(&self, resp: &RawResponse) -> io::Result<()> {
+        let message_id = match response_message_id(resp) {
+            Some(id) if Self::is_cacheable(resp.code()) && resp.has_data_blocks() => id,
+            _ => return Ok(()),
+        };
+
+        let kind = match resp.code() {
+            ResponseCode::Known(kind) => kind,
+            ResponseCode::Unknown(_) => return Ok(()),
+        };
+        let path = self.path(kind, message_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file and rename it so readers never see partial responses.
+        // Sessions run on their own threads so every write gets its own temporary file.
+        let tmp = atomic::temp_path(&path);
+        let res = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+            .and_then(|file| {
+                let mut file = io::BufWriter::new(file);
+                write!(file, "{} 0 {}\r\n", resp.code(), message_id)?;
+                if let Some(data_blocks) = resp.data_blocks() {
+                    file.write_all(data_blocks.payload())?;
+                }
+                file.flush()
+            })
+            .and_then(|_| fs::rename(&tmp, &path));
+
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        res
+    }
+
+    fn path(&self, kind: Kind, message_id: &str) -> PathBuf {
+        self.dir
+            .join(u16::from(kind).to_string())
+            .join(format!("{:016x}", fnv1a(message_id.as_bytes())))
+    }
+}
+
+/// The Message-ID in the first line of an article response
+///
+/// ```abnf
+/// response-220-content = "220" SP article-number SP message-id
+/// ```
+pub(crate) fn response_message_id(resp: &RawResponse) -> Option<&str> {
+    std::str::from_utf8(resp.first_line())
+        .ok()?
+        .split_whitespace()
+        .nth(2)
+        .filter(|id| id.starts_with('<') && id.ends_with('>'))
+}
+
+/// 64-bit FNV-1a
+///
+/// Unlike `DefaultHasher` the output is stable across Rust releases, which matters as the hash is
+/// persisted in file names.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_concurrent_put() {
+        let dir = std::env::temp_dir().join(format!("brokaw-cache-{}", std::process::id()));
+        let cache = Arc::new(DiskCache::new(&dir).unwrap());
+        let resp = Arc::new(RawResponse::with_data_blocks(
+            Kind::Article,
+            "1 <1@example.com>",
+            "Subject: test\r\n\r\nbody\r\n",
+        ));
+
+        let writers = (0..8)
+            .map(|_| {
+                let (cache, resp) = (cache.clone(), resp.clone());
+                thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache.put(&resp).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let cached = cache
+            .get(Kind::Article, "<1@example.com>")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cached.data_blocks().unwrap().payload(),
+            resp.data_blocks().unwrap().payload()
+        );
+
+        // No temporary files are left behind
+        let files = fs::read_dir(dir.join("220")).unwrap().count();
+        assert_eq!(files, 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,466 @@
+//! The proxy accepts reader connections and multiplexes them onto a bounded pool of upstream
+//! [`NntpConnection`](crate::raw::connection::NntpConnection)s. This allows many downloaders to
+//! share a provider that limits the number of concurrent connections.
+//!
+//! * `ARTICLE`, `HEAD`, and `BODY` responses are cached on disk by Message-ID
+//! * Articles requested by Message-ID are served from the cache when possible
+//! * Other reader commands are passed through to the upstream server
+//! * Posting and transit commands (`POST`, `IHAVE`, streaming) are not supported
+//!
+//! Each reader's selected group and article pointer are tracked by the proxy and replayed onto
+//! whichever upstream connection serves the reader's next command.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::net::TcpListener;
+//!
+//! use brokaw::client::ClientConfig;
+//! use brokaw::proxy::ProxyConfig;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut upstream = ClientConfig::default();
+//!     upstream.authinfo_user_pass("newsreader", "hunter2");
+//!
+//!     let proxy = ProxyConfig::default()
+//!         .upstream(upstream)
+//!         .max_connections(8)
+//!         .cache_dir(Some("/var/cache/brokaw"))
+//!         .build(("news.example.com", 119))?;
+//!
+//!     proxy.serve(TcpListener::bind("127.0.0.1:1119")?)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use log::*;
+
+use crate::client::ClientConfig;
+use crate::error::Result;
+
+/// On-disk article cache
+mod cache;
+
+/// Upstream connection pooling
+mod pool;
+
+/// Reader sessions
+mod session;
+
+use cache::DiskCache;
+use pool::Pool;
+use session::Session;
+
+/// Configuration for a [`Proxy`]
+#[derive(Clone, Debug)]
+pub struct ProxyConfig {
+    upstream: ClientConfig,
+    max_connections: usize,
+    cache_dir: Option<PathBuf>,
+    authinfo: Option<(String, String)>,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            upstream: ClientConfig::default(),
+            max_connections: 4,
+            cache_dir: None,
+            authinfo: None,
+        }
+    }
+}
+
+impl ProxyConfig {
+    /// The configuration used to connect to the upstream server
+    ///
+    /// Authentication and TLS for the upstream server are configured here. Any initial group
+    /// will be replaced by the groups selected by readers.
+    pub fn upstream(&mut self, config: ClientConfig) -> &mut Self {
+        self.upstream = config;
+        self
+    }
+
+    /// The maximum number of concurrent upstream connections (default 4)
+    ///
+    /// Readers block while all upstream connections are in use.
+    pub fn max_connections(&mut self, max: usize) -> &mut Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// The directory to cache articles in
+    ///
+    /// If this is set to None (the default) articles will not be cached
+    pub fn cache_dir(&mut self, dir: Option<impl Into<PathBuf>>) -> &mut Self {
+        self.cache_dir = dir.map(Into::into);
+        self
+    }
+
+    /// Require readers to authenticate with AUTHINFO USER/PASS
+    ///
+    /// If this is not set, readers may send any credentials (or none at all).
+    pub fn authinfo_user_pass(
+        &mut self,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> &mut Self {
+        self.authinfo = Some((username.as_ref().to_string(), password.as_ref().to_string()));
+        self
+    }
+
+    /// Create a proxy for the upstream server at `addr`
+    ///
+    /// No upstream connections are opened until a reader needs one.
+    pub fn build(&self, addr: impl ToSocketAddrs) -> Result<Proxy> {
+        let addrs = addr.to_socket_addrs()?.collect::<Vec<SocketAddr>>();
+        if addrs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No upstream address").into());
+        }
+
+        let cache = self.cache_dir.as_ref().map(DiskCache::new).transpose()?;
+
+        Ok(Proxy {
+            shared: Arc::new(Shared {
+                pool: Pool::new(addrs, self.upstream.clone(), self.max_connections),
+                cache,
+                authinfo: self.authinfo.clone(),
+            }),
+        })
+    }
+}
+
+/// State shared between reader sessions
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pool: Pool,
+    cache: Option<DiskCache>,
+    authinfo: Option<(String, String)>,
+}
+
+/// A caching NNTP proxy created by [`ProxyConfig::build`]
+///
+/// Proxies are cheap to clone, clones share the same upstream connections and cache.
+#[derive(Clone, Debug)]
+pub struct Proxy {
+    shared: Arc<Shared>,
+}
+
+impl Proxy {
+    /// Accept readers forever, serving each one on its own thread
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        info!("Proxy listening on {}", listener.local_addr()?);
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Failed to accept connection -- {}", e);
+                    continue;
+                }
+            };
+
+            let proxy = self.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+                debug!("Accepted reader {:?}", peer);
+                if let Err(e) = proxy.handle(stream) {
+                    debug!("Reader {:?} failed -- {}", peer, e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Serve a single reader on the current thread until it disconnects
+    pub fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        Session::new(&self.shared, stream)?.run()
+    }
+
+    /// The number of open upstream connections
+    pub fn upstream_connections(&self) -> usize {
+        self.shared.pool.open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::types::command::{self as cmd, AnyCommand, Decode, NntpCommand};
+    use crate::types::prelude::*;
+
+    /// A stand-in upstream server hosting `misc.test` with articles 1 through 3, and the empty
+    /// group `misc.empty`
+    ///
+    /// Returns the address and the number of `ARTICLE` commands the server has received
+    fn upstream() -> (SocketAddr, Arc<AtomicUsize>) {
+        let (addr, articles, _) = expiring_upstream();
+        (addr, articles)
+    }
+
+    /// Like [`upstream`], also returning a flag that expires article 3 when set
+    fn expiring_upstream() -> (SocketAddr, Arc<AtomicUsize>, Arc<AtomicBool>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let articles = Arc::new(AtomicUsize::new(0));
+        let expired = Arc::new(AtomicBool::new(false));
+
+        let (counter, expired_flag) = (articles.clone(), expired.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (counter, expired) = (counter.clone(), expired_flag.clone());
+                thread::spawn(move || serve_upstream(stream.unwrap(), &counter, &expired));
+            }
+        });
+
+        (addr, articles, expired)
+    }
+
+    fn serve_upstream(stream: TcpStream, articles: &AtomicUsize, expired: &AtomicBool) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut group_selected = false;
+        let mut current = None;
+        let exists =
+            |n: ArticleNumber| (1..=3).contains(&n) && !(n == 3 && expired.load(Ordering::SeqCst));
+
+        let article = |n: ArticleNumber| {
+            RawResponse::with_data_blocks(
+                Kind::Article,
+                format!("{} <{}@example.com>", n, n),
+                format!("Subject: article {}\r\n\r\nbody of {}\r\n", n, n),
+            )
+        };
+        let stat = |n: ArticleNumber| {
+            RawResponse::new(Kind::ArticleExists, format!("{} <{}@example.com>", n, n))
+        };
+        let no_article = RawResponse::new(Kind::NoArticleWithNumber, "No such article");
+
+        writer.write_all(b"200 stand-in ready\r\n").unwrap();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).unwrap() == 0 {
+                return;
+            }
+
+            // A LIST keyword the decoder does not know
+            if line == b"LIST HEADERS\r\n" {
+                RawResponse::with_data_blocks(Kind::List, "Headers:", "Subject\r\n:bytes\r\n")
+                    .write_response(&mut writer)
+                    .unwrap();
+                continue;
+            }
+
+            let resp = match AnyCommand::decode(&line).unwrap() {
+                AnyCommand::Capabilities(_) => RawResponse::with_data_blocks(
+                    Kind::Capabilities,
+                    "Capability list:",
+                    "VERSION 2\r\nREADER\r\nIHAVE\r\nOVER\r\n",
+                ),
+                AnyCommand::Group(cmd::Group(name)) if name == "misc.test" => {
+                    group_selected = true;
+                    current = Some(1);
+                    RawResponse::new(Kind::GroupSelected, "3 1 3 misc.test")
+                }
+                AnyCommand::Group(cmd::Group(name)) if name == "misc.empty" => {
+                    group_selected = true;
+                    current = None;
+                    RawResponse::new(Kind::GroupSelected, "0 1 0 misc.empty")
+                }
+                AnyCommand::Group(_) => RawResponse::new(Kind::NoSuchNewsgroup, "No such group"),
+                AnyCommand::Article(cmd::Article::MessageId(id)) => {
+                    articles.fetch_add(1, Ordering::SeqCst);
+                    match id.trim_matches(|c| c == '<' || c == '>').split('@').next() {
+                        Some(n) => article(n.parse().unwrap()),
+                        None => no_article.clone(),
+                    }
+                }
+                AnyCommand::Article(_) | AnyCommand::Stat(_) | AnyCommand::Next(_)
+                    if !group_selected =>
+                {
+                    RawResponse::new(Kind::NoNewsgroupSelected, "No group")
+                }
+                AnyCommand::Article(cmd::Article::Number(n)) if exists(n) => {
+                    articles.fetch_add(1, Ordering::SeqCst);
+                    current = Some(n);
+                    article(n)
+                }
+                AnyCommand::Article(cmd::Article::Current) => match current {
+                    Some(n) => {
+                        articles.fetch_add(1, Ordering::SeqCst);
+                        article(n)
+                    }
+                    None => RawResponse::new(Kind::InvalidCurrentArticleNumber, "No current"),
+                },
+                AnyCommand::Stat(cmd::Stat::Number(n)) if exists(n) => {
+                    current = Some(n);
+                    stat(n)
+                }
+                AnyCommand::Next(_) => match current {
+                    Some(n) if n < 3 => {
+                        current = Some(n + 1);
+                        stat(n + 1)
+                    }
+                    _ => RawResponse::new(Kind::NoNextArticle, "No next article"),
+                },
+                AnyCommand::Quit(_) => {
+                    writer.write_all(b"205 Bye\r\n").unwrap();
+                    return;
+                }
+                _ => no_article.clone(),
+            };
+            resp.write_response(&mut writer).unwrap();
+        }
+    }
+
+    fn proxy(config: &ProxyConfig, upstream: SocketAddr) -> SocketAddr {
+        let proxy = config.build(upstream).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || proxy.serve(listener));
+        addr
+    }
+
+    #[test]
+    fn test_article_cache() {
+        let (upstream, fetched) = upstream();
+        let cache_dir = std::env::temp_dir().join(format!("brokaw-proxy-{}", std::process::id()));
+        let addr = proxy(ProxyConfig::default().cache_dir(Some(&cache_dir)), upstream);
+
+        let id = "<2@example.com>".to_string();
+        for _ in 0..2 {
+            let mut client = ClientConfig::default().connect(addr).unwrap();
+            let article = client.article(cmd::Article::MessageId(id.clone())).unwrap();
+            assert_eq!(article.message_id(), id);
+            assert_eq!(article.to_text().unwrap().body(), &["body of 2"]);
+            assert!(client.capabilities().get("IHAVE").is_none());
+            assert!(client.capabilities().get("OVER").is_some());
+        }
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+        // Articles fetched by number are cached by their Message-ID
+        let mut client = ClientConfig::default().connect(addr).unwrap();
+        client.select_group("misc.test").unwrap();
+        client.article(cmd::Article::Number(3)).unwrap();
+        client
+            .article(cmd::Article::MessageId("<3@example.com>".to_string()))
+            .unwrap();
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_shared_upstream_state() {
+        let (upstream, _) = upstream();
+        let addr = proxy(ProxyConfig::default().max_connections(1), upstream);
+
+        let mut a = ClientConfig::default().connect(addr).unwrap();
+        let mut b = ClientConfig::default().connect(addr).unwrap();
+
+        a.select_group("misc.test").unwrap();
+        a.article(cmd::Article::Number(3)).unwrap();
+
+        // b has not selected a group even though the upstream connection has
+        let resp = b.command(cmd::Article::Number(1)).unwrap();
+        assert_eq!(resp.code(), Kind::NoNewsgroupSelected.into());
+
+        b.select_group("misc.test").unwrap();
+        assert_eq!(b.next_article().unwrap().unwrap().number, 2);
+
+        // a's article pointer is restored before reading the current article
+        let article = a.article(cmd::Article::Current).unwrap();
+        assert_eq!(article.number(), 3);
+    }
+
+    #[test]
+    fn test_invalid_article_pointer() {
+        let (upstream, fetched, expired) = expiring_upstream();
+        let addr = proxy(ProxyConfig::default().max_connections(1), upstream);
+
+        let mut a = ClientConfig::default().connect(addr).unwrap();
+        let mut b = ClientConfig::default().connect(addr).unwrap();
+
+        a.select_group("misc.test").unwrap();
+        a.article(cmd::Article::Number(3)).unwrap();
+        b.select_group("misc.test").unwrap();
+        assert_eq!(b.next_article().unwrap().unwrap().number, 2);
+        let fetched_before = fetched.load(Ordering::SeqCst);
+
+        // a's article expires, the upstream pointer now belongs to b and must not be used
+        expired.store(true, Ordering::SeqCst);
+        let resp = a.command(cmd::Article::Current).unwrap();
+        assert_eq!(resp.code(), Kind::InvalidCurrentArticleNumber.into());
+        let resp = a.command(cmd::Next).unwrap();
+        assert_eq!(resp.code(), Kind::InvalidCurrentArticleNumber.into());
+        assert_eq!(fetched.load(Ordering::SeqCst), fetched_before);
+
+        // A reader without an article pointer is answered by the proxy
+        a.select_group("misc.empty").unwrap();
+        let resp = a.command(cmd::Article::Current).unwrap();
+        assert_eq!(resp.code(), Kind::InvalidCurrentArticleNumber.into());
+        assert_eq!(fetched.load(Ordering::SeqCst), fetched_before);
+
+        // b is unaffected
+        assert_eq!(b.article(cmd::Article::Current).unwrap().number(), 2);
+    }
+
+    #[test]
+    fn test_unknown_list_keyword() {
+        let (upstream, _) = upstream();
+        let addr = proxy(&ProxyConfig::default(), upstream);
+
+        struct ListHeaders;
+        impl fmt::Display for ListHeaders {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "LIST HEADERS")
+            }
+        }
+        impl NntpCommand for ListHeaders {}
+
+        let client = ClientConfig::default().connect(addr).unwrap();
+        let resp = client.into_conn().command(&ListHeaders).unwrap();
+        assert_eq!(resp.code(), Kind::List.into());
+        assert_eq!(
+            resp.data_blocks().unwrap().payload(),
+            b"Subject\r\n:bytes\r\n.\r\n"
+        );
+    }
+
+    #[test]
+    fn test_reader_authentication() {
+        let (upstream, _) = upstream();
+        let mut config = ProxyConfig::default();
+        config.authinfo_user_pass("reader", "secret");
+        let addr = proxy(&config, upstream);
+
+        let mut anonymous = ClientConfig::default().connect(addr).unwrap();
+        let resp = anonymous
+            .command(cmd::Group("misc.test".to_string()))
+            .unwrap();
+        assert_eq!(resp.code(), Kind::AuthenticationRequired.into());
+
+        assert!(ClientConfig::default()
+            .authinfo_user_pass("reader", "wrong")
+            .connect(addr)
+            .is_err());
+
+        let mut client = ClientConfig::default()
+            .authinfo_user_pass("reader", "secret")
+            .connect(addr)
+            .unwrap();
+        client.select_group("misc.test").unwrap();
+    }
+}
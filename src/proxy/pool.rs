@@ -0,0 +1,185 @@
+use std::net::SocketAddr;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use log::*;
+
+use crate::client::ClientConfig;
+use crate::error::Result;
+use crate::raw::connection::NntpConnection;
+use crate::types::prelude::*;
+
+/// An upstream connection along with the state the proxy has put it in
+#[derive(Debug)]
+pub(crate) struct Upstream {
+    pub(crate) conn: NntpConnection,
+    /// The selected group
+    pub(crate) group: Option<String>,
+    /// The current article pointer
+    ///
+    /// `None` if it is unknown or invalid
+    pub(crate) article: Option<ArticleNumber>,
+}
+
+/// A bounded pool of upstream connections
+///
+/// Connections are opened lazily. Once `max` connections are open, callers block until a
+/// connection is returned to the pool.
+#[derive(Debug)]
+pub(crate) struct Pool {
+    addrs: Vec<SocketAddr>,
+    config: ClientConfig,
+    max: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    idle: Vec<Upstream>,
+    open: usize,
+    /// The number of discarded connections that have not been replaced yet
+    discarded: usize,
+    capabilities: Option<Capabilities>,
+}
+
+impl Pool {
+    pub(crate) fn new(addrs: Vec<SocketAddr>, config: ClientConfig, max: usize) -> Self {
+        Self {
+            addrs,
+            config,
+            max: max.max(1),
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                open: 0,
+                discarded: 0,
+                capabilities: None,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Check out a connection, blocking until one is available
+    pub(crate) fn get(&self) -> Result<Pooled<'_>> {
+        let mut state = self.lock();
+        loop {
+            if let Some(upstream) = state.idle.pop() {
+                return Ok(Pooled::new(self, upstream));
+            }
+
+            if state.open < self.max {
+                state.open += 1;
+                let replacement = state.discarded > 0;
+                state.discarded = state.discarded.saturating_sub(1);
+                // Don't hold the lock while connecting
+                drop(state);
+                return match self.connect() {
+                    Ok(upstream) => {
+                        if replacement {
+                            upstream.conn.metrics().record_reconnect();
+                        }
+                        Ok(Pooled::new(self, upstream))
+                    }
+                    Err(e) => {
+                        self.lock().discarded += usize::from(replacement);
+                        self.release_slot();
+                        Err(e)
+                    }
+                };
+            }
+
+            trace!("All {} upstream connections are busy, waiting...", self.max);
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// The capabilities advertised by the upstream server
+    ///
+    /// This is `None` until the first connection has been established
+    pub(crate) fn capabilities(&self) -> Option<Capabilities> {
+        self.lock().capabilities.clone()
+    }
+
+    /// The number of open upstream connections
+    pub(crate) fn open(&self) -> usize {
+        self.lock().open
+    }
+
+    fn connect(&self) -> Result<Upstream> {
+        debug!("Opening upstream connection to {:?}", self.addrs);
+        let client = self.config.connect(&self.addrs[..])?;
+
+        let group = client.group().map(|g| g.name.clone());
+        let article = client.current_article();
+        let capabilities = client.capabilities().clone();
+        self.lock().capabilities.get_or_insert(capabilities);
+
+        Ok(Upstream {
+            conn: client.into_conn(),
+            group,
+            article,
+        })
+    }
+
+    fn release_slot(&self) {
+        self.lock().open -= 1;
+        self.available.notify_one();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A connection checked out from a [`Pool`]
+///
+/// The connection is returned to the pool when dropped unless it is [discarded](Self::discard).
+#[derive(Debug)]
+pub(crate) struct Pooled<'a> {
+    pool: &'a Pool,
+    upstream: Option<Upstream>,
+}
+
+impl<'a> Pooled<'a> {
+    fn new(pool: &'a Pool, upstream: Upstream) -> Self {
+        Self {
+            pool,
+            upstream: Some(upstream),
+        }
+    }
+
+    /// Close the connection rather than returning it to the pool
+    ///
+    /// This should be called if the connection is in an unknown state (e.g. after an I/O error)
+    pub(crate) fn discard(mut self) {
+        self.upstream.take();
+        self.pool.lock().discarded += 1;
+        self.pool.release_slot();
+    }
+}
+
+impl Deref for Pooled<'_> {
+    type Target = Upstream;
+
+    fn deref(&self) -> &Self::Target {
+        self.upstream.as_ref().expect("Connection was discarded")
+    }
+}
+
+impl DerefMut for Pooled<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.upstream.as_mut().expect("Connection was discarded")
+    }
+}
+
+impl Drop for Pooled<'_> {
+    fn drop(&mut self) {
+        if let Some(upstream) = self.upstream.take() {
+            self.pool.lock().idle.push(upstream);
+            self.pool.available.notify_one();
+        }
+    }
+}
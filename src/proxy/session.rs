@@ -0,0 +1,466 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::{BufRead, Write};
+use std::net::TcpStream;
+
+use log::*;
+
+use crate::client::first_article;
+use crate::proxy::cache::DiskCache;
+use crate::proxy::pool::Pooled;
+use crate::proxy::Shared;
+use crate::types::command::{self as cmd, AnyCommand, Decode, DecodeError, Encode};
+use crate::types::prelude::*;
+
+/// Capabilities that the proxy does not pass on to readers
+const HIDDEN_CAPABILITIES: &[&str] = &[
+    "AUTHINFO",
+    "COMPRESS",
+    "IHAVE",
+    "MODE-READER",
+    "POST",
+    "SASL",
+    "STARTTLS",
+    "STREAMING",
+    "XFEATURE-COMPRESS",
+];
+
+/// Unknown commands that are known to return multi-line responses
+const MULTILINE_PASSTHROUGH: &[&str] = &["NEWGROUPS", "NEWNEWS", "XGTITLE"];
+
+/// A single reader connection
+///
+/// The session tracks the group and article pointer the reader believes it has selected.
+/// Upstream connections are shared between sessions, so before forwarding a command that depends
+/// on that state the upstream connection is brought in sync with `GROUP` and `STAT`.
+pub(crate) struct Session<'a> {
+    shared: &'a Shared,
+    reader: io::BufReader<TcpStream>,
+    writer: io::BufWriter<TcpStream>,
+    group: Option<String>,
+    article: Option<ArticleNumber>,
+    authenticated: bool,
+    username: Option<String>,
+}
+
+impl<'a> Session<'a> {
+    pub(crate) fn new(shared: &'a Shared, stream: TcpStream) -> io::Result<Self> {
+        let reader = io::BufReader::new(stream.try_clone()?);
+        let writer = io::BufWriter::new(stream);
+
+        Ok(Self {
+            shared,
+            reader,
+            writer,
+            group: None,
+            article: None,
+            authenticated: shared.authinfo.is_none(),
+            username: None,
+        })
+    }
+
+    /// Serve the reader until it sends `QUIT` or disconnects
+    pub(crate) fn run(mut self) -> io::Result<()> {
+        self.reply(Kind::PostingProhibited, "brokaw proxy ready (no posting)")?;
+        self.writer.flush()?;
+
+        let mut line = Vec::with_capacity(512);
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                debug!("Reader disconnected");
+                return Ok(());
+            }
+
+            let keep_going = match AnyCommand::decode(&line) {
+                Ok(command) => self.dispatch(command)?,
+                Err(DecodeError::UnknownCommand(keyword)) => self.passthrough(&keyword, &line)?,
+                // e.g. `LIST HEADERS`, the upstream server may support keywords we do not
+                Err(DecodeError::Syntax { ref command, .. }) if command == "LIST" => {
+                    self.passthrough(command, &line)?
+                }
+                Err(e) => {
+                    debug!("Rejecting command -- {}", e);
+                    self.reply(e.response_code(), e.to_string())?;
+                    true
+                }
+            };
+            self.writer.flush()?;
+
+            if !keep_going {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Handle a command, returning false if the session should end
+    fn dispatch(&mut self, command: AnyCommand) -> io::Result<bool> {
+        trace!("Reader sent `{}`", command.keyword());
+
+        match command {
+            AnyCommand::Quit(_) => {
+                self.reply(Kind::ConnectionClosing, "Bye!")?;
+                return Ok(false);
+            }
+            AnyCommand::AuthInfo(authinfo) => self.authinfo(authinfo)?,
+            AnyCommand::Capabilities(_) => self.capabilities()?,
+            AnyCommand::ModeReader(_) => {
+                self.reply(Kind::PostingProhibited, "Posting prohibited")?
+            }
+            _ if !self.authenticated => {
+                self.reply(Kind::AuthenticationRequired, "Authentication required")?
+            }
+            AnyCommand::IHave(_)
+            | AnyCommand::Check(_)
+            | AnyCommand::TakeThis(_)
+            | AnyCommand::ModeStream(_) => self.reply(
+                Kind::PermanentlyUnavailable,
+                "Transit commands are not supported by the proxy",
+            )?,
+            AnyCommand::Post(_) => {
+                self.reply(Kind::PostingNotPermitted, "Posting not permitted")?
+            }
+            AnyCommand::XFeatureCompress(_)
+            | AnyCommand::XFeatureCompressGzip(_)
+            | AnyCommand::XZHdr(_)
+            | AnyCommand::XZVer(_) => {
+                self.reply(Kind::FeatureNotSupported, "Compression is not supported")?
+            }
+            command if needs_group(&command) && self.group.is_none() => {
+                self.reply(Kind::NoNewsgroupSelected, "No newsgroup selected")?
+            }
+            // Never fall back on the upstream pointer, it may belong to another reader
+            command if needs_article(&command) && self.article.is_none() => self.reply(
+                Kind::InvalidCurrentArticleNumber,
+                "Current article number is invalid",
+            )?,
+            command => {
+                if let Some((kind, id)) = cached_request(&command) {
+                    if let Some(resp) = self.cache_get(kind, id) {
+                        resp.write_response(&mut self.writer)?;
+                        return Ok(true);
+                    }
+                }
+                let is_multiline = match command {
+                    AnyCommand::ListGroup(_) => Some(true),
+                    _ => None,
+                };
+                if let Some(resp) = self.forward(&command.encode(), is_multiline, Some(&command))? {
+                    resp.write_response(&mut self.writer)?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Forward a command the proxy does not understand
+    fn passthrough(&mut self, keyword: &str, line: &[u8]) -> io::Result<bool> {
+        if keyword.is_empty() {
+            self.reply(Kind::UnknownCommand, "Empty command")?;
+        } else if !self.authenticated {
+            self.reply(Kind::AuthenticationRequired, "Authentication required")?;
+        } else if keyword == "POST" {
+            self.reply(Kind::PostingNotPermitted, "Posting not permitted")?;
+        } else {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let is_multiline = if MULTILINE_PASSTHROUGH.contains(&keyword) {
+                Some(true)
+            } else {
+                None
+            };
+            if let Some(resp) = self.forward(line, is_multiline, None)? {
+                resp.write_response(&mut self.writer)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Send a command upstream and update the session state from the response
+    ///
+    /// `command` is used to track state changes, it is `None` for passthrough commands. Upstream
+    /// I/O errors are retried once on a new connection as idle connections may have been closed
+    /// by the server.
+    ///
+    /// Returns `None` if a response has already been sent to the reader
+    fn forward(
+        &mut self,
+        line: &[u8],
+        is_multiline: Option<bool>,
+        command: Option<&AnyCommand>,
+    ) -> io::Result<Option<RawResponse>> {
+        for attempt in 1..=2 {
+            let mut upstream = match self.shared.pool.get() {
+                Ok(upstream) => upstream,
+                Err(e) => {
+                    warn!("Failed to get upstream connection -- {}", e);
+                    self.reply(Kind::InternalError, "Upstream server unavailable")?;
+                    return Ok(None);
+                }
+            };
+
+            match self.exchange(&mut upstream, line, is_multiline, command) {
+                Ok(resp) => {
+                    if DiskCache::is_cacheable(resp.code()) {
+                        self.cache_put(&resp);
+                    }
+                    return Ok(Some(resp));
+                }
+                Err(e) => {
+                    warn!("Upstream connection failed (attempt {}) -- {}", attempt, e);
+                    upstream.discard();
+                }
+            }
+        }
+
+        self.reply(Kind::InternalError, "Upstream server failed")?;
+        Ok(None)
+    }
+
+    fn exchange(
+        &mut self,
+        upstream: &mut Pooled<'_>,
+        line: &[u8],
+        is_multiline: Option<bool>,
+        command: Option<&AnyCommand>,
+    ) -> crate::raw::error::Result<RawResponse> {
+        if let Some(group) = self.group.as_ref() {
+            if upstream.group.as_ref() != Some(group) {
+                trace!("Syncing upstream group to {}", group);
+                let resp = upstream.conn.command(&cmd::Group(group.clone()))?;
+                if resp.code() != Kind::GroupSelected.into() {
+                    upstream.group = None;
+                    return Ok(resp);
+                }
+                upstream.group = Some(group.clone());
+                upstream.article = Group::try_from(&resp).ok().as_ref().and_then(first_article);
+            }
+        }
+
+        if let Some(number) = self.article {
+            let needs_article = command.map(needs_article).unwrap_or(true);
+            if needs_article && upstream.article != Some(number) {
+                trace!("Syncing upstream article pointer to {}", number);
+                let resp = upstream.conn.command(&cmd::Stat::Number(number))?;
+                if resp.code() == Kind::ArticleExists.into() {
+                    upstream.article = Some(number);
+                } else if command.is_some() {
+                    // The upstream pointer may belong to another reader, so don't use it
+                    debug!("Article {} is no longer available upstream", number);
+                    return Ok(RawResponse::new(
+                        Kind::InvalidCurrentArticleNumber,
+                        "Current article number is invalid",
+                    ));
+                }
+            }
+        }
+
+        upstream.conn.send_bytes(line)?;
+        let resp = upstream.conn.read_response(is_multiline)?;
+
+        match command {
+            Some(command) => {
+                if self.update_state(command, &resp) {
+                    upstream.group = self.group.clone();
+                    upstream.article = self.article;
+                }
+            }
+            // Passthrough commands may have moved the pointer without us knowing
+            None => upstream.article = None,
+        }
+
+        Ok(resp)
+    }
+
+    /// Update the group and article pointer, returning true if they changed
+    fn update_state(&mut self, command: &AnyCommand, resp: &RawResponse) -> bool {
+        let code = resp.code();
+
+        let selected = |name: Option<&String>, current: &Option<String>| {
+            Group::try_from(resp)
+                .ok()
+                .map(|g| (name.cloned().or_else(|| current.clone()), first_article(&g)))
+        };
+
+        let group_change = match command {
+            AnyCommand::Group(cmd::Group(name)) => selected(Some(name), &self.group),
+            AnyCommand::ListGroup(cmd::ListGroup::Group(name))
+            | AnyCommand::ListGroup(cmd::ListGroup::Range { group: name, .. })
+            | AnyCommand::ListGroup(cmd::ListGroup::RangeFrom { group: name, .. }) => {
+                selected(Some(name), &self.group)
+            }
+            AnyCommand::ListGroup(cmd::ListGroup::Current) => selected(None, &self.group),
+            _ => None,
+        };
+        if let Some((group, article)) = group_change {
+            self.group = group;
+            self.article = article;
+            return true;
+        }
+
+        let number = match command {
+            AnyCommand::Article(cmd::Article::Number(n))
+            | AnyCommand::Head(cmd::Head::Number(n))
+            | AnyCommand::Body(cmd::Body::Number(n))
+            | AnyCommand::Stat(cmd::Stat::Number(n))
+                if code.is_success() =>
+            {
+                Some(*n)
+            }
+            AnyCommand::Next(_) | AnyCommand::Last(_) => {
+                Stat::try_from(resp).ok().map(|s| s.number)
+            }
+            _ => None,
+        };
+        if number.is_some() {
+            self.article = number;
+            return true;
+        }
+
+        false
+    }
+
+    fn authinfo(&mut self, authinfo: cmd::AuthInfo) -> io::Result<()> {
+        let expected = self.shared.authinfo.as_ref();
+        match (authinfo, expected) {
+            (cmd::AuthInfo::User(_), None) => {
+                self.reply(Kind::PasswordRequired, "Password required")
+            }
+            (cmd::AuthInfo::Pass(_), None) => {
+                self.reply(Kind::AuthenticationAccepted, "Authentication accepted")
+            }
+            (_, Some(_)) if self.authenticated => {
+                self.reply(Kind::AuthenticationOutOfSequence, "Already authenticated")
+            }
+            (cmd::AuthInfo::User(user), Some(_)) => {
+                self.username = Some(user);
+                self.reply(Kind::PasswordRequired, "Password required")
+            }
+            (cmd::AuthInfo::Pass(pass), Some((user, expected_pass))) => {
+                match self.username.take() {
+                    None => self.reply(
+                        Kind::AuthenticationOutOfSequence,
+                        "AUTHINFO USER is required first",
+                    ),
+                    Some(username) if &username == user && &pass == expected_pass => {
+                        self.authenticated = true;
+                        self.reply(Kind::AuthenticationAccepted, "Authentication accepted")
+                    }
+                    Some(_) => self.reply(Kind::AuthenticationFailed, "Authentication failed"),
+                }
+            }
+        }
+    }
+
+    fn capabilities(&mut self) -> io::Result<()> {
+        let upstream = match self.shared.pool.capabilities() {
+            Some(caps) => Some(caps),
+            // Open a connection so we learn what the server supports
+            None => self
+                .shared
+                .pool
+                .get()
+                .ok()
+                .and_then(|_| self.shared.pool.capabilities()),
+        };
+
+        let mut caps = upstream
+            .iter()
+            .flat_map(Capabilities::iter)
+            .filter(|cap| !HIDDEN_CAPABILITIES.contains(&cap.name.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let cap = |name: &str, args: Option<&[&str]>| Capability {
+            name: name.to_string(),
+            args: args.map(|args| args.iter().map(ToString::to_string).collect()),
+        };
+        if caps.iter().all(|c| c.name != "VERSION") {
+            caps.push(cap("VERSION", Some(&["2"])));
+        }
+        if caps.iter().all(|c| c.name != "READER") {
+            caps.push(cap("READER", None));
+        }
+        if self.shared.authinfo.is_some() && !self.authenticated {
+            caps.push(cap("AUTHINFO", Some(&["USER"])));
+        }
+
+        caps.into_iter()
+            .collect::<Capabilities>()
+            .write_response(&mut self.writer)
+    }
+
+    fn cache_get(&self, kind: Kind, message_id: &str) -> Option<RawResponse> {
+        let cache = self.shared.cache.as_ref()?;
+        cache.get(kind, message_id).unwrap_or_else(|e| {
+            warn!("Failed to read cached response for {} -- {}", message_id, e);
+            None
+        })
+    }
+
+    fn cache_put(&self, resp: &RawResponse) {
+        if let Some(cache) = self.shared.cache.as_ref() {
+            if let Err(e) = cache.put(resp) {
+                warn!("Failed to cache response -- {}", e);
+            }
+        }
+    }
+
+    fn reply(&mut self, code: impl Into<ResponseCode>, text: impl AsRef<str>) -> io::Result<()> {
+        RawResponse::new(code, text).write_response(&mut self.writer)
+    }
+}
+
+/// The response kind and Message-ID for requests that can be served from the cache
+fn cached_request(command: &AnyCommand) -> Option<(Kind, &str)> {
+    match command {
+        AnyCommand::Article(cmd::Article::MessageId(id)) => Some((Kind::Article, id)),
+        AnyCommand::Head(cmd::Head::MessageId(id)) => Some((Kind::Head, id)),
+        AnyCommand::Body(cmd::Body::MessageId(id)) => Some((Kind::Body, id)),
+        _ => None,
+    }
+}
+
+/// Commands that require a selected group
+fn needs_group(command: &AnyCommand) -> bool {
+    match command {
+        AnyCommand::Article(cmd::Article::MessageId(_))
+        | AnyCommand::Head(cmd::Head::MessageId(_))
+        | AnyCommand::Body(cmd::Body::MessageId(_))
+        | AnyCommand::Stat(cmd::Stat::MessageId(_))
+        | AnyCommand::Over(cmd::Over::MessageId(_))
+        | AnyCommand::Hdr(cmd::Hdr::MessageId { .. })
+        | AnyCommand::XHdr(cmd::XHdr::MessageId { .. }) => false,
+        AnyCommand::Article(_)
+        | AnyCommand::Head(_)
+        | AnyCommand::Body(_)
+        | AnyCommand::Stat(_)
+        | AnyCommand::Next(_)
+        | AnyCommand::Last(_)
+        | AnyCommand::Over(_)
+        | AnyCommand::XOver(_)
+        | AnyCommand::Hdr(_)
+        | AnyCommand::XHdr(_)
+        | AnyCommand::ListGroup(cmd::ListGroup::Current) => true,
+        _ => false,
+    }
+}
+
+/// Commands that operate on the current article
+fn needs_article(command: &AnyCommand) -> bool {
+    matches!(
+        command,
+        AnyCommand::Article(cmd::Article::Current)
+            | AnyCommand::Head(cmd::Head::Current)
+            | AnyCommand::Body(cmd::Body::Current)
+            | AnyCommand::Stat(cmd::Stat::Current)
+            | AnyCommand::Next(_)
+            | AnyCommand::Last(_)
+            | AnyCommand::Over(cmd::Over::Current)
+            | AnyCommand::XOver(cmd::XOver::Current)
+            | AnyCommand::Hdr(cmd::Hdr::Current { .. })
+            | AnyCommand::XHdr(cmd::XHdr::Current { .. })
+    )
+}
@@ -0,0 +1,352 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+/// A pool of reusable buffers for reading responses
+///
+/// Reading a response fills buffers taken from the pool. When a [`RawResponse`] is dropped its
+/// buffers are returned to the pool and reused for later responses, so a connection reading
+/// many small responses does not need to allocate for each of them.
+///
+/// Free buffers are kept in size classes (powers of two) so that taking a buffer returns the
+/// smallest pooled buffer that is large enough, and each class has its own lock.
+///
+/// Connections use the process wide [`global`](Self::global) pool by default. A dedicated pool
+/// (or none at all) can be configured via
+/// [`ConnectionConfig::buffer_pool`](super::connection::ConnectionConfig::buffer_pool).
+///
+/// ```
+/// use std::sync::Arc;
+/// use brokaw::raw::buffer::BufferPool;
+/// use brokaw::raw::connection::NntpConnection;
+/// use brokaw::raw::transcript::Transcript;
+/// use brokaw::types::command as cmd;
+/// use brokaw::ConnectionConfig;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let pool = Arc::new(BufferPool::new(16, 64 * 1024));
+/// let transcript = Transcript::load("tests/mozilla.transcript")?;
+/// let (mut conn, _) = NntpConnection::replay(
+///     transcript,
+///     ConnectionConfig::default()
+///         .buffer_pool(Some(pool.clone()))
+///         .to_owned(),
+/// )?;
+///
+/// let resp = conn.command(&cmd::Capabilities)?;
+/// drop(resp);
+/// assert!(pool.stats().recycled > 0);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`RawResponse`]: super::response::RawResponse
+#[derive(Debug)]
+pub struct BufferPool {
+    bytes: SizeClasses<u8>,
+    line_boundaries: SizeClasses<(usize, usize)>,
+    max_buffers: usize,
+    max_capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    recycled: AtomicU64,
+    discarded: AtomicU64,
+}
+
+/// Free buffers grouped by capacity
+///
+/// Class `k` holds buffers with a capacity of at least `2^k` and less than `2^(k + 1)` elements
+#[derive(Debug)]
+struct SizeClasses<T> {
+    classes: Vec<Mutex<Vec<Vec<T>>>>,
+    available: AtomicUsize,
+}
+
+impl<T> SizeClasses<T> {
+    /// Create classes for buffers of up to `max_len` elements
+    fn new(max_len: usize) -> Self {
+        let classes = (0..=floor_log2(max_len.max(1)))
+            .map(|_| Mutex::default())
+            .collect();
+        Self {
+            classes,
+            available: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take the smallest buffer with a capacity of at least `capacity`
+    fn take(&self, capacity: usize) -> Option<Vec<T>> {
+        if self.available.load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+
+        // Only some of the buffers in the lowest class may be large enough
+        let lowest = floor_log2(capacity.max(1));
+        let buf = self
+            .classes
+            .iter()
+            .enumerate()
+            .skip(lowest)
+            .find_map(|(k, class)| {
+                let mut class = lock(class);
+                if k == lowest {
+                    let i = class.iter().position(|buf| buf.capacity() >= capacity)?;
+                    Some(class.swap_remove(i))
+                } else {
+                    class.pop()
+                }
+            })?;
+        self.available.fetch_sub(1, Ordering::Relaxed);
+        Some(buf)
+    }
+
+    /// Store a buffer unless the pool already holds `max_buffers`
+    ///
+    /// Returns false if the buffer was not stored
+    fn put(&self, buf: Vec<T>, max_buffers: usize) -> bool {
+        let class = match self.classes.get(floor_log2(buf.capacity())) {
+            Some(class) if buf.capacity() > 0 => class,
+            _ => return false,
+        };
+
+        // Reserve a slot first so concurrent puts can not exceed the limit
+        let reserved = self
+            .available
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n + 1).filter(|n| *n <= max_buffers)
+            });
+        if reserved.is_err() {
+            return false;
+        }
+
+        lock(class).push(buf);
+        true
+    }
+
+    fn available(&self) -> usize {
+        self.available.load(Ordering::Relaxed)
+    }
+}
+
+fn floor_log2(n: usize) -> usize {
+    (usize::BITS - 1 - n.leading_zeros()) as usize
+}
+
+fn lock<T>(class: &Mutex<T>) -> MutexGuard<'_, T> {
+    // The buffers are always left in a consistent state, so a poisoned lock is still usable
+    class.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Counters describing how effective a [`BufferPool`] has been
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BufferPoolStats {
+    /// Buffers that were reused from the pool
+    pub hits: u64,
+    /// Buffers that had to be allocated because the pool had no buffer large enough
+    pub misses: u64,
+    /// Buffers that were returned to the pool
+    pub recycled: u64,
+    /// Buffers that were dropped because the pool was full or they were too large
+    pub discarded: u64,
+    /// Buffers currently available in the pool
+    pub available: usize,
+}
+
+impl BufferPool {
+    /// Create a pool
+    ///
+    /// * `max_buffers` is the number of buffers of each kind the pool will hold onto
+    /// * Buffers that have grown larger than `max_capacity` bytes are dropped rather than pooled,
+    ///   so a single large response does not pin its memory forever
+    ///
+    /// The pool holds at most `2 * max_buffers * max_capacity` bytes.
+    pub fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            bytes: SizeClasses::new(max_capacity),
+            line_boundaries: SizeClasses::new(max_capacity / std::mem::size_of::<(usize, usize)>()),
+            max_buffers,
+            max_capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            recycled: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    /// A pool that can be shared by every connection in the process
+    ///
+    /// It holds up to 32 buffers of each kind of at most 256 KiB, i.e. at most 16 MiB.
+    /// Connections use it unless another pool is configured with
+    /// [`ConnectionConfig::buffer_pool`](super::connection::ConnectionConfig::buffer_pool).
+    pub fn global() -> Arc<BufferPool> {
+        static GLOBAL: OnceLock<Arc<BufferPool>> = OnceLock::new();
+        GLOBAL
+            .get_or_init(|| Arc::new(BufferPool::new(32, 256 * 1024)))
+            .clone()
+    }
+
+    /// A snapshot of the pool's counters
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            recycled: self.recycled.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+            available: self.bytes.available() + self.line_boundaries.available(),
+        }
+    }
+
+    /// Take an empty byte buffer with at least `capacity` bytes of capacity
+    pub(crate) fn bytes(&self, capacity: usize) -> Vec<u8> {
+        self.take(&self.bytes, capacity)
+    }
+
+    /// Take an empty line boundary buffer with room for at least `capacity` lines
+    pub(crate) fn line_boundaries(&self, capacity: usize) -> Vec<(usize, usize)> {
+        self.take(&self.line_boundaries, capacity)
+    }
+
+    /// Return a byte buffer to the pool
+    pub(crate) fn recycle_bytes(&self, buf: Vec<u8>) {
+        self.recycle(&self.bytes, buf)
+    }
+
+    /// Return a line boundary buffer to the pool
+    pub(crate) fn recycle_line_boundaries(&self, buf: Vec<(usize, usize)>) {
+        self.recycle(&self.line_boundaries, buf)
+    }
+
+    fn take<T>(&self, classes: &SizeClasses<T>, capacity: usize) -> Vec<T> {
+        match classes.take(capacity) {
+            Some(buf) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buf
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(capacity)
+            }
+        }
+    }
+
+    fn recycle<T>(&self, classes: &SizeClasses<T>, mut buf: Vec<T>) {
+        let stored = buf.capacity() * std::mem::size_of::<T>() <= self.max_capacity && {
+            buf.clear();
+            classes.put(buf, self.max_buffers)
+        };
+
+        if stored {
+            self.recycled.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse() {
+        let pool = BufferPool::new(2, 1024);
+
+        let mut buf = pool.bytes(128);
+        buf.extend_from_slice(b"hello");
+        let ptr = buf.as_ptr();
+        pool.recycle_bytes(buf);
+
+        let buf = pool.bytes(64);
+        assert!(buf.is_empty());
+        assert_eq!(buf.as_ptr(), ptr);
+
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses, stats.recycled), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_prefers_large_enough() {
+        let pool = BufferPool::new(4, 1024);
+        pool.recycle_bytes(Vec::with_capacity(512));
+        pool.recycle_bytes(Vec::with_capacity(16));
+
+        assert!(pool.bytes(256).capacity() >= 512);
+        assert_eq!(pool.bytes(8).capacity(), 16);
+    }
+
+    #[test]
+    fn test_size_classes() {
+        let pool = BufferPool::new(8, 64 * 1024);
+        for capacity in [4096, 100, 120, 32 * 1024].iter() {
+            pool.recycle_bytes(Vec::with_capacity(*capacity));
+        }
+
+        // The smallest buffer that fits is used, even within a class
+        assert_eq!(pool.bytes(110).capacity(), 120);
+        assert_eq!(pool.bytes(1000).capacity(), 4096);
+        assert_eq!(pool.bytes(16).capacity(), 100);
+
+        // Buffers that are too small are left for smaller requests
+        assert!(pool.bytes(40 * 1024).capacity() >= 40 * 1024);
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses, stats.available), (3, 1, 1));
+    }
+
+    #[test]
+    fn test_limits() {
+        let pool = BufferPool::new(1, 1024);
+        pool.recycle_bytes(Vec::with_capacity(4096));
+        pool.recycle_bytes(Vec::with_capacity(16));
+        pool.recycle_bytes(Vec::with_capacity(16));
+        pool.recycle_line_boundaries(Vec::with_capacity(16));
+
+        let stats = pool.stats();
+        assert_eq!(stats.recycled, 2);
+        assert_eq!(stats.discarded, 2);
+        assert_eq!(stats.available, 2);
+    }
+
+    #[test]
+    fn test_grown_buffers_are_recycled() {
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::raw::transcript::{Record, Transcript};
+        use crate::types::command as cmd;
+
+        let mut help = b"100 Help text follows\r\n".to_vec();
+        for _ in 0..1000 {
+            help.extend_from_slice(&[b'x'; 62]);
+            help.extend_from_slice(b"\r\n");
+        }
+        help.extend_from_slice(b".\r\n");
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"HELP\r\n".to_vec()));
+        transcript.push(Record::Received(help));
+        transcript.push(Record::Sent(b"DATE\r\n".to_vec()));
+        transcript.push(Record::Received(b"111 20200605000000\r\n".to_vec()));
+
+        let pool = Arc::new(BufferPool::new(4, 1024 * 1024));
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::default()
+                .buffer_pool(Some(pool.clone()))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let len = conn
+            .command_borrowed(&cmd::Help)
+            .unwrap()
+            .data_blocks()
+            .unwrap()
+            .payload()
+            .len();
+        assert!(len > 60_000);
+
+        // The grown buffer is returned to the pool instead of being shrunk
+        conn.command_borrowed(&cmd::Date).unwrap();
+        let hits = pool.stats().hits;
+        assert!(pool.bytes(len).capacity() >= len);
+        assert_eq!(pool.stats().hits, hits + 1);
+    }
+}
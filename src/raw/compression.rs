@@ -1,77 +1,253 @@
-use std::io::{BufRead, BufReader, Read};
+use std::fmt;
+use std::io::{BufRead, BufReader, Cursor, ErrorKind, Read, Write};
 
-use flate2::bufread::ZlibDecoder;
+use flate2::bufread::{DeflateDecoder, ZlibDecoder};
 use std::io;
 
+/// A codec that decompresses responses and optionally compresses data sent to the server
+///
+/// Codecs are registered with [`ConnectionConfig::codec`] and are shared between connections
+/// created from the same config. For each multi-line response the connection uses the first
+/// codec whose [`use_decoder`](Self::use_decoder) returns true, checking registered codecs
+/// before the configured [`Compression`].
+///
+/// # Example: A Custom Codec
+///
+/// ```
+/// use std::io::{BufRead, BufReader};
+/// use brokaw::raw::Codec;
+/// use flate2::bufread::GzDecoder;
+///
+/// /// Responses to our in-house XGZOVER command are gzipped
+/// #[derive(Debug)]
+/// struct XGzOver;
+///
+/// impl Codec for XGzOver {
+///     fn use_decoder(&self, keyword: &str, _first_line: &[u8]) -> bool {
+///         keyword == "XGZOVER"
+///     }
+///
+///     fn decoder<'a>(&self, stream: &'a mut dyn BufRead) -> Box<dyn BufRead + 'a> {
+///         Box::new(BufReader::new(GzDecoder::new(stream)))
+///     }
+/// }
+/// ```
+///
+/// [`ConnectionConfig::codec`]: super::connection::ConnectionConfig::codec
+pub trait Codec: fmt::Debug + Send + Sync {
+    /// Returns true if the data blocks of a response should be decoded
+    ///
+    /// * `keyword` is the keyword of the command the response belongs to (e.g. `XOVER`), it is
+    ///   empty if no command was sent
+    /// * `first_line` is the first line of the response including the CRLF terminator
+    fn use_decoder(&self, keyword: &str, first_line: &[u8]) -> bool;
+
+    /// Wrap the stream the data blocks are read from
+    ///
+    /// The returned reader must yield the decoded data blocks, including the terminating `.`
+    /// line, and must not read past the end of the response from `stream`.
+    /// The connection reads the decoder to the end after the terminating line so that trailers
+    /// (e.g. checksums) are consumed, anything it yields after the terminating line is ignored.
+    fn decoder<'a>(&self, stream: &'a mut dyn BufRead) -> Box<dyn BufRead + 'a>;
+
+    /// Wrap the writer data blocks are sent with (e.g. articles for `POST` or `IHAVE`)
+    ///
+    /// * `keyword` is the keyword of the command the data blocks belong to (e.g. `POST`)
+    ///
+    /// The data blocks are written dot-stuffed and terminated, then [`Encoder::finish`] is
+    /// called. Returning `None` (the default) sends the data blocks unchanged.
+    fn encoder<'a>(
+        &self,
+        keyword: &str,
+        writer: &'a mut dyn Write,
+    ) -> Option<Box<dyn Encoder + 'a>> {
+        let _ = (keyword, writer);
+        None
+    }
+}
+
+/// A writer returned by [`Codec::encoder`]
+///
+/// ```
+/// use std::io::{self, Write};
+/// use brokaw::raw::Encoder;
+/// use flate2::write::GzEncoder;
+///
+/// struct Gzip<W: Write>(GzEncoder<W>);
+///
+/// impl<W: Write> Write for Gzip<W> {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         self.0.flush()
+///     }
+/// }
+///
+/// impl<W: Write> Encoder for Gzip<W> {
+///     fn finish(&mut self) -> io::Result<()> {
+///         self.0.try_finish()
+///     }
+/// }
+/// ```
+pub trait Encoder: Write {
+    /// Write any buffered data and trailers (e.g. checksums) to the underlying writer
+    ///
+    /// Errors are returned from
+    /// [`send_data_blocks`](super::connection::NntpConnection::send_data_blocks).
+    fn finish(&mut self) -> io::Result<()>;
+}
+
 /// A type of compression enabled on the server
 #[derive(Copy, Clone, Debug)]
 pub enum Compression {
     /// Giganews style compression
     XFeature,
+    /// yEnc encoded, deflate compressed responses to `XZVER` and `XZHDR`
+    ///
+    /// Responses to these commands are always compressed, so connections decode them whether
+    /// or not this is configured.
+    XZVer,
 }
 
-/// An codec that can unpack compressed data streams
+impl Codec for Compression {
+    fn use_decoder(&self, keyword: &str, first_line: &[u8]) -> bool {
+        match self {
+            Self::XFeature => first_line.ends_with(b"[COMPRESS=GZIP]\r\n"),
+            Self::XZVer => matches!(keyword, "XZVER" | "XZHDR"),
+        }
+    }
+
+    fn decoder<'a>(&self, stream: &'a mut dyn BufRead) -> Box<dyn BufRead + 'a> {
+        match self {
+            Self::XFeature => Box::new(BufReader::new(ZlibDecoder::new(stream))),
+            Self::XZVer => Box::new(XZVerDecoder {
+                stream,
+                decoded: None,
+            }),
+        }
+    }
+}
+
+/// Unpacks the data blocks returned by `XZVER` and `XZHDR`
+///
+/// The data blocks contain a yEnc encoded, deflate compressed response. As yEnc is line based,
+/// the entire response is read and decompressed the first time the decoder is read from.
 #[derive(Debug)]
-pub(crate) enum Decoder<S> {
-    XFeature(BufReader<ZlibDecoder<S>>),
-    Passthrough(S),
+struct XZVerDecoder<S> {
+    stream: S,
+    decoded: Option<Cursor<Vec<u8>>>,
 }
 
-impl Compression {
-    pub(crate) fn use_decoder(&self, first_line: impl AsRef<[u8]>) -> bool {
-        match self {
-            Self::XFeature => first_line.as_ref().ends_with(b"[COMPRESS=GZIP]\r\n"),
+impl<S: BufRead> XZVerDecoder<S> {
+    fn decoded(&mut self) -> io::Result<&mut Cursor<Vec<u8>>> {
+        if self.decoded.is_none() {
+            let compressed = read_yenc(&mut self.stream)?;
+            let mut decoded = Vec::with_capacity(compressed.len() * 4);
+            DeflateDecoder::new(compressed.as_slice()).read_to_end(&mut decoded)?;
+
+            // Not every server compresses the terminator along with the response
+            if decoded != b".\r\n" && !decoded.ends_with(b"\r\n.\r\n") {
+                decoded.extend_from_slice(b".\r\n");
+            }
+            self.decoded = Some(Cursor::new(decoded));
         }
+
+        Ok(self.decoded.get_or_insert_with(Default::default))
     }
+}
 
-    pub(crate) fn decoder<S: BufRead + Read>(&self, stream: S) -> Decoder<S> {
-        match self {
-            Self::XFeature => Decoder::XFeature(BufReader::new(ZlibDecoder::new(stream))),
+/// Read yEnc encoded data blocks up to and including the terminating `.` line
+///
+/// `=ybegin`, `=ypart`, and `=yend` lines are skipped, though the size in the `=yend` line is
+/// verified if present.
+fn read_yenc<S: BufRead>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let invalid = |msg| io::Error::new(ErrorKind::InvalidData, msg);
+    let mut decoded = Vec::new();
+    let mut expected_size = None;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if stream.read_until(b'\n', &mut line)? == 0 {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "yEnc data blocks are not terminated",
+            ));
+        }
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if content == b"." {
+            break;
+        }
+        // Undo dot-stuffing
+        let content = content.strip_prefix(b".").unwrap_or(content);
+
+        if content.starts_with(b"=yend") {
+            expected_size = String::from_utf8_lossy(content)
+                .split_whitespace()
+                .find_map(|param| param.strip_prefix("size=")?.parse::<usize>().ok());
+            continue;
+        }
+        if content.starts_with(b"=ybegin") || content.starts_with(b"=ypart") {
+            continue;
         }
+
+        let mut bytes = content.iter();
+        while let Some(byte) = bytes.next() {
+            let byte = match byte {
+                b'=' => bytes
+                    .next()
+                    .ok_or_else(|| invalid("yEnc escape at end of line"))?
+                    .wrapping_sub(64),
+                byte => *byte,
+            };
+            decoded.push(byte.wrapping_sub(42));
+        }
+    }
+
+    match expected_size {
+        Some(size) if size != decoded.len() => Err(invalid("yEnc size does not match =yend")),
+        _ => Ok(decoded),
     }
 }
 
-impl<S: Read + BufRead> Read for Decoder<S> {
+impl<S: BufRead> Read for XZVerDecoder<S> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        match self {
-            Decoder::XFeature(d) => d.read(buf),
-            Decoder::Passthrough(s) => s.read(buf),
-        }
+        self.decoded()?.read(buf)
     }
 }
 
-impl<S: BufRead> BufRead for Decoder<S> {
+impl<S: BufRead> BufRead for XZVerDecoder<S> {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
-        match self {
-            Decoder::XFeature(d) => d.fill_buf(),
-            Decoder::Passthrough(s) => s.fill_buf(),
-        }
+        self.decoded()?.fill_buf()
     }
 
     fn consume(&mut self, amt: usize) {
-        match self {
-            Decoder::XFeature(d) => d.consume(amt),
-            Decoder::Passthrough(s) => s.consume(amt),
+        if let Some(decoded) = self.decoded.as_mut() {
+            decoded.consume(amt)
         }
     }
 }
 
-/*
-    In theory if we wanted to implement extensible compression we could replace Decoder and
-    Compression objects w/ traits. That said it didn't seem necessary given the slow moving
-    nature of the NNTP standard. If users ask for this we can always revisit it.
-*/
-
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
     #[test]
     fn test_use_decoder() {
-        assert!(
-            Compression::XFeature.use_decoder("224 xover information follows [COMPRESS=GZIP]\r\n")
-        );
-        assert!(!Compression::XFeature.use_decoder("224 xover information follows [COMPRESS=GZIP]"))
+        assert!(Compression::XFeature.use_decoder(
+            "XOVER",
+            b"224 xover information follows [COMPRESS=GZIP]\r\n"
+        ));
+        assert!(!Compression::XFeature
+            .use_decoder("XOVER", b"224 xover information follows [COMPRESS=GZIP]"));
+        assert!(Compression::XZVer.use_decoder("XZVER", b"224 compressed data follows\r\n"));
+        assert!(!Compression::XZVer.use_decoder("XOVER", b"224 overview follows\r\n"));
     }
 
     #[test]
@@ -92,16 +268,239 @@ mod tests {
             .map(|(i, _)| i)
             .unwrap();
 
-        let (first_line, data_blocks) = (
+        let (first_line, mut data_blocks) = (
             &compressed_resp[..line_boundary + 1],
             &compressed_resp[line_boundary + 1..],
         );
 
-        assert!(Compression::XFeature.use_decoder(first_line));
+        assert!(Compression::XFeature.use_decoder("XOVER", first_line));
 
-        let mut decoder = Compression::XFeature.decoder(&data_blocks[..]);
+        let mut decoder = Compression::XFeature.decoder(&mut data_blocks);
         let mut buf = String::new();
         decoder.read_to_string(&mut buf).unwrap();
         assert_eq!(buf, String::from_utf8(plain_resp.to_vec()).unwrap())
     }
+
+    /// yEnc encode and dot-stuff `data` as data blocks
+    fn yenc(data: &[u8]) -> Vec<u8> {
+        let mut encoded =
+            format!("=ybegin line=128 size={} name=xzver\r\n", data.len()).into_bytes();
+        let mut line = Vec::new();
+        for byte in data {
+            match byte.wrapping_add(42) {
+                c @ (b'\0' | b'\n' | b'\r' | b'=') => {
+                    line.extend_from_slice(&[b'=', c.wrapping_add(64)])
+                }
+                c => line.push(c),
+            }
+            if line.len() >= 128 {
+                if line[0] == b'.' {
+                    encoded.push(b'.');
+                }
+                encoded.append(&mut line);
+                encoded.extend_from_slice(b"\r\n");
+            }
+        }
+        if !line.is_empty() {
+            if line[0] == b'.' {
+                encoded.push(b'.');
+            }
+            encoded.append(&mut line);
+            encoded.extend_from_slice(b"\r\n");
+        }
+        encoded.extend_from_slice(format!("=yend size={}\r\n.\r\n", data.len()).as_bytes());
+        encoded
+    }
+
+    #[test]
+    fn test_xzver() {
+        use std::convert::TryFrom;
+
+        use flate2::write::DeflateEncoder;
+
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::raw::transcript::{Record, Transcript};
+        use crate::types::command::XZVer;
+        use crate::types::prelude::*;
+
+        let plain_resp = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/xover_resp_plain_text"
+        ));
+        // Compress the response without its terminator
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&plain_resp[..plain_resp.len() - 3])
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut received = b"224 compressed data follows\r\n".to_vec();
+        received.extend(yenc(&compressed));
+        assert!(received.len() < plain_resp.len());
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"XZVER 1-5\r\n".to_vec()));
+        transcript.push(Record::Received(received));
+
+        let (mut conn, _) =
+            NntpConnection::replay(transcript, ConnectionConfig::default()).unwrap();
+        let resp = conn.command(&XZVer::Range { low: 1, high: 5 }).unwrap();
+
+        assert_eq!(resp.data_blocks().unwrap().payload(), &plain_resp[..]);
+        assert!(!Overviews::try_from(&resp).unwrap().is_empty());
+    }
+
+    #[derive(Debug)]
+    struct Gzip;
+
+    impl Codec for Gzip {
+        fn use_decoder(&self, keyword: &str, _first_line: &[u8]) -> bool {
+            keyword == "XGZOVER"
+        }
+
+        fn decoder<'a>(&self, stream: &'a mut dyn BufRead) -> Box<dyn BufRead + 'a> {
+            Box::new(BufReader::new(flate2::bufread::GzDecoder::new(stream)))
+        }
+
+        fn encoder<'a>(
+            &self,
+            keyword: &str,
+            writer: &'a mut dyn Write,
+        ) -> Option<Box<dyn Encoder + 'a>> {
+            if keyword != "POST" {
+                return None;
+            }
+            Some(Box::new(GzipEncoder(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            ))))
+        }
+    }
+
+    struct GzipEncoder<W: Write>(flate2::write::GzEncoder<W>);
+
+    impl<W: Write> Write for GzipEncoder<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl<W: Write> Encoder for GzipEncoder<W> {
+        fn finish(&mut self) -> io::Result<()> {
+            self.0.try_finish()
+        }
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_codec() {
+        use std::sync::Arc;
+
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::raw::transcript::{Record, Transcript};
+
+        let overview = b"1\tSubject\t<a@b.c>\r\n.\r\n";
+        let mut received = b"224 overview follows\r\n".to_vec();
+        received.extend(gzip(overview));
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"XGZOVER\r\n".to_vec()));
+        transcript.push(Record::Received(received));
+        transcript.push(Record::Sent(b"POST\r\n".to_vec()));
+        transcript.push(Record::Received(b"340 send article\r\n".to_vec()));
+        transcript.push(Record::Sent(gzip(b"Subject: hi\r\n\r\n..body\r\n.\r\n")));
+        transcript.push(Record::Received(b"240 article received\r\n".to_vec()));
+
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::default().codec(Arc::new(Gzip)).to_owned(),
+        )
+        .unwrap();
+
+        conn.send_bytes("XGZOVER").unwrap();
+        let resp = conn.read_response_auto().unwrap();
+        assert_eq!(resp.data_blocks().unwrap().payload(), &overview[..]);
+
+        conn.send_bytes("POST").unwrap();
+        assert_eq!(u16::from(conn.read_response_auto().unwrap().code()), 340);
+        let sent = conn
+            .send_data_blocks("Subject: hi\r\n\r\n.body\r\n")
+            .unwrap();
+        assert_eq!(u16::from(conn.read_response_auto().unwrap().code()), 240);
+
+        // The compressed size is reported
+        assert_eq!(sent, gzip(b"Subject: hi\r\n\r\n..body\r\n.\r\n").len());
+        assert_eq!(conn.metrics().snapshot().bytes_sent as usize, sent + 15);
+    }
+
+    #[test]
+    fn test_encoder_errors() {
+        use std::sync::Arc;
+
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::raw::transcript::{Record, Transcript};
+
+        #[derive(Debug)]
+        struct Broken;
+
+        impl Codec for Broken {
+            fn use_decoder(&self, _keyword: &str, _first_line: &[u8]) -> bool {
+                false
+            }
+
+            fn decoder<'a>(&self, stream: &'a mut dyn BufRead) -> Box<dyn BufRead + 'a> {
+                Box::new(stream)
+            }
+
+            fn encoder<'a>(
+                &self,
+                _keyword: &str,
+                writer: &'a mut dyn Write,
+            ) -> Option<Box<dyn Encoder + 'a>> {
+                Some(Box::new(BrokenEncoder(writer)))
+            }
+        }
+
+        struct BrokenEncoder<'a>(&'a mut dyn Write);
+
+        impl Write for BrokenEncoder<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.0.flush()
+            }
+        }
+
+        impl Encoder for BrokenEncoder<'_> {
+            fn finish(&mut self) -> io::Result<()> {
+                Err(ErrorKind::WriteZero.into())
+            }
+        }
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::default()
+                .codec(Arc::new(Broken))
+                .to_owned(),
+        )
+        .unwrap();
+        assert!(conn
+            .send_data_blocks("Subject: hi\r\n\r\nbody\r\n")
+            .is_err());
+    }
 }
@@ -1,18 +1,27 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::fmt;
 use std::io;
 use std::io::{ErrorKind, Write};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use log::*;
 use native_tls::TlsConnector;
 
-use crate::raw::compression::{Compression, Decoder};
+use crate::raw::buffer::BufferPool;
+use crate::raw::compression::{Codec, Compression};
+use crate::raw::encode::write_data_blocks;
 use crate::raw::error::Result;
+use crate::raw::interceptor::{Exchange, Interceptor};
+use crate::raw::metrics::{command_keyword, Metrics};
 use crate::raw::parse::{is_end_of_datablock, parse_data_block_line, parse_first_line};
-use crate::raw::response::{DataBlocks, RawResponse};
+use crate::raw::response::{DataBlocks, DataBlocksRef, RawResponse, RawResponseRef};
 use crate::raw::stream::NntpStream;
+use crate::raw::transcript::{self, Record, Replay, Tap, Transcript, TranscriptWriter};
 use crate::types::command::NntpCommand;
 use crate::types::prelude::*;
 
@@ -71,7 +80,7 @@ impl fmt::Debug for TlsConfig {
 /// `NntpConnection` DOES NOT...
 ///
 /// * Manage any of the stateful details of the connection such as server capabilities,
-/// selected group, or selected articles.
+///   selected group, or selected articles.
 /// * Perform detailed parsing of responses.
 ///
 /// For a more ergonomic client please see the [`NntpClient`](crate::client::NntpClient).
@@ -88,13 +97,19 @@ impl fmt::Debug for TlsConfig {
 /// * [`send`](Self::send) & [`send_bytes`](Self::send_bytes) for writing commands
 /// * [`read_response`](Self::read_response) & [`read_response_auto`](Self::read_response_auto)
 ///   for reading responses
+/// * [`read_response_borrowed`](Self::read_response_borrowed) for reading responses without
+///   copying them out of the connection's buffers
 /// ## Buffer Management
 ///
 /// The connection maintains several internal buffers for reading responses.
 /// These buffers may grow when reading large responses.
 ///
-/// The buffer sizes can be tuned via [`ConnectionConfig`], and they can be reset to their
-/// preconfigured size by calling [`NntpConnection::reset_buffers`].
+/// The buffer sizes can be tuned via [`ConnectionConfig`]. The buffers are shrunk back to
+/// their preconfigured size before each response is read.
+///
+/// Buffers are taken from a [`BufferPool`] that is shared by all connections in the process
+/// by default. Owned responses take the buffers they were read into rather than copying them,
+/// and return them to the pool when dropped.
 ///
 /// ## Example: Getting Capabilities
 ///
@@ -130,7 +145,30 @@ pub struct NntpConnection {
     stream: BufNntpStream,
     first_line_buf: Vec<u8>,
     data_blocks_buf: Vec<u8>,
+    line_boundaries_buf: Vec<(usize, usize)>,
+    buffer_pool: Option<Arc<BufferPool>>,
+    /// The last response read by `read_response_borrowed` if it was passed through interceptors
+    intercepted: Option<RawResponse>,
     config: ConnectionConfig,
+    transcript: Option<TranscriptWriter>,
+    in_flight: VecDeque<InFlight>,
+    /// True if the last response read was produced by an interceptor
+    ///
+    /// The server never saw the command, so it must not be sent data blocks for it either
+    short_circuited: bool,
+    /// The keyword of the last command sent, which data blocks are sent for
+    last_keyword: String,
+    metrics: Arc<Metrics>,
+}
+
+/// A command that has been sent (or short-circuited) but whose response has not been read
+#[derive(Debug)]
+struct InFlight {
+    command: Vec<u8>,
+    keyword: String,
+    sent_at: Instant,
+    /// The index of the interceptor that short-circuited the command and its response
+    short_circuit: Option<(usize, RawResponse)>,
 }
 
 impl NntpConnection {
@@ -140,18 +178,17 @@ impl NntpConnection {
         config: ConnectionConfig,
     ) -> Result<(Self, RawResponse)> {
         let ConnectionConfig {
-            compression: _,
             tls_config,
             read_timeout,
-            write_timeout: _,
-            first_line_buf_size,
-            data_blocks_buf_size,
+            write_timeout,
+            ..
         } = config.clone();
 
         trace!("Opening TcpStream...");
         let tcp_stream = TcpStream::connect(&addr)?;
 
         tcp_stream.set_read_timeout(read_timeout)?;
+        tcp_stream.set_write_timeout(write_timeout)?;
 
         let nntp_stream = if let Some(TlsConfig { connector, domain }) = tls_config.as_ref() {
             trace!("Wrapping TcpStream w/ TlsConnector");
@@ -161,14 +198,59 @@ impl NntpConnection {
             tcp_stream.into()
         };
 
-        let first_line_buf = Vec::with_capacity(first_line_buf_size);
-        let data_blocks_buf = Vec::with_capacity(data_blocks_buf_size);
+        Self::from_stream(nntp_stream, config)
+    }
+
+    /// Play back a recorded session
+    ///
+    /// The returned connection behaves like one connected to the server the transcript was
+    /// recorded from, as long as the same commands are sent in the same order.
+    /// See [`Replay`] for details.
+    ///
+    /// TLS settings in the config are ignored.
+    pub fn replay(transcript: Transcript, config: ConnectionConfig) -> Result<(Self, RawResponse)> {
+        Self::from_stream(Replay::new(transcript).into(), config)
+    }
+
+    fn from_stream(stream: NntpStream, config: ConnectionConfig) -> Result<(Self, RawResponse)> {
+        let transcript = match config.transcript.as_ref() {
+            Some(path) => {
+                debug!("Recording transcript to {}", path.display());
+                Some(TranscriptWriter::create(path)?)
+            }
+            None => None,
+        };
+
+        let metrics = config.metrics.clone().unwrap_or_default();
+        metrics.record_connection();
+
+        let buffer_pool = config.buffer_pool.clone();
+        let (first_line_buf, data_blocks_buf, line_boundaries_buf) = match buffer_pool.as_ref() {
+            Some(pool) => (
+                pool.bytes(config.first_line_buf_size),
+                pool.bytes(config.data_blocks_buf_size),
+                pool.line_boundaries(config.line_boundaries_buf_size),
+            ),
+            None => (
+                Vec::with_capacity(config.first_line_buf_size),
+                Vec::with_capacity(config.data_blocks_buf_size),
+                Vec::with_capacity(config.line_boundaries_buf_size),
+            ),
+        };
 
         let mut conn = Self {
-            stream: io::BufReader::new(nntp_stream),
+            stream: io::BufReader::new(stream),
             first_line_buf,
             data_blocks_buf,
+            line_boundaries_buf,
+            buffer_pool,
+            intercepted: None,
             config,
+            transcript,
+            in_flight: VecDeque::new(),
+            short_circuited: false,
+            last_keyword: String::new(),
+            metrics,
         };
 
         let initial_resp = conn.read_response_auto()?;
@@ -204,6 +286,14 @@ impl NntpConnection {
         Ok(resp)
     }
 
+    /// Send a command to the server and read the response without copying it
+    ///
+    /// See [`read_response_borrowed`](Self::read_response_borrowed) for details
+    pub fn command_borrowed<C: NntpCommand>(&mut self, command: &C) -> Result<RawResponseRef<'_>> {
+        self.send(command)?;
+        self.read_response_borrowed(None)
+    }
+
     /// Send a command to the server, returning the number of bytes written
     ///
     /// The caller is responsible for reading the response
@@ -215,16 +305,102 @@ impl NntpConnection {
     /// Send a command to the server, returning the number of bytes written
     ///
     /// This function can be used for commands not implemented/supported by the library
-    /// (e.g. `LISTGROUP misc.test 3000238-3000248`)
+    /// (e.g. `XPAT Subject 3000238-3000248 *brokaw*`)
     ///
     /// * The caller is responsible for reading the response
     /// * The command SHOULD NOT include the CRLF terminator
     pub fn send_bytes(&mut self, command: impl AsRef<[u8]>) -> Result<usize> {
+        let mut line = Vec::with_capacity(command.as_ref().len() + 2);
+        line.extend_from_slice(command.as_ref());
+
+        let short_circuit = self
+            .config
+            .interceptors
+            .iter()
+            .enumerate()
+            .find_map(|(i, interceptor)| interceptor.on_command(&mut line).map(|r| (i, r)));
+        self.last_keyword = command_keyword(&line);
+        let in_flight = InFlight {
+            keyword: self.last_keyword.clone(),
+            command: line.clone(),
+            sent_at: Instant::now(),
+            short_circuit,
+        };
+
+        if in_flight.short_circuit.is_some() {
+            trace!("Command was short-circuited by an interceptor");
+            self.in_flight.push_back(in_flight);
+            return Ok(0);
+        }
+
+        line.extend_from_slice(b"\r\n");
+
         let writer = self.stream.get_mut();
-        // Write the command and terminal char
-        let bytes = writer.write(command.as_ref())? + writer.write(b"\r\n")?;
-        // Flush the buffer
+        writer.write_all(&line)?;
+        writer.flush()?;
+
+        let bytes = line.len();
+        self.metrics.record_sent(Some(&in_flight.keyword), bytes);
+        self.in_flight.push_back(in_flight);
+        self.record(Record::Sent(transcript::redact(line)));
+        Ok(bytes)
+    }
+
+    /// Send a multi-line data block to the server, returning the number of bytes written
+    ///
+    /// This is used to send articles after the server has accepted a `POST`, `IHAVE` or
+    /// `TAKETHIS` command.
+    ///
+    /// * Lines will be dot-stuffed and terminated with CRLF
+    /// * The payload SHOULD NOT include the terminating `.` line, it will be added automatically
+    /// * The data blocks are encoded by the first registered [`Codec`] that provides an encoder
+    /// * The caller is responsible for reading the response
+    ///
+    /// Returns an [`InvalidInput`](io::ErrorKind::InvalidInput) error if the response to the last
+    /// command was produced by an [`Interceptor`], as the server is not expecting data blocks.
+    pub fn send_data_blocks(&mut self, payload: impl AsRef<[u8]>) -> Result<usize> {
+        if self.short_circuited {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Data blocks can not be sent for a short-circuited command",
+            )
+            .into());
+        }
+
+        if !self.config.interceptors.is_empty() {
+            let mut payload = payload.as_ref().to_vec();
+            self.config
+                .interceptors
+                .iter()
+                .for_each(|interceptor| interceptor.on_data_blocks(&mut payload));
+            return self.write_data_blocks(&payload);
+        }
+
+        self.write_data_blocks(payload.as_ref())
+    }
+
+    fn write_data_blocks(&mut self, payload: &[u8]) -> Result<usize> {
+        if self.transcript.is_some() {
+            let mut buf = Vec::with_capacity(payload.len() + 3);
+            let bytes =
+                encode_data_blocks(&self.config.codecs, &self.last_keyword, &mut buf, payload)?;
+            let writer = self.stream.get_mut();
+            writer.write_all(&buf)?;
+            writer.flush()?;
+            self.metrics.record_sent(None, bytes);
+            self.record(Record::Sent(buf));
+            return Ok(bytes);
+        }
+
+        let mut writer = io::BufWriter::new(self.stream.get_mut());
+        let bytes = encode_data_blocks(
+            &self.config.codecs,
+            &self.last_keyword,
+            &mut writer,
+            payload,
+        )?;
         writer.flush()?;
+        self.metrics.record_sent(None, bytes);
         Ok(bytes)
     }
 
@@ -247,65 +423,303 @@ impl NntpConnection {
     /// If `is_multiline` is set to None then the connection use [`ResponseCode::is_multiline`]
     /// to determine if it should expect a multiline response.
     /// This behavior can be overridden by manually specifying `Some(true)` or `Some(false)`
+    ///
+    /// Failure (4xx) and error (5xx) responses never contain data blocks, so they are returned
+    /// as single-line responses even if `Some(true)` is specified. This allows commands such as
+    /// `LISTGROUP` to be sent without knowing ahead of time whether they will succeed.
     pub fn read_response(&mut self, is_multiline: Option<bool>) -> Result<RawResponse> {
-        self.first_line_buf.truncate(0);
-        self.data_blocks_buf.truncate(0);
-        let resp_code = read_initial_response(&mut self.stream, &mut self.first_line_buf)?;
+        let in_flight = self.in_flight.pop_front();
+
+        let (mut resp, layers, short_circuited) =
+            match in_flight.as_ref().and_then(|f| f.short_circuit.as_ref()) {
+                Some((i, resp)) => (resp.clone(), i + 1, true),
+                None => {
+                    let (code, has_data_blocks) =
+                        self.read_response_recorded(is_multiline, in_flight.as_ref())?;
+                    (
+                        self.take_response(code, has_data_blocks),
+                        self.config.interceptors.len(),
+                        false,
+                    )
+                }
+            };
+        self.short_circuited = short_circuited;
+
+        if layers == 0 {
+            return Ok(resp);
+        }
+
+        let interceptors = self.config.interceptors.clone();
+        let exchange = Exchange {
+            command: in_flight.as_ref().map_or(&[], |f| &f.command),
+            elapsed: in_flight.as_ref().map(|f| f.sent_at.elapsed()),
+            short_circuited,
+        };
+        interceptors[..layers]
+            .iter()
+            .rev()
+            .for_each(|interceptor| interceptor.on_response(&exchange, &mut resp));
+
+        Ok(resp)
+    }
+
+    /// Read an NNTP response from the connection without copying it
+    ///
+    /// This behaves like [`read_response`](Self::read_response), but the returned response
+    /// references the connection's internal buffers rather than a copy of them.
+    /// The response must be dropped before the connection is used again, call
+    /// [`RawResponseRef::into_owned`] to keep it longer.
+    ///
+    /// Note that responses passed through [`Interceptors`](Interceptor) are still copied
+    /// as interceptors operate on owned responses.
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use brokaw::raw::connection::NntpConnection;
+    /// use brokaw::raw::transcript::Transcript;
+    /// use brokaw::types::command as cmd;
+    /// use brokaw::types::prelude::*;
+    /// use brokaw::ConnectionConfig;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let transcript = Transcript::load("tests/mozilla.transcript")?;
+    /// let (mut conn, _) = NntpConnection::replay(transcript, ConnectionConfig::default())?;
+    ///
+    /// let resp = conn.command_borrowed(&cmd::Capabilities)?;
+    /// let capabilities = Capabilities::try_from(&resp)?;
+    /// assert!(capabilities.get("VERSION").is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_response_borrowed(
+        &mut self,
+        is_multiline: Option<bool>,
+    ) -> Result<RawResponseRef<'_>> {
+        // Short-circuited commands imply that interceptors are registered
+        if !self.config.interceptors.is_empty() {
+            let resp = self.read_response(is_multiline)?;
+            return Ok(self.intercepted.insert(resp).as_borrowed());
+        }
+
+        let in_flight = self.in_flight.pop_front();
+        let (code, has_data_blocks) =
+            self.read_response_recorded(is_multiline, in_flight.as_ref())?;
+        self.short_circuited = false;
+
+        Ok(self.buffered_response(code, has_data_blocks))
+    }
+
+    /// Borrow the response most recently read into the connection's buffers
+    fn buffered_response(&self, code: ResponseCode, has_data_blocks: bool) -> RawResponseRef<'_> {
+        let data_blocks = if has_data_blocks {
+            Some(DataBlocksRef {
+                payload: &self.data_blocks_buf,
+                line_boundaries: &self.line_boundaries_buf,
+            })
+        } else {
+            None
+        };
+
+        RawResponseRef {
+            code,
+            first_line: &self.first_line_buf,
+            data_blocks,
+        }
+    }
+
+    /// Move the response most recently read into the connection's buffers into a [`RawResponse`]
+    ///
+    /// If the connection has a buffer pool the response takes the buffers and they are replaced
+    /// with buffers from the pool, otherwise the response is copied.
+    fn take_response(&mut self, code: ResponseCode, has_data_blocks: bool) -> RawResponse {
+        let pool = match self.buffer_pool.clone() {
+            Some(pool) => pool,
+            None => return self.buffered_response(code, has_data_blocks).into_owned(),
+        };
+
+        let first_line = std::mem::replace(
+            &mut self.first_line_buf,
+            pool.bytes(self.config.first_line_buf_size),
+        );
+        let data_blocks = if has_data_blocks {
+            Some(DataBlocks {
+                payload: std::mem::replace(
+                    &mut self.data_blocks_buf,
+                    pool.bytes(self.config.data_blocks_buf_size),
+                ),
+                line_boundaries: std::mem::replace(
+                    &mut self.line_boundaries_buf,
+                    pool.line_boundaries(self.config.line_boundaries_buf_size),
+                ),
+            })
+        } else {
+            None
+        };
+
+        RawResponse {
+            code,
+            first_line,
+            data_blocks,
+            pool: Some(pool),
+        }
+    }
+
+    /// Read a response, recording it to the transcript (if enabled) and the metrics
+    ///
+    /// Returns the response code and whether or not the response has data blocks, the response
+    /// itself is left in the connection's buffers
+    fn read_response_recorded(
+        &mut self,
+        is_multiline: Option<bool>,
+        in_flight: Option<&InFlight>,
+    ) -> Result<(ResponseCode, bool)> {
+        let mut raw = Vec::new();
+        let tap = self.transcript.as_ref().map(|_| &mut raw);
+        let keyword = in_flight.map_or("", |f| f.keyword.as_str());
+        let result = self.read_response_tapped(is_multiline, keyword, tap);
 
-        let data_blocks = match (is_multiline, resp_code.is_multiline()) {
+        // Record whatever was read even if the response is malformed
+        if self.transcript.is_some() {
+            self.record(Record::Received(raw));
+        }
+
+        let (code, has_data_blocks, decompressed, wire_bytes) = result?;
+        let decoded_bytes = self.first_line_buf.len() + self.data_blocks_buf.len();
+        self.metrics.record_response(
+            in_flight.map(|f| (f.keyword.as_str(), f.sent_at.elapsed())),
+            code,
+            wire_bytes,
+            decoded_bytes,
+        );
+
+        if decompressed && self.transcript.is_some() {
+            let mut decoded = self.first_line_buf.clone();
+            decoded.extend_from_slice(&self.data_blocks_buf);
+            self.record(Record::Decoded(decoded));
+        }
+
+        Ok((code, has_data_blocks))
+    }
+
+    /// Read a response into the connection's buffers, copying the bytes read from the stream
+    /// into `tap`
+    ///
+    /// `keyword` is the keyword of the command the response is for, which determines whether
+    /// some kinds of compressed responses are decoded
+    ///
+    /// Returns the response code, whether or not the response has data blocks, whether or not
+    /// it was decompressed, and the number of bytes read from the stream
+    fn read_response_tapped(
+        &mut self,
+        is_multiline: Option<bool>,
+        keyword: &str,
+        tap: Option<&mut Vec<u8>>,
+    ) -> Result<(ResponseCode, bool, bool, usize)> {
+        self.reset_buffers();
+        let mut tapped = Tap::new(&mut self.stream, tap);
+        let resp_code = read_initial_response(&mut tapped, &mut self.first_line_buf)?;
+        let mut decompressed = false;
+
+        let has_data_blocks = match (is_multiline, resp_code.is_multiline()) {
+            _ if resp_code.is_failure() || resp_code.is_error() => false,
             // Check for data blocks if the caller tells us to OR the kind is multiline
             (Some(true), _) | (_, true) => {
                 trace!("Parsing data blocks for response {}", u16::from(resp_code));
 
-                // FIXME(ops): Consider pre-allocating this buffer
-                let mut line_boundaries = Vec::with_capacity(10);
+                // XZVER responses are always compressed whether or not it is configured
+                let first_line = &self.first_line_buf;
+                let codec = self
+                    .config
+                    .codecs
+                    .iter()
+                    .map(|codec| codec.as_ref())
+                    .chain(self.config.compression.as_ref().map(|c| c as &dyn Codec))
+                    .chain(Some(&Compression::XZVer as &dyn Codec))
+                    .find(|codec| codec.use_decoder(keyword, first_line));
 
-                let mut stream = match self.config.compression {
-                    Some(c) if c.use_decoder(&self.first_line_buf) => {
-                        trace!("Compression enabled, wrapping stream with decoder");
-                        c.decoder(&mut self.stream)
+                match codec {
+                    Some(codec) => {
+                        trace!("Decoding data blocks with {:?}", codec);
+                        decompressed = true;
+                        let mut decoder = codec.decoder(&mut tapped);
+                        read_data_blocks(
+                            &mut decoder,
+                            &mut self.data_blocks_buf,
+                            &mut self.line_boundaries_buf,
+                        )?;
+                        // Drain the decoder so that any trailer (e.g. a checksum) is consumed
+                        io::copy(&mut decoder, &mut io::sink())?;
                     }
-                    _ => {
+                    None => {
                         trace!("Using passthrough decoder");
-                        Decoder::Passthrough(&mut self.stream)
+                        read_data_blocks(
+                            &mut tapped,
+                            &mut self.data_blocks_buf,
+                            &mut self.line_boundaries_buf,
+                        )?;
                     }
-                };
-
-                read_data_blocks(&mut stream, &mut self.data_blocks_buf, &mut line_boundaries)?;
-
-                Some(DataBlocks {
-                    payload: self.data_blocks_buf.clone(),
-                    line_boundaries,
-                })
+                }
+                true
             }
-            (Some(false), _) => None, // The caller says not to look for data blocks
-            _ => None,
+            (Some(false), _) => false, // The caller says not to look for data blocks
+            _ => false,
         };
 
-        let resp = RawResponse {
-            code: resp_code,
-            first_line: self.first_line_buf.clone(),
-            data_blocks,
-        };
+        let wire_bytes = tapped.consumed();
 
-        self.reset_buffers();
+        Ok((resp_code, has_data_blocks, decompressed, wire_bytes))
+    }
 
-        Ok(resp)
+    /// Append a record to the transcript if one is being recorded
+    ///
+    /// Failing to record is logged rather than failing the connection
+    fn record(&mut self, record: Record) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            if let Err(e) = transcript.record(&record) {
+                warn!("Failed to record transcript -- {}", e);
+            }
+        }
     }
 
-    /// Reset the connection's buffers to their initial size
+    /// Clear the connection's buffers and shrink them to their initial size
     ///
-    /// This should be run after reading responses to prevent the buffers from growing unbounded
+    /// This is run before reading each response to prevent the buffers from growing unbounded.
+    /// It is not run after reading as borrowed responses reference the buffers.
+    ///
+    /// Pooled buffers that have grown are swapped for smaller ones rather than shrunk, so the pool
+    /// can hand the larger buffer to a response that needs it.
     fn reset_buffers(&mut self) {
-        // Honestly we should probably just use the bytes create, it seems better suited to
-        //  what we want in this layer
-        self.first_line_buf
-            .truncate(self.config.first_line_buf_size);
-        self.first_line_buf.shrink_to_fit();
+        self.first_line_buf.clear();
+        self.data_blocks_buf.clear();
+        self.line_boundaries_buf.clear();
+        self.intercepted = None;
 
-        self.data_blocks_buf
-            .truncate(self.config.data_blocks_buf_size);
-        self.data_blocks_buf.shrink_to_fit();
+        let config = &self.config;
+        match self.buffer_pool.as_ref() {
+            Some(pool) => {
+                if self.first_line_buf.capacity() > config.first_line_buf_size {
+                    let buf = pool.bytes(config.first_line_buf_size);
+                    pool.recycle_bytes(std::mem::replace(&mut self.first_line_buf, buf));
+                }
+                if self.data_blocks_buf.capacity() > config.data_blocks_buf_size {
+                    let buf = pool.bytes(config.data_blocks_buf_size);
+                    pool.recycle_bytes(std::mem::replace(&mut self.data_blocks_buf, buf));
+                }
+                if self.line_boundaries_buf.capacity() > config.line_boundaries_buf_size {
+                    let buf = pool.line_boundaries(config.line_boundaries_buf_size);
+                    pool.recycle_line_boundaries(std::mem::replace(
+                        &mut self.line_boundaries_buf,
+                        buf,
+                    ));
+                }
+            }
+            None => {
+                self.first_line_buf.shrink_to(config.first_line_buf_size);
+                self.data_blocks_buf.shrink_to(config.data_blocks_buf_size);
+                self.line_boundaries_buf
+                    .shrink_to(config.line_boundaries_buf_size);
+            }
+        }
     }
 
     /// Get a ref to the underlying NntpStream
@@ -324,6 +738,32 @@ impl NntpConnection {
     pub fn config(&self) -> &ConnectionConfig {
         &self.config
     }
+
+    /// The metrics collected by the connection
+    ///
+    /// These are shared with any other connections created with the same
+    /// [`ConnectionConfig::metrics`].
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    /// Register an [`Interceptor`]
+    ///
+    /// The interceptor is added after any interceptors that are already registered
+    pub fn add_interceptor(&mut self, interceptor: Arc<dyn Interceptor>) -> &mut Self {
+        self.config.interceptors.push(interceptor);
+        self
+    }
+}
+
+impl Drop for NntpConnection {
+    fn drop(&mut self) {
+        if let Some(pool) = self.buffer_pool.as_ref() {
+            pool.recycle_bytes(std::mem::take(&mut self.first_line_buf));
+            pool.recycle_bytes(std::mem::take(&mut self.data_blocks_buf));
+            pool.recycle_line_boundaries(std::mem::take(&mut self.line_boundaries_buf));
+        }
+    }
 }
 
 /// A buffered NntpStream
@@ -333,22 +773,34 @@ pub type BufNntpStream = io::BufReader<NntpStream>;
 #[derive(Clone, Debug)]
 pub struct ConnectionConfig {
     pub(crate) compression: Option<Compression>,
+    pub(crate) codecs: Vec<Arc<dyn Codec>>,
     pub(crate) tls_config: Option<TlsConfig>,
     pub(crate) read_timeout: Option<Duration>,
     pub(crate) write_timeout: Option<Duration>,
     pub(crate) first_line_buf_size: usize,
     pub(crate) data_blocks_buf_size: usize,
+    pub(crate) line_boundaries_buf_size: usize,
+    pub(crate) buffer_pool: Option<Arc<BufferPool>>,
+    pub(crate) transcript: Option<PathBuf>,
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    pub(crate) metrics: Option<Arc<Metrics>>,
 }
 
 impl Default for ConnectionConfig {
     fn default() -> Self {
         ConnectionConfig {
             compression: None,
+            codecs: Vec::new(),
             tls_config: None,
             read_timeout: None,
             write_timeout: None,
             first_line_buf_size: 128,
             data_blocks_buf_size: 16 * 1024,
+            line_boundaries_buf_size: 256,
+            buffer_pool: Some(BufferPool::global()),
+            transcript: None,
+            interceptors: Vec::new(),
+            metrics: None,
         }
     }
 }
@@ -365,6 +817,15 @@ impl ConnectionConfig {
         self
     }
 
+    /// Register a [`Codec`] on connections created from this config
+    ///
+    /// Codecs are tried in the order they are registered, before the configured
+    /// [`compression`](Self::compression).
+    pub fn codec(&mut self, codec: Arc<dyn Codec>) -> &mut Self {
+        self.codecs.push(codec);
+        self
+    }
+
     /// Configure TLS on the connection
     pub fn tls_config(&mut self, config: Option<TlsConfig>) -> &mut Self {
         self.tls_config = config;
@@ -386,6 +847,12 @@ impl ConnectionConfig {
         self
     }
 
+    /// Set the write timeout on the socket
+    pub fn write_timeout(&mut self, dur: Option<Duration>) -> &mut Self {
+        self.write_timeout = dur;
+        self
+    }
+
     /// Set the size of the buffer used to read the first line
     pub fn first_line_buf_size(&mut self, s: usize) -> &mut Self {
         self.first_line_buf_size = s;
@@ -398,6 +865,50 @@ impl ConnectionConfig {
         self
     }
 
+    /// Set the number of lines the buffer used to track line boundaries can hold
+    pub fn line_boundaries_buf_size(&mut self, s: usize) -> &mut Self {
+        self.line_boundaries_buf_size = s;
+        self
+    }
+
+    /// Take buffers for reading responses from `pool`
+    ///
+    /// This defaults to the process wide [`BufferPool::global`] pool. With `None` the connection
+    /// allocates its own buffers and owned responses are copied out of them.
+    pub fn buffer_pool(&mut self, pool: Option<Arc<BufferPool>>) -> &mut Self {
+        self.buffer_pool = pool;
+        self
+    }
+
+    /// Record every command sent and response received to a transcript file
+    ///
+    /// The file is created (or truncated) when the connection is opened. Responses are recorded
+    /// exactly as they were received, compressed responses are additionally recorded after
+    /// decompression. See [`Transcript`] for the file format.
+    ///
+    /// Passwords sent with `AUTHINFO PASS` are redacted, usernames are recorded as is.
+    pub fn record_transcript(&mut self, path: Option<impl Into<PathBuf>>) -> &mut Self {
+        self.transcript = path.map(Into::into);
+        self
+    }
+
+    /// Register an [`Interceptor`] on connections created from this config
+    ///
+    /// Interceptors are applied in the order they are registered, see [`Interceptor`] for
+    /// details.
+    pub fn interceptor(&mut self, interceptor: Arc<dyn Interceptor>) -> &mut Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Collect metrics from connections created from this config into `metrics`
+    ///
+    /// If this is `None`, each connection collects its own [`Metrics`].
+    pub fn metrics(&mut self, metrics: Option<Arc<Metrics>>) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Create a connection from the config
     pub fn connect(&self, addr: impl ToSocketAddrs) -> Result<(NntpConnection, RawResponse)> {
         NntpConnection::connect(addr, self.clone())
@@ -413,7 +924,7 @@ fn read_initial_response<S: io::BufRead>(
     buffer: &mut Vec<u8>,
 ) -> Result<ResponseCode> {
     stream.read_until(b'\n', buffer)?;
-    let (_initial_line_buffer, resp) = parse_first_line(&buffer).map_err(|_e| {
+    let (_initial_line_buffer, resp) = parse_first_line(buffer).map_err(|_e| {
         io::Error::new(
             ErrorKind::InvalidData,
             "Failed to parse first line of response",
@@ -428,6 +939,84 @@ fn read_initial_response<S: io::BufRead>(
     Ok(code_u16.into())
 }
 
+/// Read an uncompressed response from any buffered reader
+///
+/// Unlike [`NntpConnection::read_response`], data blocks are read if and only if `is_multiline`
+/// is true or the response code indicates a multi-line response.
+pub(crate) fn read_raw_response<S: io::BufRead>(
+    stream: &mut S,
+    is_multiline: bool,
+) -> Result<RawResponse> {
+    let mut first_line = Vec::new();
+    let code = read_initial_response(stream, &mut first_line)?;
+
+    let data_blocks =
+        if !code.is_failure() && !code.is_error() && (is_multiline || code.is_multiline()) {
+            let mut payload = Vec::new();
+            let mut line_boundaries = Vec::new();
+            read_data_blocks(stream, &mut payload, &mut line_boundaries)?;
+            Some(DataBlocks {
+                payload,
+                line_boundaries,
+            })
+        } else {
+            None
+        };
+
+    Ok(RawResponse {
+        code,
+        first_line,
+        data_blocks,
+        pool: None,
+    })
+}
+
+/// Write data blocks, encoding them with the first codec that provides an encoder
+///
+/// Returns the number of bytes written to `writer`, i.e. after encoding
+fn encode_data_blocks(
+    codecs: &[Arc<dyn Codec>],
+    keyword: &str,
+    writer: &mut dyn Write,
+    payload: &[u8],
+) -> io::Result<usize> {
+    let written = Cell::new(0);
+    let mut writer = CountingWriter {
+        inner: writer,
+        written: &written,
+    };
+
+    for codec in codecs {
+        if let Some(mut encoder) = codec.encoder(keyword, &mut writer) {
+            // The caller flushes the writer
+            write_data_blocks(&mut encoder, payload)?;
+            encoder.finish()?;
+            return Ok(written.get());
+        }
+    }
+
+    write_data_blocks(&mut writer, payload)?;
+    Ok(written.get())
+}
+
+/// Counts the bytes written to the inner writer
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    written: &'a Cell<usize>,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written.set(self.written.get() + n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 /// Read multi-line data block portion from a stream
 ///
 /// * The data will be read line-by-line into the provided `buffer`
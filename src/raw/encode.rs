@@ -0,0 +1,58 @@
+use std::io;
+use std::io::Write;
+
+/// Write a payload as a [multi-line data block](https://tools.ietf.org/html/rfc3977#section-3.1.1)
+///
+/// 1. Lines are terminated with CRLF. Bare LF terminators are converted to CRLF.
+/// 2. Lines beginning with `.` are dot-stuffed
+/// 3. A final CRLF is added to the payload if it does not end with one
+/// 4. The terminating `.` line is written
+///
+/// Returns the number of bytes written
+pub(crate) fn write_data_blocks<W: Write + ?Sized>(w: &mut W, payload: &[u8]) -> io::Result<usize> {
+    let mut written = 0;
+
+    if !payload.is_empty() {
+        let payload = payload.strip_suffix(b"\n").unwrap_or(payload);
+        for line in payload.split(|b| *b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.starts_with(b".") {
+                w.write_all(b".")?;
+                written += 1;
+            }
+            w.write_all(line)?;
+            w.write_all(b"\r\n")?;
+            written += line.len() + 2;
+        }
+    }
+
+    w.write_all(b".\r\n")?;
+    Ok(written + 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(payload: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let written = write_data_blocks(&mut buf, payload).unwrap();
+        assert_eq!(written, buf.len());
+        buf
+    }
+
+    #[test]
+    fn test_dot_stuffing() {
+        assert_eq!(
+            encode(b"Subject: hi\r\n\r\n.hidden\r\n..\r\nend\r\n"),
+            b"Subject: hi\r\n\r\n..hidden\r\n...\r\nend\r\n.\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(encode(b"a\nb"), b"a\r\nb\r\n.\r\n".to_vec());
+        assert_eq!(encode(b"a\r\n\r\n"), b"a\r\n\r\n.\r\n".to_vec());
+        assert_eq!(encode(b""), b".\r\n".to_vec());
+    }
+}
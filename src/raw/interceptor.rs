@@ -0,0 +1,294 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::raw::response::RawResponse;
+
+/// Middleware that observes and modifies traffic on an [`NntpConnection`]
+///
+/// Interceptors are registered with [`ConnectionConfig::interceptor`],
+/// [`NntpConnection::add_interceptor`], or their [`NntpClient`](crate::client::NntpClient)
+/// equivalents. They are shared between connections created from the same config, so they take
+/// `&self` and must use interior mutability to keep state.
+///
+/// # Ordering
+///
+/// Interceptors are layered like an onion: commands pass through them in registration order and
+/// responses pass back in reverse order. If an interceptor short-circuits a command by returning
+/// a response from [`on_command`](Self::on_command), the interceptors registered after it never
+/// see the command or the response.
+///
+/// # Example: Counting Bytes
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use brokaw::raw::{Exchange, Interceptor, RawResponse};
+///
+/// #[derive(Debug, Default)]
+/// struct ByteCounter {
+///     sent: AtomicUsize,
+///     received: AtomicUsize,
+/// }
+///
+/// impl Interceptor for ByteCounter {
+///     fn on_command(&self, command: &mut Vec<u8>) -> Option<RawResponse> {
+///         self.sent.fetch_add(command.len() + 2, Ordering::Relaxed);
+///         None
+///     }
+///
+///     fn on_response(&self, _exchange: &Exchange<'_>, response: &mut RawResponse) {
+///         let data_blocks = response.data_blocks().map_or(0, |db| db.payload_len());
+///         self.received
+///             .fetch_add(response.first_line().len() + data_blocks, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+///
+/// [`NntpConnection`]: super::connection::NntpConnection
+/// [`NntpConnection::add_interceptor`]: super::connection::NntpConnection::add_interceptor
+/// [`ConnectionConfig::interceptor`]: super::connection::ConnectionConfig::interceptor
+pub trait Interceptor: fmt::Debug + Send + Sync {
+    /// Called before a command is sent
+    ///
+    /// The command does not include the CRLF terminator and may be modified in place.
+    ///
+    /// Returning a response short-circuits the command: nothing is sent to the server and the
+    /// response is returned by the next call to
+    /// [`read_response`](super::connection::NntpConnection::read_response).
+    /// As the server never saw the command, data blocks can not be sent after a short-circuited
+    /// `IHAVE` or `POST`.
+    fn on_command(&self, command: &mut Vec<u8>) -> Option<RawResponse> {
+        let _ = command;
+        None
+    }
+
+    /// Called before a multi-line data block (e.g. an article for `IHAVE`) is sent
+    ///
+    /// The payload has not been dot-stuffed yet and may be modified in place.
+    fn on_data_blocks(&self, payload: &mut Vec<u8>) {
+        let _ = payload;
+    }
+
+    /// Called after a response has been read or produced by a short-circuit
+    fn on_response(&self, exchange: &Exchange<'_>, response: &mut RawResponse) {
+        let _ = (exchange, response);
+    }
+}
+
+/// Details about the command a response belongs to
+#[derive(Clone, Debug)]
+pub struct Exchange<'a> {
+    pub(crate) command: &'a [u8],
+    pub(crate) elapsed: Option<Duration>,
+    pub(crate) short_circuited: bool,
+}
+
+impl Exchange<'_> {
+    /// The command as it was sent, without the CRLF terminator
+    ///
+    /// This is empty for the server's greeting and for responses read without sending a command.
+    pub fn command(&self) -> &[u8] {
+        self.command
+    }
+
+    /// The time between sending the command and reading the response
+    ///
+    /// This is `None` if no command was sent
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// True if the response was produced by an interceptor rather than the server
+    pub fn is_short_circuited(&self) -> bool {
+        self.short_circuited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::raw::connection::{ConnectionConfig, NntpConnection};
+    use crate::raw::transcript::{Record, Transcript};
+    use crate::types::command as cmd;
+    use crate::types::prelude::*;
+
+    /// Logs every command and response it sees
+    #[derive(Debug)]
+    struct Log {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Interceptor for Log {
+        fn on_command(&self, command: &mut Vec<u8>) -> Option<RawResponse> {
+            self.events.lock().unwrap().push(format!(
+                "{} > {}",
+                self.name,
+                String::from_utf8_lossy(command)
+            ));
+            None
+        }
+
+        fn on_response(&self, exchange: &Exchange<'_>, response: &mut RawResponse) {
+            assert_eq!(exchange.elapsed().is_some(), !exchange.command().is_empty());
+            self.events.lock().unwrap().push(format!(
+                "{} < {}{}",
+                self.name,
+                response.code(),
+                if exchange.is_short_circuited() {
+                    " (cached)"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
+
+    /// Answers `DATE` without asking the server and tags outgoing articles
+    #[derive(Debug)]
+    struct Local;
+
+    impl Interceptor for Local {
+        fn on_command(&self, command: &mut Vec<u8>) -> Option<RawResponse> {
+            if command == b"DATE" {
+                Some(RawResponse::new(Kind::Date, "20200605000000"))
+            } else {
+                None
+            }
+        }
+
+        fn on_data_blocks(&self, payload: &mut Vec<u8>) {
+            payload.splice(0..0, b"X-Via: brokaw\r\n".iter().copied());
+        }
+    }
+
+    #[test]
+    fn test_interceptors() {
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"IHAVE <a@b>\r\n".to_vec()));
+        transcript.push(Record::Received(b"335 send it\r\n".to_vec()));
+        transcript.push(Record::Sent(
+            b"X-Via: brokaw\r\nSubject: hi\r\n\r\nbody\r\n.\r\n".to_vec(),
+        ));
+        transcript.push(Record::Received(b"235 thanks\r\n".to_vec()));
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = |name| {
+            Arc::new(Log {
+                name,
+                events: events.clone(),
+            })
+        };
+
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::new()
+                .interceptor(log("outer"))
+                .interceptor(Arc::new(Local))
+                .to_owned(),
+        )
+        .unwrap();
+        conn.add_interceptor(log("inner"));
+
+        let date = conn.command(&cmd::Date).unwrap();
+        assert_eq!(date.first_line(), b"111 20200605000000\r\n");
+
+        let resp = conn.command(&cmd::IHave("<a@b>".to_string())).unwrap();
+        assert_eq!(resp.code(), Kind::IHaveSendArticle.into());
+        conn.send_data_blocks("Subject: hi\r\n\r\nbody\r\n")
+            .unwrap();
+        assert_eq!(
+            conn.read_response_auto().unwrap().code(),
+            Kind::ArticleTransferredOk.into()
+        );
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "outer < 200",
+                "outer > DATE",
+                "outer < 111 (cached)",
+                "outer > IHAVE <a@b>",
+                "inner > IHAVE <a@b>",
+                "inner < 335",
+                "outer < 335",
+                "inner < 235",
+                "outer < 235",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_short_circuited_ihave() {
+        /// Claims the server wants every article
+        #[derive(Debug)]
+        struct Eager;
+
+        impl Interceptor for Eager {
+            fn on_command(&self, command: &mut Vec<u8>) -> Option<RawResponse> {
+                if command.starts_with(b"IHAVE") {
+                    Some(RawResponse::new(Kind::IHaveSendArticle, "send it"))
+                } else {
+                    None
+                }
+            }
+        }
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"DATE\r\n".to_vec()));
+        transcript.push(Record::Received(b"111 20200605000000\r\n".to_vec()));
+
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::new()
+                .interceptor(Arc::new(Eager))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let resp = conn.command(&cmd::IHave("<a@b>".to_string())).unwrap();
+        assert_eq!(resp.code(), Kind::IHaveSendArticle.into());
+        assert!(conn
+            .send_data_blocks("Subject: hi\r\n\r\nbody\r\n")
+            .is_err());
+
+        // Nothing was sent, so the connection is still in sync with the server
+        let date = conn.command(&cmd::Date).unwrap();
+        assert_eq!(date.code(), Kind::Date.into());
+    }
+
+    #[test]
+    fn test_client_interceptors() {
+        use crate::client::ClientConfig;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let transcript = Transcript::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/mozilla.transcript"
+        ))
+        .unwrap();
+
+        ClientConfig::default()
+            .interceptor(Arc::new(Log {
+                name: "client",
+                events: events.clone(),
+            }))
+            .group(Some("mozilla.dev.platform"))
+            .replay(transcript)
+            .unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "client < 200",
+                "client > CAPABILITIES",
+                "client < 101",
+                "client > GROUP mozilla.dev.platform",
+                "client < 211",
+            ]
+        );
+    }
+}
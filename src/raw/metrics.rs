@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::types::response_code::{Kind, ResponseCode};
+
+/// Upper bounds of the latency histogram buckets, in milliseconds
+const LATENCY_BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Counters and histograms collected by an [`NntpConnection`]
+///
+/// Every connection records into a `Metrics`. By default each connection gets its own, but a
+/// single `Metrics` can be shared between connections via
+/// [`ConnectionConfig::metrics`](super::connection::ConnectionConfig::metrics), e.g. to aggregate
+/// all connections to one provider.
+///
+/// Only traffic that reaches the wire is counted: commands short-circuited by an
+/// [`Interceptor`](super::interceptor::Interceptor) are ignored. Responses that do not follow a
+/// command (the greeting, the response to an article sent with
+/// [`send_data_blocks`](super::connection::NntpConnection::send_data_blocks)) are only counted
+/// in the byte totals.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use brokaw::ConnectionConfig;
+/// use brokaw::raw::{Metrics, NntpConnection, Transcript};
+/// use brokaw::types::command as cmd;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let metrics = Arc::new(Metrics::new());
+///     let (mut conn, _) = NntpConnection::replay(
+///         Transcript::load("tests/mozilla.transcript")?,
+///         ConnectionConfig::default().metrics(Some(metrics.clone())).to_owned(),
+///     )?;
+///     conn.command(&cmd::Capabilities)?;
+///
+///     let snapshot = metrics.snapshot();
+///     assert_eq!(snapshot.connections, 1);
+///     assert_eq!(snapshot.commands["CAPABILITIES"].responses[&101], 1);
+///     Ok(())
+/// }
+/// ```
+///
+/// [`NntpConnection`]: super::connection::NntpConnection
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsSnapshot>,
+}
+
+impl Metrics {
+    /// Create an empty set of metrics
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A copy of the metrics collected so far
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.lock().clone()
+    }
+
+    /// Return the metrics collected so far and reset them
+    ///
+    /// This is useful for reporting deltas to a metrics backend.
+    pub fn take(&self) -> MetricsSnapshot {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Count a new connection
+    pub(crate) fn record_connection(&self) {
+        self.lock().connections += 1;
+    }
+
+    /// Count a connection that was opened to replace one that failed
+    pub(crate) fn record_reconnect(&self) {
+        self.lock().reconnects += 1;
+    }
+
+    /// Count bytes written to the server
+    pub(crate) fn record_sent(&self, keyword: Option<&str>, bytes: usize) {
+        let mut inner = self.lock();
+        inner.bytes_sent += bytes as u64;
+        if let Some(keyword) = keyword {
+            command_entry(&mut inner, keyword).sent += 1;
+        }
+    }
+
+    /// Record a response read from the server
+    ///
+    /// `wire_bytes` is the number of bytes read from the stream and `decompressed_bytes` is the
+    /// size of the response after any decompression.
+    pub(crate) fn record_response(
+        &self,
+        command: Option<(&str, Duration)>,
+        code: ResponseCode,
+        wire_bytes: usize,
+        decompressed_bytes: usize,
+    ) {
+        let mut inner = self.lock();
+        inner.wire_bytes_received += wire_bytes as u64;
+        inner.decompressed_bytes_received += decompressed_bytes as u64;
+
+        // RFC 4643 reserves these codes for rejected credentials, whichever command triggered them
+        if matches!(
+            code,
+            ResponseCode::Known(Kind::AuthenticationFailed)
+                | ResponseCode::Known(Kind::AuthenticationOutOfSequence)
+        ) {
+            inner.auth_failures += 1;
+        }
+
+        if let Some((keyword, latency)) = command {
+            let code = u16::from(code);
+            let entry = command_entry(&mut inner, keyword);
+            *entry.responses.entry(code).or_default() += 1;
+            entry.latency.record(latency);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MetricsSnapshot> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn command_entry<'a>(snapshot: &'a mut MetricsSnapshot, keyword: &str) -> &'a mut CommandMetrics {
+    // Avoid allocating a key for commands that have already been seen
+    if !snapshot.commands.contains_key(keyword) {
+        snapshot
+            .commands
+            .insert(keyword.to_string(), Default::default());
+    }
+    snapshot.commands.get_mut(keyword).unwrap()
+}
+
+/// A point-in-time copy of [`Metrics`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Metrics for each command, keyed by the uppercase command keyword (e.g. `ARTICLE`)
+    pub commands: BTreeMap<String, CommandMetrics>,
+    /// Bytes written to the server, including articles sent as data blocks
+    pub bytes_sent: u64,
+    /// Bytes read from the server
+    pub wire_bytes_received: u64,
+    /// Bytes read from the server after decompression
+    ///
+    /// This equals [`wire_bytes_received`](Self::wire_bytes_received) unless compression is
+    /// enabled.
+    pub decompressed_bytes_received: u64,
+    /// The number of connections opened
+    pub connections: u64,
+    /// The number of connections opened to replace a failed connection
+    ///
+    /// Only connections reopened by the proxy's upstream pool and by
+    /// [`FailoverClient`](crate::client::FailoverClient) are counted. An [`NntpConnection`]
+    /// never reconnects by itself.
+    ///
+    /// [`NntpConnection`]: super::connection::NntpConnection
+    pub reconnects: u64,
+    /// The number of `481` and `482` responses, i.e. credentials rejected by the server
+    pub auth_failures: u64,
+}
+
+impl MetricsSnapshot {
+    /// The ratio of decompressed bytes to bytes on the wire
+    ///
+    /// Returns `None` if nothing has been received
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.wire_bytes_received == 0 {
+            None
+        } else {
+            Some(self.decompressed_bytes_received as f64 / self.wire_bytes_received as f64)
+        }
+    }
+
+    /// The total number of commands sent
+    pub fn commands_sent(&self) -> u64 {
+        self.commands.values().map(|c| c.sent).sum()
+    }
+}
+
+/// Metrics for a single command
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandMetrics {
+    /// The number of times the command was sent
+    pub sent: u64,
+    /// The number of responses received, keyed by response code
+    pub responses: BTreeMap<u16, u64>,
+    /// The time between sending the command and reading its response
+    pub latency: Histogram,
+}
+
+/// A latency histogram with fixed buckets
+///
+/// Buckets have upper bounds of 1ms, 5ms, 10ms, 25ms, 50ms, 100ms, 250ms, 500ms, 1s, 2.5s,
+/// 5s, 10s, and a final unbounded bucket.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Histogram {
+    counts: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    sum: Duration,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; LATENCY_BUCKETS_MS.len() + 1],
+            sum: Duration::default(),
+        }
+    }
+}
+
+impl Histogram {
+    /// Add an observation
+    pub fn record(&mut self, value: Duration) {
+        let i = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&ms| value <= Duration::from_millis(ms))
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[i] += 1;
+        self.sum += value;
+    }
+
+    /// The number of observations
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The sum of all observations
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// The mean of all observations, `None` if there are none
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            n => Some(self.sum / n as u32),
+        }
+    }
+
+    /// An iterator over the upper bound of each bucket and the number of observations in it
+    ///
+    /// The last bucket is unbounded and has an upper bound of `None`.
+    /// Counts are per-bucket, not cumulative.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKETS_MS
+            .iter()
+            .map(|&ms| Some(Duration::from_millis(ms)))
+            .chain(std::iter::once(None))
+            .zip(self.counts.iter().copied())
+    }
+
+    /// An estimate of the `q`th quantile (e.g. `0.99`)
+    ///
+    /// This is the upper bound of the bucket the quantile falls in, or `None` if there are no
+    /// observations or the quantile falls in the unbounded bucket.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bound, n) in self.buckets() {
+            seen += n;
+            if seen >= rank {
+                return bound;
+            }
+        }
+        None
+    }
+}
+
+/// The uppercase keyword of a command, e.g. `ARTICLE` for `article <foo@bar>`
+pub(crate) fn command_keyword(command: &[u8]) -> String {
+    let end = command
+        .iter()
+        .position(|b| b.is_ascii_whitespace())
+        .unwrap_or(command.len());
+    String::from_utf8_lossy(&command[..end]).to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.mean(), None);
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for ms in &[1, 3, 7, 40, 20_000] {
+            histogram.record(Duration::from_millis(*ms));
+        }
+
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.sum(), Duration::from_millis(20_051));
+        assert_eq!(histogram.quantile(0.2), Some(Duration::from_millis(1)));
+        assert_eq!(histogram.quantile(0.4), Some(Duration::from_millis(5)));
+        assert_eq!(histogram.quantile(0.8), Some(Duration::from_millis(50)));
+        assert_eq!(histogram.quantile(1.0), None);
+        assert_eq!(histogram.buckets().last(), Some((None, 1)));
+    }
+
+    #[test]
+    fn test_record() {
+        let metrics = Metrics::new();
+        metrics.record_sent(Some("AUTHINFO"), 20);
+        metrics.record_response(
+            Some(("AUTHINFO", Duration::from_millis(2))),
+            Kind::AuthenticationFailed.into(),
+            30,
+            30,
+        );
+        metrics.record_sent(Some("XOVER"), 10);
+        metrics.record_response(
+            Some(("XOVER", Duration::from_millis(2))),
+            Kind::Overview.into(),
+            100,
+            400,
+        );
+        metrics.record_response(None, Kind::PostingAllowed.into(), 20, 20);
+        // Already authenticated, which is not a failure
+        metrics.record_response(
+            Some(("AUTHINFO", Duration::from_millis(2))),
+            Kind::PermanentlyUnavailable.into(),
+            0,
+            0,
+        );
+        metrics.record_response(None, Kind::AuthenticationOutOfSequence.into(), 0, 0);
+
+        let snapshot = metrics.take();
+        assert_eq!(snapshot.auth_failures, 2);
+        assert_eq!(snapshot.bytes_sent, 30);
+        assert_eq!(snapshot.commands_sent(), 2);
+        assert_eq!(snapshot.commands["XOVER"].responses[&224], 1);
+        assert_eq!(snapshot.commands["XOVER"].latency.count(), 1);
+        assert_eq!(snapshot.compression_ratio(), Some(3.0));
+
+        assert_eq!(metrics.snapshot(), MetricsSnapshot::default());
+    }
+
+    #[test]
+    fn test_connection_metrics() {
+        use crate::raw::compression::Compression;
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::raw::transcript::{Record, Transcript};
+        use crate::types::command::XOver;
+
+        let compressed = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/xover_resp_xfeature_compress"
+        ));
+        // The fixture contains two responses, only the first is used
+        let second = compressed
+            .windows(4)
+            .skip(1)
+            .position(|w| w == b"224 ")
+            .unwrap();
+        let compressed = &compressed[..second + 1];
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"XOVER 1-5\r\n".to_vec()));
+        transcript.push(Record::Received(compressed.to_vec()));
+
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::new()
+                .compression(Some(Compression::XFeature))
+                .to_owned(),
+        )
+        .unwrap();
+        conn.command(&XOver::Range { low: 1, high: 5 }).unwrap();
+
+        let snapshot = conn.metrics().snapshot();
+        assert_eq!(snapshot.connections, 1);
+        assert_eq!(snapshot.bytes_sent, 11);
+        assert_eq!(snapshot.wire_bytes_received as usize, 11 + compressed.len());
+        assert!(snapshot.compression_ratio().unwrap() > 1.0);
+
+        let xover = &snapshot.commands["XOVER"];
+        assert_eq!(xover.sent, 1);
+        assert_eq!(xover.responses[&224], 1);
+        assert_eq!(xover.latency.count(), 1);
+    }
+
+    #[test]
+    fn test_command_keyword() {
+        assert_eq!(command_keyword(b"article <a@b>"), "ARTICLE");
+        assert_eq!(command_keyword(b"DATE"), "DATE");
+    }
+}
@@ -1,9 +1,21 @@
+/// Reusable response buffers
+pub mod buffer;
+
 /// Raw connection implementation
 pub mod connection;
 
 /// Low level API errors
 pub mod error;
 
+/// Wire format encoding
+pub(crate) mod encode;
+
+/// Command/response middleware
+pub mod interceptor;
+
+/// Connection metrics
+pub mod metrics;
+
 /// Response parsing logic
 ///
 /// * The parsing is line based
@@ -17,14 +29,25 @@ pub mod response;
 /// Raw TCP stream implementation
 pub(crate) mod stream;
 
+/// Session recording and playback
+pub mod transcript;
+
+#[doc(inline)]
+pub use buffer::{BufferPool, BufferPoolStats};
 #[doc(inline)]
 pub use connection::{NntpConnection, TlsConfig};
 #[doc(inline)]
-pub use response::{DataBlocks, RawResponse};
+pub use interceptor::{Exchange, Interceptor};
+#[doc(inline)]
+pub use metrics::{CommandMetrics, Histogram, Metrics, MetricsSnapshot};
+#[doc(inline)]
+pub use response::{DataBlocks, DataBlocksRef, RawResponse, RawResponseRef};
 
 #[doc(inline)]
 pub use stream::NntpStream;
+#[doc(inline)]
+pub use transcript::{Record, Replay, Transcript};
 
 pub(crate) mod compression;
 
-pub use compression::Compression;
+pub use compression::{Codec, Compression, Encoder};
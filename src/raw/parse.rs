@@ -103,7 +103,7 @@ mod tests {
             let expected_resp = InitialResponseLine {
                 code: b"200",
                 data: &b"news.example.com InterNetNews server INN 2.5.5 ready (transit mode)"[..],
-                buffer: &MOTD,
+                buffer: MOTD,
             };
             assert_eq!(raw_response, expected_resp)
         }
@@ -1,7 +1,10 @@
 use std::borrow::Cow;
 use std::str::{from_utf8, from_utf8_unchecked};
+use std::sync::Arc;
 
 use crate::error::Error;
+use crate::raw::buffer::BufferPool;
+use crate::raw::encode::write_data_blocks;
 
 use crate::types::response_code::ResponseCode;
 
@@ -9,14 +12,64 @@ use crate::types::response_code::ResponseCode;
 ///
 /// 1. The contents are guaranteed to be represent a syntactically valid NNTP response
 /// 2. The contents ARE NOT guaranteed to be UTF-8 as the NNTP does not require contents be UTF-8.
+///
+/// Responses read by a connection with a [`BufferPool`] return their buffers to the pool when
+/// dropped.
 #[derive(Clone, Debug)]
 pub struct RawResponse {
     pub(crate) code: ResponseCode,
     pub(crate) first_line: Vec<u8>,
     pub(crate) data_blocks: Option<DataBlocks>,
+    pub(crate) pool: Option<Arc<BufferPool>>,
 }
 
 impl RawResponse {
+    /// Create a single-line response
+    ///
+    /// The first line will consist of the response code followed by `text`.
+    /// `text` SHOULD NOT contain a CRLF terminator.
+    ///
+    /// ```
+    /// use brokaw::types::prelude::*;
+    ///
+    /// let resp = RawResponse::new(Kind::ArticleExists, "3000234 <45223423@example.com>");
+    /// assert_eq!(resp.first_line(), b"223 3000234 <45223423@example.com>\r\n");
+    /// ```
+    pub fn new(code: impl Into<ResponseCode>, text: impl AsRef<str>) -> Self {
+        let code = code.into();
+        let text = text.as_ref();
+        let first_line = if text.is_empty() {
+            format!("{}\r\n", code)
+        } else {
+            format!("{} {}\r\n", code, text)
+        };
+
+        Self {
+            code,
+            first_line: first_line.into_bytes(),
+            data_blocks: None,
+            pool: None,
+        }
+    }
+
+    /// Create a multi-line response
+    ///
+    /// The payload will be converted to wire format as described in
+    /// [`NntpConnection::send_data_blocks`](super::connection::NntpConnection::send_data_blocks).
+    pub fn with_data_blocks(
+        code: impl Into<ResponseCode>,
+        text: impl AsRef<str>,
+        payload: impl AsRef<[u8]>,
+    ) -> Self {
+        let mut resp = Self::new(code, text);
+        let mut wire = Vec::with_capacity(payload.as_ref().len() + 3);
+        // writes to a Vec are infallible
+        write_data_blocks(&mut wire, payload.as_ref()).expect("Failed to write to vec");
+        resp.data_blocks =
+            Some(DataBlocks::from_wire(wire).expect("Encoded data blocks are always valid"));
+        resp
+    }
+
     /// The response code
     pub fn code(&self) -> ResponseCode {
         self.code
@@ -24,7 +77,7 @@ impl RawResponse {
 
     /// Return true if this response is a multi-line response and contains a data block section
     pub fn has_data_blocks(&self) -> bool {
-        matches!(self.data_blocks, Some(_))
+        self.data_blocks.is_some()
     }
 
     /// Return multi-line data blocks
@@ -67,6 +120,108 @@ impl RawResponse {
     pub unsafe fn first_line_as_utf8_unchecked(&self) -> &str {
         from_utf8_unchecked(&self.first_line)
     }
+
+    /// Borrow the response as a [`RawResponseRef`]
+    pub fn as_borrowed(&self) -> RawResponseRef<'_> {
+        RawResponseRef {
+            code: self.code,
+            first_line: &self.first_line,
+            data_blocks: self.data_blocks.as_ref().map(DataBlocks::as_borrowed),
+        }
+    }
+}
+
+/// A response that borrows its contents
+///
+/// This is returned by [`NntpConnection::read_response_borrowed`] and references the
+/// connection's internal buffers, so reading a response does not require copying it.
+/// The response is only valid until the next read from the connection, call
+/// [`into_owned`](Self::into_owned) to keep it around longer.
+///
+/// The typed responses (e.g. [`Head`](crate::types::response::Head) and
+/// [`Body`](crate::types::response::Body)) can be parsed directly from a `RawResponseRef`.
+///
+/// [`NntpConnection::read_response_borrowed`]: super::connection::NntpConnection::read_response_borrowed
+#[derive(Copy, Clone, Debug)]
+pub struct RawResponseRef<'a> {
+    pub(crate) code: ResponseCode,
+    pub(crate) first_line: &'a [u8],
+    pub(crate) data_blocks: Option<DataBlocksRef<'a>>,
+}
+
+impl<'a> RawResponseRef<'a> {
+    /// The response code
+    pub fn code(&self) -> ResponseCode {
+        self.code
+    }
+
+    /// Return true if this response is a multi-line response and contains a data block section
+    pub fn has_data_blocks(&self) -> bool {
+        self.data_blocks.is_some()
+    }
+
+    /// Return multi-line data blocks
+    pub fn data_blocks(&self) -> Option<DataBlocksRef<'a>> {
+        self.data_blocks
+    }
+
+    /// Return the first line of the response
+    pub fn first_line(&self) -> &'a [u8] {
+        self.first_line
+    }
+
+    /// Return the first line of the response without the response code
+    pub fn first_line_without_code(&self) -> &'a [u8] {
+        // n.b. this should be infallible barring bugs in the response parsing layer
+        &self.first_line[4..]
+    }
+
+    /// Lossily convert the first line to UTF-8
+    pub fn first_line_to_utf8_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.first_line)
+    }
+
+    /// Converts a response into an error if it does not match the provided status
+    ///
+    /// The response is copied into the error.
+    pub fn fail_unless(self, desired: impl Into<ResponseCode>) -> Result<Self, Error> {
+        if self.code() != desired.into() {
+            Err(Error::failure(self.into_owned()))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Copy the response into a [`RawResponse`]
+    pub fn into_owned(self) -> RawResponse {
+        RawResponse {
+            code: self.code,
+            first_line: self.first_line.to_vec(),
+            data_blocks: self.data_blocks.map(|data_blocks| DataBlocks {
+                payload: data_blocks.payload.to_vec(),
+                line_boundaries: data_blocks.line_boundaries.to_vec(),
+            }),
+            pool: None,
+        }
+    }
+}
+
+impl Drop for RawResponse {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.recycle_bytes(std::mem::take(&mut self.first_line));
+            if let Some(data_blocks) = self.data_blocks.take() {
+                pool.recycle_bytes(data_blocks.payload);
+                pool.recycle_line_boundaries(data_blocks.line_boundaries);
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a RawResponse> for RawResponseRef<'a> {
+    fn from(resp: &'a RawResponse) -> Self {
+        resp.as_borrowed()
+    }
 }
 
 /// The [Multi-line Data Blocks](https://tools.ietf.org/html/rfc3977#section-3.1.1)
@@ -78,7 +233,7 @@ impl RawResponse {
 /// [`DataBlocks::payload`](Self::payload) returns the raw bytes in the payload
 /// * [`DataBlocks::lines`](Self::lines) returns an iterator over the lines within the block
 /// * [`DataBlocks::unterminated`](Self::unterminated) returns an iterator over the lines with the
-/// CRLF terminator and the final `.` line of the response stripped
+///   CRLF terminator and the final `.` line of the response stripped
 #[derive(Clone, Debug)]
 pub struct DataBlocks {
     pub(crate) payload: Vec<u8>,
@@ -86,6 +241,43 @@ pub struct DataBlocks {
 }
 
 impl DataBlocks {
+    /// Create data blocks from a payload that is already in wire format
+    ///
+    /// Returns an error unless the payload is made up of complete lines, the last of which is the
+    /// terminating `.` line
+    pub(crate) fn from_wire(payload: Vec<u8>) -> Result<Self, Error> {
+        let mut line_boundaries = Vec::new();
+        let mut start = 0;
+        for (i, _) in payload.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            if payload[start..=i] == *b".\r\n" && i + 1 != payload.len() {
+                return Err(Error::de("Data blocks continue past the terminating line"));
+            }
+            line_boundaries.push((start, i + 1));
+            start = i + 1;
+        }
+
+        if start != payload.len() {
+            return Err(Error::de("Data blocks end with a partial line"));
+        }
+        match line_boundaries.last() {
+            Some((start, end)) if payload[*start..*end] == *b".\r\n" => {}
+            _ => return Err(Error::de("Data blocks are not terminated")),
+        }
+
+        Ok(Self {
+            payload,
+            line_boundaries,
+        })
+    }
+
+    /// Borrow the data blocks as a [`DataBlocksRef`]
+    pub fn as_borrowed(&self) -> DataBlocksRef<'_> {
+        DataBlocksRef {
+            payload: &self.payload,
+            line_boundaries: &self.line_boundaries,
+        }
+    }
+
     /// Return the raw contained by the payload of the Datablocks
     pub fn payload(&self) -> &[u8] {
         &self.payload
@@ -98,10 +290,7 @@ impl DataBlocks {
 
     /// An iterator over the lines within the data block
     pub fn lines(&self) -> Lines<'_> {
-        Lines {
-            data_blocks: self,
-            inner: self.line_boundaries.iter(),
-        }
+        self.as_borrowed().lines()
     }
 
     /// An iterator over the unterminated data block
@@ -130,10 +319,63 @@ impl DataBlocks {
     }
 }
 
+/// The data blocks of a [`RawResponseRef`]
+///
+/// This provides the same accessors as [`DataBlocks`] without owning the payload
+#[derive(Copy, Clone, Debug)]
+pub struct DataBlocksRef<'a> {
+    pub(crate) payload: &'a [u8],
+    pub(crate) line_boundaries: &'a [(usize, usize)],
+}
+
+impl<'a> DataBlocksRef<'a> {
+    /// Return the raw contained by the payload of the Datablocks
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// A convenience function that simply calls [`from_utf8`]
+    pub fn payload_as_utf8(&self) -> Result<&'a str, std::str::Utf8Error> {
+        from_utf8(self.payload)
+    }
+
+    /// An iterator over the lines within the data block
+    pub fn lines(&self) -> Lines<'a> {
+        Lines {
+            payload: self.payload,
+            inner: self.line_boundaries.iter(),
+        }
+    }
+
+    /// An iterator over the unterminated data block
+    ///
+    /// See [`DataBlocks::unterminated`]
+    pub fn unterminated(&self) -> Unterminated<'a> {
+        Unterminated {
+            inner: self.lines(),
+        }
+    }
+
+    /// The number of lines
+    pub fn lines_len(&self) -> usize {
+        self.line_boundaries.len()
+    }
+
+    /// The number of bytes in the data block
+    pub fn payload_len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Returns true if there are no lines
+    pub fn is_empty(&self) -> bool {
+        self.line_boundaries.is_empty()
+    }
+}
+
 /// An iterator over the data blocks within a response
 #[derive(Clone, Debug)]
 pub struct Lines<'a> {
-    data_blocks: &'a DataBlocks,
+    payload: &'a [u8],
     inner: std::slice::Iter<'a, (usize, usize)>,
 }
 
@@ -142,7 +384,7 @@ impl<'a> Iterator for Lines<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some((start, end)) = self.inner.next() {
-            Some(&self.data_blocks.payload[*start..*end])
+            Some(&self.payload[*start..*end])
         } else {
             None
         }
@@ -168,3 +410,111 @@ impl<'a> Iterator for Unterminated<'a> {
         //unimplemented!()
     }
 }
+
+/// Responses are serialized with the first line and data blocks as bytes in wire format
+#[cfg(feature = "serde")]
+impl serde::Serialize for RawResponse {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use crate::types::bytes::Bytes;
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("RawResponse", 3)?;
+        s.serialize_field("code", &self.code)?;
+        s.serialize_field("first_line", &Bytes(&self.first_line))?;
+        s.serialize_field(
+            "data_blocks",
+            &self.data_blocks.as_ref().map(|db| Bytes(&db.payload)),
+        )?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RawResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "RawResponse")]
+        struct Repr {
+            code: ResponseCode,
+            #[serde(deserialize_with = "crate::types::bytes::deserialize")]
+            first_line: Vec<u8>,
+            #[serde(default, deserialize_with = "data_blocks")]
+            data_blocks: Option<Vec<u8>>,
+        }
+
+        fn data_blocks<'de, D: serde::Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<u8>>, D::Error> {
+            #[derive(serde::Deserialize)]
+            struct Payload(#[serde(deserialize_with = "crate::types::bytes::deserialize")] Vec<u8>);
+
+            Option::<Payload>::deserialize(deserializer).map(|p| p.map(|p| p.0))
+        }
+
+        let Repr {
+            code,
+            first_line,
+            data_blocks,
+        } = Repr::deserialize(deserializer)?;
+
+        Ok(Self {
+            code,
+            first_line,
+            data_blocks: data_blocks
+                .map(DataBlocks::from_wire)
+                .transpose()
+                .map_err(serde::de::Error::custom)?,
+            pool: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_wire() {
+        let data_blocks = DataBlocks::from_wire(b"a\r\n..b\r\n.\r\n".to_vec()).unwrap();
+        assert_eq!(data_blocks.line_boundaries, vec![(0, 3), (3, 8), (8, 11)]);
+        assert!(DataBlocks::from_wire(b".\r\n".to_vec()).is_ok());
+
+        for invalid in [
+            &b""[..],
+            b"a\r\n",
+            b"a.\r\n",
+            b"a\r\n.\r\npartial",
+            b"a\r\n.\r\nb\r\n.\r\n",
+        ]
+        .iter()
+        {
+            assert!(DataBlocks::from_wire(invalid.to_vec()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let resp = RawResponse::with_data_blocks(220, "0 <a@b.c>", "first\r\n.dotted\r\n");
+        let borrowed = resp.as_borrowed();
+
+        assert_eq!(borrowed.code(), resp.code());
+        assert_eq!(borrowed.first_line(), resp.first_line());
+
+        let data_blocks = borrowed.data_blocks().unwrap();
+        assert_eq!(data_blocks.payload(), b"first\r\n..dotted\r\n.\r\n");
+        assert_eq!(
+            data_blocks.unterminated().collect::<Vec<_>>(),
+            resp.data_blocks()
+                .unwrap()
+                .unterminated()
+                .collect::<Vec<_>>()
+        );
+
+        let owned = borrowed.into_owned();
+        assert_eq!(owned.first_line(), resp.first_line());
+        assert_eq!(
+            owned.data_blocks().unwrap().payload(),
+            resp.data_blocks().unwrap().payload()
+        );
+    }
+}
@@ -4,13 +4,18 @@ use native_tls::TlsStream;
 use std::io;
 use std::io::{Read, Write};
 
+use crate::raw::transcript::Replay;
+
 /// A raw NNTP session
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum NntpStream {
     /// A stream using TLS
     Tls(TlsStream<TcpStream>),
     /// A plain text stream
     Tcp(TcpStream),
+    /// A recorded session played back from a transcript
+    Replay(Replay),
 }
 
 impl From<TlsStream<TcpStream>> for NntpStream {
@@ -25,11 +30,18 @@ impl From<TcpStream> for NntpStream {
     }
 }
 
+impl From<Replay> for NntpStream {
+    fn from(replay: Replay) -> NntpStream {
+        Self::Replay(replay)
+    }
+}
+
 impl Read for NntpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self {
             NntpStream::Tls(s) => s.read(buf),
             NntpStream::Tcp(s) => s.read(buf),
+            NntpStream::Replay(s) => s.read(buf),
         }
     }
 }
@@ -39,6 +51,7 @@ impl Write for NntpStream {
         match self {
             NntpStream::Tls(s) => s.write(buf),
             NntpStream::Tcp(s) => s.write(buf),
+            NntpStream::Replay(s) => s.write(buf),
         }
     }
 
@@ -46,6 +59,7 @@ impl Write for NntpStream {
         match self {
             NntpStream::Tls(s) => s.flush(),
             NntpStream::Tcp(s) => s.flush(),
+            NntpStream::Replay(s) => s.flush(),
         }
     }
 }
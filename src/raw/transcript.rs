@@ -0,0 +1,509 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+
+/// The first line of every transcript
+const MAGIC: &str = "brokaw-transcript 1";
+
+/// Passwords are replaced with this line when recording a transcript
+const REDACTED_PASS: &[u8] = b"AUTHINFO PASS <redacted>\r\n";
+
+/// Returns true if `line` sends a password with `AUTHINFO PASS`
+fn is_authinfo_pass(line: &[u8]) -> bool {
+    line.len() >= 14 && line[..14].eq_ignore_ascii_case(b"AUTHINFO PASS ")
+}
+
+/// Remove the password from an `AUTHINFO PASS` command before it is recorded
+pub(crate) fn redact(line: Vec<u8>) -> Vec<u8> {
+    if is_authinfo_pass(&line) {
+        REDACTED_PASS.to_vec()
+    } else {
+        line
+    }
+}
+
+/// A single entry in a [`Transcript`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Record {
+    /// Bytes sent to the server, such as a command or a posted article
+    Sent(Vec<u8>),
+    /// Bytes received from the server exactly as they were read from the socket
+    Received(Vec<u8>),
+    /// A response after decompression
+    ///
+    /// This is only recorded for compressed responses, for all others it would be identical to
+    /// the preceding [`Received`](Record::Received) record.
+    Decoded(Vec<u8>),
+}
+
+impl Record {
+    fn tag(&self) -> &'static str {
+        match self {
+            Record::Sent(_) => "C",
+            Record::Received(_) => "S",
+            Record::Decoded(_) => "D",
+        }
+    }
+
+    /// The raw bytes of the record
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Record::Sent(b) | Record::Received(b) | Record::Decoded(b) => b,
+        }
+    }
+}
+
+/// A recording of the traffic on an [`NntpConnection`](super::connection::NntpConnection)
+///
+/// Transcripts are recorded by enabling
+/// [`ConnectionConfig::record_transcript`](super::connection::ConnectionConfig::record_transcript)
+/// and can be replayed with [`NntpConnection::replay`](super::connection::NntpConnection::replay)
+/// or [`ClientConfig::replay`](crate::client::ClientConfig::replay).
+///
+/// # Format
+///
+/// Transcripts are mostly human readable. After a `brokaw-transcript 1` header line,
+/// every record consists of a tag and byte count on their own line, followed by the raw bytes
+/// and a newline.
+///
+/// * `C` - bytes sent by the client
+/// * `S` - bytes received from the server
+/// * `D` - a decompressed response
+///
+/// ```text
+/// brokaw-transcript 1
+/// S 19
+/// 200 news.mozilla.org
+///
+/// C 29
+/// GROUP mozilla.dev.platform
+///
+/// S 39
+/// 211 47660 2 47661 mozilla.dev.platform
+///
+/// ```
+///
+/// Note that the lengths include the CRLF terminators which aren't visible above.
+///
+/// Passwords sent with `AUTHINFO PASS` are recorded as `AUTHINFO PASS <redacted>`, which matches
+/// any password when the transcript is replayed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Transcript {
+    records: Vec<Record>,
+}
+
+impl Transcript {
+    /// Create an empty transcript
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Load a transcript from a file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        Self::read_from(&mut io::BufReader::new(file))
+    }
+
+    /// Read a transcript
+    pub fn read_from(r: &mut impl BufRead) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut line = String::new();
+        r.read_line(&mut line)?;
+        if line.trim_end() != MAGIC {
+            return Err(invalid("Missing transcript header".to_string()));
+        }
+
+        let mut records = Vec::new();
+        loop {
+            line.clear();
+            if r.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let mut header = line.split_whitespace();
+            let (tag, len) = match (header.next(), header.next().map(str::parse::<usize>)) {
+                (Some(tag), Some(Ok(len))) => (tag, len),
+                _ => {
+                    return Err(invalid(format!(
+                        "Invalid record header `{}`",
+                        line.trim_end()
+                    )))
+                }
+            };
+
+            let mut bytes = vec![0; len];
+            r.read_exact(&mut bytes)?;
+            let mut newline = [0];
+            r.read_exact(&mut newline)?;
+            if newline != *b"\n" {
+                return Err(invalid(format!(
+                    "Record {} is not terminated",
+                    records.len()
+                )));
+            }
+
+            records.push(match tag {
+                "C" => Record::Sent(bytes),
+                "S" => Record::Received(bytes),
+                "D" => Record::Decoded(bytes),
+                other => return Err(invalid(format!("Unknown record tag `{}`", other))),
+            });
+        }
+
+        Ok(Self { records })
+    }
+
+    /// Save the transcript to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    /// Write the transcript
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "{}", MAGIC)?;
+        self.records
+            .iter()
+            .try_for_each(|record| write_record(w, record))
+    }
+
+    /// Append a record
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record)
+    }
+
+    /// The records in the transcript
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+}
+
+fn write_record(w: &mut impl Write, record: &Record) -> io::Result<()> {
+    writeln!(w, "{} {}", record.tag(), record.bytes().len())?;
+    w.write_all(record.bytes())?;
+    w.write_all(b"\n")
+}
+
+/// Appends records to a transcript file as they happen
+#[derive(Debug)]
+pub(crate) struct TranscriptWriter {
+    file: io::BufWriter<fs::File>,
+}
+
+impl TranscriptWriter {
+    pub(crate) fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        writeln!(file, "{}", MAGIC)?;
+        file.flush()?;
+        Ok(Self { file })
+    }
+
+    /// Write a record, flushing it immediately so the transcript survives crashes
+    pub(crate) fn record(&mut self, record: &Record) -> io::Result<()> {
+        write_record(&mut self.file, record)?;
+        self.file.flush()
+    }
+}
+
+/// A buffered reader that copies every byte consumed into `tap`
+///
+/// This captures the bytes of a response exactly as they were read off the wire, even if they
+/// are subsequently decompressed.
+pub(crate) struct Tap<'a, S> {
+    inner: S,
+    tap: Option<&'a mut Vec<u8>>,
+    consumed: usize,
+}
+
+impl<'a, S: BufRead> Tap<'a, S> {
+    pub(crate) fn new(inner: S, tap: Option<&'a mut Vec<u8>>) -> Self {
+        Self {
+            inner,
+            tap,
+            consumed: 0,
+        }
+    }
+
+    /// The number of bytes consumed from the inner reader
+    pub(crate) fn consumed(&self) -> usize {
+        self.consumed
+    }
+}
+
+impl<S: BufRead> Read for Tap<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<S: BufRead> BufRead for Tap<'_, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Some(tap) = self.tap.as_mut() {
+            if let Ok(buf) = self.inner.fill_buf() {
+                tap.extend_from_slice(&buf[..amt.min(buf.len())]);
+            }
+        }
+        self.consumed += amt;
+        self.inner.consume(amt)
+    }
+}
+
+/// A transport that plays back the server's side of a [`Transcript`]
+///
+/// Commands written to the transport must match the commands in the transcript byte-for-byte,
+/// otherwise an [`InvalidInput`](io::ErrorKind::InvalidInput) error is returned. This makes
+/// replays deterministic: a client that behaves differently than it did when the transcript was
+/// recorded will fail rather than silently reading the wrong responses.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    records: VecDeque<Record>,
+    /// The position within the record at the front of the queue
+    pos: usize,
+}
+
+impl Replay {
+    /// Create a replay of a transcript
+    ///
+    /// [`Decoded`](Record::Decoded) records are ignored, the connection will decompress
+    /// [`Received`](Record::Received) records itself.
+    pub fn new(transcript: Transcript) -> Self {
+        let records = transcript
+            .records
+            .into_iter()
+            .filter(|r| !matches!(r, Record::Decoded(_)))
+            .collect();
+
+        Self { records, pos: 0 }
+    }
+
+    /// Returns true if every record in the transcript has been played back
+    pub fn is_finished(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.pos += n;
+        if self.pos == self.records.front().map_or(0, |r| r.bytes().len()) {
+            self.records.pop_front();
+            self.pos = 0;
+        }
+    }
+}
+
+impl Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.records.front() {
+            Some(Record::Received(bytes)) => {
+                let remaining = &bytes[self.pos..];
+                let n = remaining.len().min(buf.len());
+                buf[..n].copy_from_slice(&remaining[..n]);
+                self.advance(n);
+                Ok(n)
+            }
+            Some(Record::Sent(bytes)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Transcript expects `{}` to be sent before reading",
+                    String::from_utf8_lossy(bytes).trim_end()
+                ),
+            )),
+            _ => Ok(0),
+        }
+    }
+}
+
+impl Write for Replay {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mismatch = |expected: &[u8]| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Sent `{}` but the transcript expects `{}`",
+                    String::from_utf8_lossy(buf).trim_end(),
+                    String::from_utf8_lossy(expected).trim_end()
+                ),
+            )
+        };
+
+        match self.records.front() {
+            Some(Record::Sent(expected))
+                if expected == REDACTED_PASS && self.pos == 0 && is_authinfo_pass(buf) =>
+            {
+                self.advance(expected.len());
+                Ok(buf.len())
+            }
+            Some(Record::Sent(expected)) => {
+                let remaining = &expected[self.pos..];
+                let n = remaining.len().min(buf.len());
+                if buf[..n] != remaining[..n] {
+                    return Err(mismatch(expected));
+                }
+                self.advance(n);
+                Ok(n)
+            }
+            Some(Record::Received(_)) => Err(mismatch(b"<nothing, a response is pending>")),
+            _ => Err(mismatch(b"<nothing, the transcript has ended>")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Helpers for building transcripts in tests
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"DATE\r\n".to_vec()));
+        transcript.push(Record::Received(b"111 20200605000000\r\n".to_vec()));
+        transcript
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let transcript = transcript();
+        let mut buf = Vec::new();
+        transcript.write_to(&mut buf).unwrap();
+        assert!(buf.starts_with(b"brokaw-transcript 1\nS 11\n200 ready\r\n\nC 6\nDATE\r\n\n"));
+
+        assert_eq!(
+            Transcript::read_from(&mut buf.as_slice()).unwrap(),
+            transcript
+        );
+        assert!(Transcript::read_from(&mut &b"garbage\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_record_replayed_connection() {
+        use crate::raw::compression::Compression;
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::types::command::XOver;
+
+        let compressed = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/xover_resp_xfeature_compress"
+        ));
+        let plain = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/xover_resp_plain_text"
+        ));
+
+        // The fixture contains two responses, only the first is used
+        let second = compressed
+            .windows(4)
+            .skip(1)
+            .position(|w| w == b"224 ")
+            .unwrap();
+        let compressed = &compressed[..second + 1];
+
+        let mut transcript = transcript();
+        transcript.push(Record::Sent(b"XOVER 1-5\r\n".to_vec()));
+        transcript.push(Record::Received(compressed.to_vec()));
+
+        let path = std::env::temp_dir().join(format!("brokaw-{}.transcript", std::process::id()));
+        let (mut conn, greeting) = NntpConnection::replay(
+            transcript.clone(),
+            ConnectionConfig::new()
+                .compression(Some(Compression::XFeature))
+                .record_transcript(Some(&path))
+                .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(greeting.first_line(), b"200 ready\r\n");
+
+        // Commands that differ from the transcript are rejected
+        assert!(conn.command(&crate::types::command::Help).is_err());
+
+        conn.command(&crate::types::command::Date).unwrap();
+        let resp = conn.command(&XOver::Range { low: 1, high: 5 }).unwrap();
+        assert_eq!(resp.data_blocks().unwrap().payload(), &plain[..]);
+
+        let recorded = Transcript::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (decoded, recorded) = recorded.records().split_last().unwrap();
+        assert_eq!(recorded, transcript.records());
+        let mut expected = b"224 xover information follows [COMPRESS=GZIP]\r\n".to_vec();
+        expected.extend_from_slice(plain);
+        assert_eq!(decoded, &Record::Decoded(expected));
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut replay = Replay::new(transcript());
+        let mut buf = [0; 64];
+
+        assert_eq!(replay.read(&mut buf).unwrap(), 11);
+        assert!(replay.read(&mut buf).is_err());
+
+        let err = replay.write_all(b"HELP\r\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        replay.write_all(b"DA").unwrap();
+        replay.write_all(b"TE\r\n").unwrap();
+        let n = replay.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"111 20200605000000\r\n");
+        assert!(replay.is_finished());
+        assert_eq!(replay.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_passwords_are_redacted() {
+        use crate::raw::connection::{ConnectionConfig, NntpConnection};
+        use crate::types::command::AuthInfo;
+
+        let mut transcript = Transcript::new();
+        transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+        transcript.push(Record::Sent(b"AUTHINFO USER reader\r\n".to_vec()));
+        transcript.push(Record::Received(b"381 Password required\r\n".to_vec()));
+        transcript.push(Record::Sent(b"AUTHINFO PASS secret\r\n".to_vec()));
+        transcript.push(Record::Received(b"281 Ok\r\n".to_vec()));
+
+        let path =
+            std::env::temp_dir().join(format!("brokaw-{}-redacted.transcript", std::process::id()));
+        let (mut conn, _) = NntpConnection::replay(
+            transcript,
+            ConnectionConfig::new()
+                .record_transcript(Some(&path))
+                .to_owned(),
+        )
+        .unwrap();
+        conn.command(&AuthInfo::User("reader".to_string())).unwrap();
+        conn.command(&AuthInfo::Pass("secret".to_string())).unwrap();
+
+        let recorded = Transcript::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            recorded.records()[3],
+            Record::Sent(b"AUTHINFO PASS <redacted>\r\n".to_vec())
+        );
+
+        // A redacted password matches any password on replay
+        let (mut conn, _) = NntpConnection::replay(recorded, ConnectionConfig::default()).unwrap();
+        conn.command(&AuthInfo::User("reader".to_string())).unwrap();
+        let resp = conn
+            .command(&AuthInfo::Pass("hunter2".to_string()))
+            .unwrap();
+        assert_eq!(resp.first_line(), b"281 Ok\r\n");
+    }
+}
@@ -0,0 +1,36 @@
+use super::{Record, Transcript};
+
+/// Append a command sent by the client and the server's response to a transcript
+///
+/// The command is terminated with CRLF, the response is recorded as is.
+pub(crate) fn exchange(transcript: &mut Transcript, command: &str, response: impl AsRef<[u8]>) {
+    transcript.push(Record::Sent(format!("{}\r\n", command).into_bytes()));
+    transcript.push(Record::Received(response.as_ref().to_vec()));
+}
+
+/// A transcript containing only the server greeting
+pub(crate) fn greeting() -> Transcript {
+    let mut transcript = Transcript::new();
+    transcript.push(Record::Received(b"200 ready\r\n".to_vec()));
+    transcript
+}
+
+/// A transcript of a server greeting followed by a `CAPABILITIES` exchange
+pub(crate) fn session(capabilities: &[&str]) -> Transcript {
+    let mut transcript = greeting();
+
+    let mut resp = "101 Capability list:\r\n".to_string();
+    for capability in capabilities {
+        resp.push_str(capability);
+        resp.push_str("\r\n");
+    }
+    resp.push_str(".\r\n");
+    exchange(&mut transcript, "CAPABILITIES", resp);
+
+    transcript
+}
+
+/// A [`session`] with a server that advertises `VERSION 2` and `READER`
+pub(crate) fn reader_session() -> Transcript {
+    session(&["VERSION 2", "READER"])
+}
@@ -0,0 +1,843 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::atomic;
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// A set of scoring rules, i.e. a kill file
+///
+/// Rules add or subtract points from articles, or hide them entirely, based on their headers.
+/// They can be evaluated against overview records (to score a group while it loads) or full
+/// article [`Headers`].
+///
+/// # Format
+///
+/// ```text
+/// # Rules before the first section apply to every group
+/// kill
+///     from = *@spam.example
+///
+/// [comp.lang.*,!comp.lang.java.*]
+/// score 50
+///     subject ~ \brust\b
+/// score -20 expires 2030-01-01
+///     subject ~ ^re:
+///     !references ~ <my-post@example\.com>
+/// score -100
+///     xref > 4
+/// ```
+///
+/// * `[wildmat]` starts a section; the rules that follow only apply to matching groups
+/// * `score N` or `kill` starts a rule. `expires YYYY-MM-DD` removes the rule after that day (UTC).
+/// * Each following line is a condition; a rule applies if *all* of its conditions match
+/// * A condition is `field op value`, optionally negated with a leading `!`
+///     * `~` matches a (case-insensitive) regular expression
+///     * `=` matches a [`Wildmat`]
+///     * `>` and `<` compare numbers
+/// * Lines starting with `#` are comments
+///
+/// See [`Field`] for the fields that can be matched.
+///
+/// # Example
+///
+/// ```
+/// use brokaw::score::ScoreFile;
+/// use brokaw::types::prelude::*;
+///
+/// let rules: ScoreFile = "[comp.*]\nscore 10\n  subject ~ rust\nkill\n  lines > 1000\n"
+///     .parse()
+///     .unwrap();
+/// let scorer = rules.scorer("comp.lang.rust");
+///
+/// let overview = Overview {
+///     number: 1,
+///     subject: "Rust 1.0 released".to_string(),
+///     from: "someone@example.com".to_string(),
+///     date: "Fri, 15 May 2015 10:00:00 +0000".to_string(),
+///     message_id: "<1@example.com>".to_string(),
+///     references: "".to_string(),
+///     bytes: Some(1234),
+///     lines: Some(20),
+///     extra: vec![],
+/// };
+/// let score = scorer.score_overview(&overview);
+/// assert_eq!(score.points, 10);
+/// assert!(!score.killed);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScoreFile {
+    rules: Vec<Rule>,
+}
+
+/// A single scoring rule within a [`ScoreFile`]
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// The groups the rule applies to
+    pub groups: Wildmat,
+    /// What to do with matching articles
+    pub action: Action,
+    /// The conditions that must all match for the rule to apply
+    pub conditions: Vec<Condition>,
+    expires: Option<(i32, u32, u32)>,
+}
+
+/// What a [`Rule`] does to the articles it matches
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Add (or subtract) points
+    Score(i32),
+    /// Hide the article
+    Kill,
+}
+
+/// A test against a single field of an article
+#[derive(Clone, Debug)]
+pub struct Condition {
+    /// The field to test
+    pub field: Field,
+    /// How to test the field
+    pub matcher: Matcher,
+    /// Invert the result of the matcher
+    pub negated: bool,
+}
+
+/// A field of an article that can be scored
+///
+/// Text matchers can be used with `Subject`, `From`, `Message-ID`, `References`, and `Xref`.
+/// Numeric matchers can be used with `Lines`, `Bytes`, `Age` (in days, from the `Date` header),
+/// `References` (the number of message-ids, i.e. the depth of the thread), and `Xref`
+/// (the number of groups the article is cross-posted to).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[allow(missing_docs)]
+pub enum Field {
+    Subject,
+    From,
+    MessageId,
+    References,
+    Xref,
+    Lines,
+    Bytes,
+    Age,
+}
+
+/// How a [`Condition`] tests a field
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    /// The field matches a regular expression
+    Regex(Regex),
+    /// The field matches a wildmat
+    Wildmat(Wildmat),
+    /// The field is greater than a number
+    GreaterThan(u64),
+    /// The field is less than a number
+    LessThan(u64),
+}
+
+/// The result of scoring an article
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Score {
+    /// The sum of the points from every matching rule
+    pub points: i32,
+    /// The article matched a `kill` rule and should be hidden
+    pub killed: bool,
+}
+
+impl ScoreFile {
+    /// Create an empty score file
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Read a score file from disk
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Write the score file to disk
+    ///
+    /// Like [`Newsrc::save`](crate::newsrc::Newsrc::save), the file is replaced atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        atomic::write_file(path.as_ref(), |w| write!(w, "{}", self))?;
+        Ok(())
+    }
+
+    /// Add a rule
+    pub fn push(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    /// The rules in the file
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Remove expired rules, returning the number of rules removed
+    pub fn expire(&mut self, now: SystemTime) -> usize {
+        let before = self.rules.len();
+        self.rules.retain(|rule| !rule.is_expired(now));
+        before - self.rules.len()
+    }
+
+    /// Prepare the rules that apply to a group
+    pub fn scorer(&self, group: impl AsRef<str>) -> Scorer {
+        self.scorer_at(group, SystemTime::now())
+    }
+
+    /// Prepare the rules that apply to a group as of `now`
+    ///
+    /// `now` is used to skip expired rules and to determine the age of articles.
+    pub fn scorer_at(&self, group: impl AsRef<str>, now: SystemTime) -> Scorer {
+        let group = group.as_ref();
+        let mut rules = self
+            .rules
+            .iter()
+            .filter(|rule| !rule.is_expired(now) && rule.groups.matches(group))
+            .map(|rule| (rule.action, rule.conditions.clone()))
+            .collect::<Vec<_>>();
+        // Evaluate kill rules first so that killed articles can skip the remaining rules
+        rules.sort_by_key(|(action, _)| *action != Action::Kill);
+
+        Scorer {
+            rules,
+            now: unix_seconds(now),
+        }
+    }
+}
+
+impl Rule {
+    /// Create a rule that applies to every group
+    ///
+    /// A rule without conditions matches every article.
+    pub fn new(action: Action) -> Self {
+        Self {
+            groups: Wildmat::any(),
+            action,
+            conditions: Vec::new(),
+            expires: None,
+        }
+    }
+
+    /// Restrict the rule to groups that match the wildmat
+    pub fn groups(&mut self, groups: Wildmat) -> &mut Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Add a condition
+    pub fn condition(&mut self, field: Field, matcher: Matcher, negated: bool) -> &mut Self {
+        self.conditions.push(Condition {
+            field,
+            matcher,
+            negated,
+        });
+        self
+    }
+
+    /// Expire the rule after the given day (UTC)
+    pub fn expires(&mut self, year: i32, month: u32, day: u32) -> Result<&mut Self> {
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(Error::parse_error("expires"));
+        }
+        self.expires = Some((year, month, day));
+        Ok(self)
+    }
+
+    /// Returns true if the rule has expired
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|(year, month, day)| {
+            let end_of_day = (days_from_civil(year, month, day) + 1) * SECONDS_PER_DAY;
+            unix_seconds(now) >= end_of_day
+        })
+    }
+}
+
+impl Matcher {
+    /// Create a case-insensitive regex matcher
+    pub fn regex(pattern: impl AsRef<str>) -> Result<Self> {
+        RegexBuilder::new(pattern.as_ref())
+            .case_insensitive(true)
+            .build()
+            .map(Matcher::Regex)
+            .map_err(|e| Error::de(e.to_string()))
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Matcher::GreaterThan(_) | Matcher::LessThan(_))
+    }
+}
+
+impl Field {
+    fn name(&self) -> &'static str {
+        match self {
+            Field::Subject => "subject",
+            Field::From => "from",
+            Field::MessageId => "message-id",
+            Field::References => "references",
+            Field::Xref => "xref",
+            Field::Lines => "lines",
+            Field::Bytes => "bytes",
+            Field::Age => "age",
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        !matches!(self, Field::Lines | Field::Bytes | Field::Age)
+    }
+
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Field::Subject | Field::From | Field::MessageId)
+    }
+}
+
+impl FromStr for Field {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let field = match s.to_ascii_lowercase().as_str() {
+            "subject" => Field::Subject,
+            "from" => Field::From,
+            "message-id" => Field::MessageId,
+            "references" => Field::References,
+            "xref" => Field::Xref,
+            "lines" => Field::Lines,
+            "bytes" => Field::Bytes,
+            "age" => Field::Age,
+            _ => return Err(Error::de(format!("Unknown field `{}`", s))),
+        };
+        Ok(field)
+    }
+}
+
+/// The rules from a [`ScoreFile`] that apply to a single group
+///
+/// Created by [`ScoreFile::scorer`]. Rule selection and regex compilation happen once, so
+/// scoring an article only evaluates the conditions of the applicable rules.
+#[derive(Clone, Debug)]
+pub struct Scorer {
+    rules: Vec<(Action, Vec<Condition>)>,
+    now: i64,
+}
+
+impl Scorer {
+    /// Score an overview record
+    pub fn score_overview(&self, overview: &Overview) -> Score {
+        self.score(&OverviewFields(overview))
+    }
+
+    /// Score a set of article headers
+    pub fn score_headers(&self, headers: &Headers) -> Score {
+        self.score(&HeaderFields(headers))
+    }
+
+    /// Returns true if no rules apply to the group
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn score(&self, article: &dyn Fields) -> Score {
+        let mut score = Score::default();
+        for (action, conditions) in &self.rules {
+            if conditions.iter().all(|c| self.matches(c, article)) {
+                match action {
+                    Action::Kill => {
+                        score.killed = true;
+                        return score;
+                    }
+                    Action::Score(points) => score.points = score.points.saturating_add(*points),
+                }
+            }
+        }
+        score
+    }
+
+    fn matches(&self, condition: &Condition, article: &dyn Fields) -> bool {
+        let matched = match &condition.matcher {
+            Matcher::Regex(regex) => article
+                .text(condition.field)
+                .is_some_and(|text| regex.is_match(&text)),
+            Matcher::Wildmat(wildmat) => article
+                .text(condition.field)
+                .is_some_and(|text| wildmat.matches(text)),
+            Matcher::GreaterThan(n) => self
+                .number(condition.field, article)
+                .is_some_and(|v| v > *n),
+            Matcher::LessThan(n) => self
+                .number(condition.field, article)
+                .is_some_and(|v| v < *n),
+        };
+        matched != condition.negated
+    }
+
+    fn number(&self, field: Field, article: &dyn Fields) -> Option<u64> {
+        match field {
+            Field::Lines | Field::Bytes => article.number(field),
+            Field::Age => {
+                let date = parse_date(&article.text(Field::Age)?)?;
+                Some(((self.now - date).max(0) / SECONDS_PER_DAY) as u64)
+            }
+            Field::References => article
+                .text(field)
+                .map(|refs| refs.split_whitespace().count() as u64),
+            // The first entry is the name of the server
+            Field::Xref => article
+                .text(field)
+                .map(|xref| xref.split_whitespace().skip(1).count() as u64),
+            Field::Subject | Field::From | Field::MessageId => None,
+        }
+    }
+}
+
+/// Access to the fields of an article
+///
+/// For [`Field::Age`], `text` returns the `Date` header.
+trait Fields {
+    fn text(&self, field: Field) -> Option<Cow<'_, str>>;
+    fn number(&self, field: Field) -> Option<u64>;
+}
+
+struct OverviewFields<'a>(&'a Overview);
+
+impl Fields for OverviewFields<'_> {
+    fn text(&self, field: Field) -> Option<Cow<'_, str>> {
+        let text = match field {
+            Field::Subject => &self.0.subject,
+            Field::From => &self.0.from,
+            Field::MessageId => &self.0.message_id,
+            Field::References => &self.0.references,
+            Field::Age => &self.0.date,
+            Field::Xref => {
+                return self.0.extra.iter().find_map(|extra| {
+                    let (name, value) = extra.split_once(':')?;
+                    if name.eq_ignore_ascii_case("xref") {
+                        Some(Cow::Borrowed(value.trim()))
+                    } else {
+                        None
+                    }
+                })
+            }
+            Field::Lines | Field::Bytes => return None,
+        };
+        Some(Cow::Borrowed(text))
+    }
+
+    fn number(&self, field: Field) -> Option<u64> {
+        match field {
+            Field::Lines => self.0.lines,
+            Field::Bytes => self.0.bytes,
+            _ => None,
+        }
+    }
+}
+
+struct HeaderFields<'a>(&'a Headers);
+
+impl HeaderFields<'_> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        let header = self
+            .0
+            .get(name)
+            .or_else(|| self.0.iter().find(|h| h.name.eq_ignore_ascii_case(name)))?;
+        match header.content.as_slice() {
+            [content] => Some(Cow::Borrowed(content.as_str())),
+            content => Some(Cow::Owned(content.join(" "))),
+        }
+    }
+}
+
+impl Fields for HeaderFields<'_> {
+    fn text(&self, field: Field) -> Option<Cow<'_, str>> {
+        match field {
+            Field::Subject => self.get("Subject"),
+            Field::From => self.get("From"),
+            Field::MessageId => self.get("Message-ID"),
+            Field::References => self.get("References"),
+            Field::Xref => self.get("Xref"),
+            Field::Age => self.get("Date"),
+            Field::Lines | Field::Bytes => None,
+        }
+    }
+
+    fn number(&self, field: Field) -> Option<u64> {
+        match field {
+            Field::Lines => self.get("Lines")?.trim().parse().ok(),
+            Field::Bytes => self.get("Bytes")?.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScoreFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let any = Wildmat::any();
+        let mut groups = &any;
+        for rule in &self.rules {
+            if rule.groups != *groups {
+                groups = &rule.groups;
+                writeln!(f, "[{}]", groups)?;
+            }
+            write!(f, "{}", rule)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules are displayed without their section
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.action {
+            Action::Score(points) => write!(f, "score {}", points)?,
+            Action::Kill => write!(f, "kill")?,
+        }
+        if let Some((year, month, day)) = self.expires {
+            write!(f, " expires {:04}-{:02}-{:02}", year, month, day)?;
+        }
+        writeln!(f)?;
+        self.conditions
+            .iter()
+            .try_for_each(|condition| writeln!(f, "    {}", condition))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "!")?;
+        }
+        write!(f, "{} ", self.field.name())?;
+        match &self.matcher {
+            Matcher::Regex(regex) => write!(f, "~ {}", regex.as_str()),
+            Matcher::Wildmat(wildmat) => write!(f, "= {}", wildmat),
+            Matcher::GreaterThan(n) => write!(f, "> {}", n),
+            Matcher::LessThan(n) => write!(f, "< {}", n),
+        }
+    }
+}
+
+impl FromStr for ScoreFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut file = ScoreFile::new();
+        let mut groups = Wildmat::any();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid =
+                |e: Error| Error::Deserialization(format!("Invalid score line {} -- {}", i + 1, e));
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                groups = Wildmat::new(section).map_err(|e| invalid(Error::de(e.to_string())))?;
+                continue;
+            }
+
+            let keyword = line.split_whitespace().next().unwrap_or_default();
+            if keyword.eq_ignore_ascii_case("score") || keyword.eq_ignore_ascii_case("kill") {
+                let mut rule = parse_rule(line).map_err(invalid)?;
+                rule.groups(groups.clone());
+                file.push(rule);
+            } else {
+                let condition = parse_condition(line).map_err(invalid)?;
+                let rule = file
+                    .rules
+                    .last_mut()
+                    .ok_or_else(|| invalid(Error::de("Condition outside of a rule")))?;
+                rule.conditions.push(condition);
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+/// Parse a `score N [expires YYYY-MM-DD]` or `kill [expires YYYY-MM-DD]` line
+fn parse_rule(line: &str) -> Result<Rule> {
+    let mut words = line.split_whitespace();
+    let action = match words.next().map(str::to_ascii_lowercase).as_deref() {
+        Some("kill") => Action::Kill,
+        _ => {
+            let points = words.next().ok_or_else(|| Error::missing_field("score"))?;
+            let points = points.strip_prefix('+').unwrap_or(points);
+            Action::Score(points.parse().map_err(|_| Error::parse_error("score"))?)
+        }
+    };
+
+    let mut rule = Rule::new(action);
+    match (words.next(), words.next(), words.next()) {
+        (None, _, _) => {}
+        (Some(keyword), Some(date), None) if keyword.eq_ignore_ascii_case("expires") => {
+            let mut parts = date.splitn(3, '-').map(str::parse);
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => {
+                    rule.expires(year as i32, month, day)?;
+                }
+                _ => return Err(Error::parse_error("expires")),
+            }
+        }
+        _ => return Err(Error::de("Expected `expires YYYY-MM-DD`")),
+    }
+
+    Ok(rule)
+}
+
+/// Parse a `[!]field op value` line
+fn parse_condition(line: &str) -> Result<Condition> {
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+
+    let op_index = line
+        .find(['~', '=', '>', '<'])
+        .ok_or_else(|| Error::missing_field("operator"))?;
+    let field: Field = line[..op_index].trim().parse()?;
+    let value = line[op_index + 1..].trim();
+    if value.is_empty() {
+        return Err(Error::missing_field("value"));
+    }
+
+    let number = || value.parse().map_err(|_| Error::parse_error("value"));
+    let matcher = match &line[op_index..op_index + 1] {
+        "~" => Matcher::regex(value)?,
+        "=" => Matcher::Wildmat(Wildmat::new(value).map_err(|e| Error::de(e.to_string()))?),
+        ">" => Matcher::GreaterThan(number()?),
+        _ => Matcher::LessThan(number()?),
+    };
+
+    if matcher.is_numeric() && !field.is_numeric() || !matcher.is_numeric() && !field.is_text() {
+        return Err(Error::de(format!(
+            "Operator cannot be used with `{}`",
+            field.name()
+        )));
+    }
+
+    Ok(Condition {
+        field,
+        matcher,
+        negated,
+    })
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// The number of days between 1970-01-01 and a date in the proleptic Gregorian calendar
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parse an [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.3) date into seconds since
+/// the Unix epoch
+///
+/// The obsolete syntax (two digit years, named time zones) is accepted. Unknown time zones are
+/// treated as UTC.
+pub(crate) fn parse_date(date: &str) -> Option<i64> {
+    // Strip comments such as `(UTC)`
+    let date = date.split('(').next()?;
+    let mut tokens = date
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .peekable();
+
+    // The day of the week is optional
+    tokens.next_if(|t| t.chars().all(|c| c.is_ascii_alphabetic()));
+
+    let day: u32 = tokens.next()?.parse().ok()?;
+    let month = match tokens.next()?.to_ascii_lowercase().get(..3)? {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" => 8,
+        "sep" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" => 12,
+        _ => return None,
+    };
+    let year = match tokens.next()? {
+        y if y.len() <= 2 => match y.parse::<i32>().ok()? {
+            y @ 0..=49 => 2000 + y,
+            y => 1900 + y,
+        },
+        y if y.len() == 3 => 1900 + y.parse::<i32>().ok()?,
+        y => y.parse().ok()?,
+    };
+
+    let mut time = tokens.next()?.split(':').map(str::parse::<i64>);
+    let hour = time.next()?.ok()?;
+    let minute = time.next()?.ok()?;
+    let second = time.next().unwrap_or(Ok(0)).ok()?;
+
+    let offset = match tokens.next() {
+        Some(zone) if zone.starts_with('+') || zone.starts_with('-') => {
+            let digits: i64 = zone.get(1..5)?.parse().ok()?;
+            let minutes = (digits / 100) * 60 + digits % 100;
+            if zone.starts_with('-') {
+                -minutes
+            } else {
+                minutes
+            }
+        }
+        Some(zone) => match zone.to_ascii_uppercase().as_str() {
+            "EDT" => -4 * 60,
+            "EST" | "CDT" => -5 * 60,
+            "CST" | "MDT" => -6 * 60,
+            "MST" | "PDT" => -7 * 60,
+            "PST" => -8 * 60,
+            _ => 0,
+        },
+        None => 0,
+    };
+
+    let days = days_from_civil(year, month, day);
+    Some(days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second - offset * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::time::Duration;
+
+    use super::*;
+
+    const RULES: &str = "\
+# Applies everywhere
+kill
+    from = *@spam.example
+
+[comp.lang.*,!comp.lang.java.*]
+score 50
+    subject ~ \\brust\\b
+score -20 expires 2020-01-31
+    subject ~ ^re:
+    !references ~ <mine@example\\.com>
+score -100
+    xref > 2
+score -5
+    age > 30
+";
+
+    fn overview(subject: &str, from: &str) -> Overview {
+        Overview {
+            number: 1,
+            subject: subject.to_string(),
+            from: from.to_string(),
+            date: "Wed, 1 Jan 2020 12:00:00 +0000".to_string(),
+            message_id: "<1@example.com>".to_string(),
+            references: "".to_string(),
+            bytes: Some(1000),
+            lines: Some(10),
+            extra: vec!["Xref: news.example.com comp.lang.rust:1".to_string()],
+        }
+    }
+
+    fn at(date: &str) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(parse_date(date).unwrap() as u64)
+    }
+
+    #[test]
+    fn test_score() {
+        let rules: ScoreFile = RULES.parse().unwrap();
+        let now = at("10 Jan 2020 00:00 GMT");
+        let scorer = rules.scorer_at("comp.lang.rust", now);
+
+        let score = scorer.score_overview(&overview("Re: Rust is neat", "a@example.com"));
+        assert_eq!(score.points, 30);
+
+        let score = scorer.score_overview(&overview("Rust is neat", "bot@spam.example"));
+        assert!(score.killed);
+
+        let mut crossposted = overview("Buy now", "a@example.com");
+        crossposted.extra = vec!["Xref: news.example.com a:1 b:2 c:3".to_string()];
+        assert_eq!(scorer.score_overview(&crossposted).points, -100);
+
+        // The expiring rule no longer applies and the article is old
+        let scorer = rules.scorer_at("comp.lang.rust", at("1 Mar 2020 00:00 GMT"));
+        let score = scorer.score_overview(&overview("Re: Rust is neat", "a@example.com"));
+        assert_eq!(score.points, 45);
+
+        // Only the global rule applies to other groups
+        let scorer = rules.scorer_at("comp.lang.java.help", now);
+        assert_eq!(scorer.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_score_headers() {
+        let rules: ScoreFile = RULES.parse().unwrap();
+        let scorer = rules.scorer_at("comp.lang.c", at("10 Jan 2020 00:00 GMT"));
+
+        let resp = RawResponse::with_data_blocks(
+            Kind::Head,
+            "0 <1@example.com>",
+            "Subject: rust\r\nFrom: me@example.com\r\nreferences: <mine@example.com>\r\n",
+        );
+        let head = Head::try_from(&resp).unwrap();
+        assert_eq!(scorer.score_headers(&head.headers).points, 50);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rules: ScoreFile = RULES.parse().unwrap();
+        let reparsed: ScoreFile = rules.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), rules.to_string());
+
+        assert_eq!(rules.expire(at("1 Feb 2020 00:00 GMT")), 1);
+        assert_eq!(rules.rules().len(), 4);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for rules in &[
+            "subject ~ rust",
+            "score ten\n  subject ~ rust",
+            "score 10 expires tomorrow",
+            "kill\n  lines ~ 10",
+            "kill\n  subject > 10",
+            "kill\n  date ~ 2020",
+            "kill\n  subject ~ (",
+            "[!foo]",
+        ] {
+            assert!(rules.parse::<ScoreFile>().is_err(), "{}", rules);
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("Thu, 01 Jan 1970 00:00:00 +0000"), Some(0));
+        assert_eq!(parse_date("1 Jan 1970 01:00 +0100"), Some(0));
+        assert_eq!(parse_date("Fri, 2 Jan 70 00:00:00 GMT (UTC)"), Some(86_400));
+        assert_eq!(
+            parse_date("Tue, 14 Jul 2020 10:02:03 -0400"),
+            Some(1_594_735_323)
+        );
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
@@ -0,0 +1,446 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::types::ArticleNumber;
+
+/// A compact, sorted set of article numbers
+///
+/// Article numbers are stored as a list of disjoint inclusive ranges, so a group with millions of
+/// contiguous articles takes up the same space as a group with one. This makes the set well suited
+/// for the responses returned by [`LISTGROUP`](crate::types::command::ListGroup) and for tracking
+/// which articles have been read.
+///
+/// # Example
+///
+/// ```
+/// use brokaw::types::ArticleSet;
+///
+/// let mut set: ArticleSet = vec![1, 2, 3, 7, 8, 10].into_iter().collect();
+/// assert_eq!(set.len(), 6);
+/// assert!(set.contains(8));
+/// assert_eq!(set.ranges().collect::<Vec<_>>(), vec![1..=3, 7..=8, 10..=10]);
+///
+/// // Find the articles that are missing from the group
+/// let gaps = set.gaps(1..=10);
+/// assert_eq!(gaps.iter().collect::<Vec<_>>(), vec![4, 5, 6, 9]);
+/// ```
+///
+/// # `.newsrc` Syntax
+///
+/// Sets can be parsed from and displayed as the comma separated ranges used by `.newsrc` files.
+///
+/// ```
+/// use brokaw::types::ArticleSet;
+///
+/// let set: ArticleSet = "1-500,502,510-600".parse().unwrap();
+/// assert_eq!(set.len(), 592);
+/// assert_eq!(set.to_string(), "1-500,502,510-600");
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ArticleSet {
+    /// Sorted, disjoint, and non-adjacent `(low, high)` pairs
+    ranges: Vec<(ArticleNumber, ArticleNumber)>,
+}
+
+impl ArticleSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a single article number to the set
+    ///
+    /// Returns true if the number was not already present
+    pub fn insert(&mut self, number: ArticleNumber) -> bool {
+        if self.contains(number) {
+            false
+        } else {
+            self.insert_range(number..=number);
+            true
+        }
+    }
+
+    /// Add an inclusive range of article numbers to the set
+    ///
+    /// Empty ranges (e.g. `5..=4`) are ignored.
+    pub fn insert_range(&mut self, range: RangeInclusive<ArticleNumber>) {
+        let (mut low, mut high) = range.into_inner();
+        if low > high {
+            return;
+        }
+
+        // The first range that could overlap or touch the new range
+        let start = self
+            .ranges
+            .partition_point(|&(_, h)| h.saturating_add(1) < low);
+        // One past the last range that could overlap or touch the new range
+        let end = self
+            .ranges
+            .partition_point(|&(l, _)| l <= high.saturating_add(1));
+
+        if start < end {
+            low = low.min(self.ranges[start].0);
+            high = high.max(self.ranges[end - 1].1);
+        }
+
+        self.ranges.splice(start..end, Some((low, high)));
+    }
+
+    /// Remove a single article number from the set
+    ///
+    /// Returns true if the number was present
+    pub fn remove(&mut self, number: ArticleNumber) -> bool {
+        let idx = match self.find(number) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        let (low, high) = self.ranges[idx];
+        match (low == number, high == number) {
+            (true, true) => {
+                self.ranges.remove(idx);
+            }
+            (true, false) => self.ranges[idx].0 = number + 1,
+            (false, true) => self.ranges[idx].1 = number - 1,
+            (false, false) => {
+                self.ranges[idx].1 = number - 1;
+                self.ranges.insert(idx + 1, (number + 1, high));
+            }
+        }
+        true
+    }
+
+    /// Returns true if the set contains the article number
+    pub fn contains(&self, number: ArticleNumber) -> bool {
+        self.find(number).is_some()
+    }
+
+    /// The number of articles in the set
+    ///
+    /// Saturates at `u64::MAX` for a set spanning every possible article number.
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().fold(0, |len: u64, (low, high)| {
+            len.saturating_add((high - low).saturating_add(1))
+        })
+    }
+
+    /// Returns true if the set contains no articles
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The lowest article number in the set
+    pub fn first(&self) -> Option<ArticleNumber> {
+        self.ranges.first().map(|(low, _)| *low)
+    }
+
+    /// The highest article number in the set
+    pub fn last(&self) -> Option<ArticleNumber> {
+        self.ranges.last().map(|(_, high)| *high)
+    }
+
+    /// An iterator over the contiguous ranges in the set, in ascending order
+    pub fn ranges(&self) -> Ranges<'_> {
+        Ranges {
+            inner: self.ranges.iter(),
+        }
+    }
+
+    /// An iterator over every article number in the set, in ascending order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            ranges: self.ranges.iter(),
+            current: None,
+        }
+    }
+
+    /// The article numbers within `bounds` that are *not* in the set
+    pub fn gaps(&self, bounds: RangeInclusive<ArticleNumber>) -> ArticleSet {
+        let (low, high) = bounds.into_inner();
+        let mut gaps = ArticleSet::new();
+        if low > high {
+            return gaps;
+        }
+
+        let mut next = low;
+        for &(l, h) in &self.ranges {
+            if h < next {
+                continue;
+            }
+            if l > high {
+                break;
+            }
+            if l > next {
+                gaps.ranges.push((next, l - 1));
+            }
+            match h.checked_add(1) {
+                Some(n) => next = n,
+                None => return gaps,
+            }
+            if next > high {
+                return gaps;
+            }
+        }
+        gaps.ranges.push((next, high));
+        gaps
+    }
+
+    /// Find the index of the range containing the article number
+    fn find(&self, number: ArticleNumber) -> Option<usize> {
+        let idx = self.ranges.partition_point(|&(_, high)| high < number);
+        match self.ranges.get(idx) {
+            Some(&(low, _)) if low <= number => Some(idx),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ArticleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (low, high)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if low == high {
+                write!(f, "{}", low)?;
+            } else {
+                write!(f, "{}-{}", low, high)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ArticleSet {
+    type Err = Error;
+
+    /// Parse a comma separated list of article numbers and ranges (e.g. `1-500,502,510-600`)
+    ///
+    /// Whitespace around entries is ignored, as are empty entries. Ranges may overlap and appear
+    /// in any order.
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_number = |s: &str| {
+            s.trim()
+                .parse::<ArticleNumber>()
+                .map_err(|_| Error::parse_error("article-range"))
+        };
+
+        let mut set = ArticleSet::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.find('-') {
+                Some(idx) => {
+                    let low = parse_number(&entry[..idx])?;
+                    let high = parse_number(&entry[idx + 1..])?;
+                    set.insert_range(low..=high);
+                }
+                None => {
+                    set.insert(parse_number(entry)?);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl std::iter::FromIterator<ArticleNumber> for ArticleSet {
+    fn from_iter<I: IntoIterator<Item = ArticleNumber>>(iter: I) -> Self {
+        let mut set = ArticleSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<ArticleNumber> for ArticleSet {
+    fn extend<I: IntoIterator<Item = ArticleNumber>>(&mut self, iter: I) {
+        for number in iter {
+            // Fast path for sorted input such as a LISTGROUP response
+            match self.ranges.last_mut() {
+                Some((_, high)) if high.checked_add(1) == Some(number) => *high = number,
+                Some((_, high)) if *high < number => self.ranges.push((number, number)),
+                None => self.ranges.push((number, number)),
+                _ => self.insert_range(number..=number),
+            }
+        }
+    }
+}
+
+impl std::iter::FromIterator<RangeInclusive<ArticleNumber>> for ArticleSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<ArticleNumber>>>(iter: I) -> Self {
+        let mut set = ArticleSet::new();
+        iter.into_iter().for_each(|range| set.insert_range(range));
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a ArticleSet {
+    type Item = ArticleNumber;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the ranges within an [`ArticleSet`]
+///
+/// Created by [`ArticleSet::ranges`]
+#[derive(Clone, Debug)]
+pub struct Ranges<'a> {
+    inner: std::slice::Iter<'a, (ArticleNumber, ArticleNumber)>,
+}
+
+impl<'a> Iterator for Ranges<'a> {
+    type Item = RangeInclusive<ArticleNumber>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|&(low, high)| low..=high)
+    }
+}
+
+/// An iterator over the article numbers within an [`ArticleSet`]
+///
+/// Created by [`ArticleSet::iter`]
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    ranges: std::slice::Iter<'a, (ArticleNumber, ArticleNumber)>,
+    current: Option<RangeInclusive<ArticleNumber>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = ArticleNumber;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(number) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(number);
+            }
+            let &(low, high) = self.ranges.next()?;
+            self.current = Some(low..=high);
+        }
+    }
+}
+
+/// Sets are serialized as a sequence of inclusive `[low, high]` ranges
+#[cfg(feature = "serde")]
+impl serde::Serialize for ArticleSet {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.ranges.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ArticleSet {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let ranges = Vec::<(ArticleNumber, ArticleNumber)>::deserialize(deserializer)?;
+        let mut set = ArticleSet::new();
+        for (low, high) in ranges {
+            if low > high {
+                return Err(serde::de::Error::custom(format!(
+                    "Invalid range {}-{}",
+                    low, high
+                )));
+            }
+            set.insert_range(low..=high);
+        }
+
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(set: &ArticleSet) -> Vec<RangeInclusive<ArticleNumber>> {
+        set.ranges().collect()
+    }
+
+    #[test]
+    fn test_insert_merges_adjacent() {
+        let mut set = ArticleSet::new();
+        assert!(set.insert(5));
+        assert!(set.insert(7));
+        assert!(!set.insert(7));
+        assert_eq!(ranges(&set), vec![5..=5, 7..=7]);
+
+        assert!(set.insert(6));
+        assert_eq!(ranges(&set), vec![5..=7]);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_insert_range_spanning() {
+        let mut set: ArticleSet = vec![1..=2, 5..=6, 10..=12, 20..=20].into_iter().collect();
+        set.insert_range(4..=11);
+        assert_eq!(ranges(&set), vec![1..=2, 4..=12, 20..=20]);
+
+        set.insert_range(3..=3);
+        assert_eq!(ranges(&set), vec![1..=12, 20..=20]);
+
+        let (low, high) = (30, 29);
+        set.insert_range(low..=high);
+        assert_eq!(ranges(&set), vec![1..=12, 20..=20]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set: ArticleSet = vec![1..=10].into_iter().collect();
+        assert!(set.remove(5));
+        assert!(set.remove(1));
+        assert!(set.remove(10));
+        assert!(!set.remove(5));
+        assert_eq!(ranges(&set), vec![2..=4, 6..=9]);
+    }
+
+    #[test]
+    fn test_unsorted_extend() {
+        let set: ArticleSet = vec![9, 3, 4, 1, 2, 8, 3].into_iter().collect();
+        assert_eq!(ranges(&set), vec![1..=4, 8..=9]);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 8, 9]);
+        assert_eq!(set.first(), Some(1));
+        assert_eq!(set.last(), Some(9));
+    }
+
+    #[test]
+    fn test_newsrc_syntax() {
+        let set: ArticleSet = " 510-600, 1-500 ,502,,499-501".parse().unwrap();
+        assert_eq!(ranges(&set), vec![1..=502, 510..=600]);
+        assert_eq!(set.to_string(), "1-502,510-600");
+        assert_eq!(ArticleSet::new().to_string(), "");
+        assert!("".parse::<ArticleSet>().unwrap().is_empty());
+
+        assert!("1-x".parse::<ArticleSet>().is_err());
+        assert!("-5".parse::<ArticleSet>().is_err());
+    }
+
+    #[test]
+    fn test_len_saturates() {
+        let set: ArticleSet = "0-18446744073709551615".parse().unwrap();
+        assert_eq!(set.len(), u64::MAX);
+        assert_eq!(set.gaps(0..=u64::MAX).len(), 0);
+
+        let set: ArticleSet = "1-3,10,18446744073709551614-18446744073709551615"
+            .parse()
+            .unwrap();
+        assert_eq!(set.len(), 6);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let set: ArticleSet = vec![3..=5, 8..=8, 12..=15].into_iter().collect();
+        assert_eq!(
+            ranges(&set.gaps(1..=20)),
+            vec![1..=2, 6..=7, 9..=11, 16..=20]
+        );
+        assert_eq!(ranges(&set.gaps(4..=13)), vec![6..=7, 9..=11]);
+        assert_eq!(ranges(&set.gaps(3..=5)), vec![]);
+        assert_eq!(ranges(&ArticleSet::new().gaps(1..=3)), vec![1..=3]);
+    }
+}
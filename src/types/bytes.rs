@@ -0,0 +1,152 @@
+use std::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserializer, Serialize, Serializer};
+
+/// A byte slice that is serialized with [`Serializer::serialize_bytes`]
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Deserialize a byte buffer from bytes, a string, or a sequence of integers
+///
+/// Self-describing formats without a byte type (e.g. JSON) serialize bytes as a sequence.
+/// For use with `#[serde(deserialize_with = "crate::types::bytes::deserialize")]`
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    deserializer.deserialize_byte_buf(BytesVisitor)
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a byte buffer")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.as_bytes().to_vec())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::fmt::Debug;
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use crate::raw::connection::read_raw_response;
+    use crate::types::command::AnyCommand;
+    use crate::types::prelude::*;
+
+    const TEXT_ARTICLE: &[u8] =
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/text_article"));
+
+    fn round_trip<T: Serialize + DeserializeOwned + Debug + PartialEq>(value: &T) -> String {
+        let json = serde_json::to_string(value).unwrap();
+        let de: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(&de, value);
+        json
+    }
+
+    #[test]
+    fn test_article() {
+        let raw = read_raw_response(&mut &TEXT_ARTICLE[..], true).unwrap();
+        let article = BinaryArticle::try_from(&raw).unwrap();
+        round_trip(&article);
+        round_trip(article.headers());
+        round_trip(&article.to_text().unwrap());
+
+        let raw_body = RawResponse::with_data_blocks(Kind::Body, "1 <a@b>", "hi\n.dot\n");
+        round_trip(&Body::try_from(&raw_body).unwrap());
+
+        let json = serde_json::to_string(&raw).unwrap();
+        let de: RawResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.to_wire(), raw.to_wire());
+    }
+
+    #[test]
+    fn test_invalid_bodies() {
+        let article = |body: &str| {
+            format!(
+                r#"{{"number":1,"message_id":"<a@b>","headers":[],"body":{:?}}}"#,
+                body
+            )
+        };
+        let body = |body: &str| format!(r#"{{"number":1,"message_id":"<a@b>","body":{:?}}}"#, body);
+        let raw = |body: &str| {
+            format!(
+                r#"{{"code":222,"first_line":"222 1 <a@b>\r\n","data_blocks":{:?}}}"#,
+                body
+            )
+        };
+
+        let de = serde_json::from_str::<BinaryArticle>(&article("hi\r\n.\r\n")).unwrap();
+        assert_eq!(de.lines().count(), 1);
+        assert!(serde_json::from_str::<Body>(&body("hi\r\n.\r\n")).is_ok());
+        assert!(serde_json::from_str::<RawResponse>(&raw("hi\r\n.\r\n")).is_ok());
+
+        // Missing terminators, partial lines, and lines after the terminator are rejected
+        for invalid in ["", "hi\r\n", "hi\r\n.\r\nmore", "hi\r\n.\r\n.\r\n"].iter() {
+            assert!(serde_json::from_str::<BinaryArticle>(&article(invalid)).is_err());
+            assert!(serde_json::from_str::<Body>(&body(invalid)).is_err());
+            assert!(serde_json::from_str::<RawResponse>(&raw(invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_headers_are_ordered() {
+        let json = r#"[["To","a"],["Path","b"],["From","d"],["Path","c"]]"#;
+        let headers: Headers = serde_json::from_str(json).unwrap();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers.get("Path").unwrap().content, vec!["b", "c"]);
+        assert_eq!(serde_json::to_string(&headers).unwrap(), json);
+    }
+
+    #[test]
+    fn test_misc() {
+        let set: ArticleSet = vec![1..=3, 7..=7].into_iter().collect();
+        assert_eq!(round_trip(&set), "[[1,3],[7,7]]");
+        assert!(serde_json::from_str::<ArticleSet>("[[3,1]]").is_err());
+
+        assert_eq!(round_trip(&Kind::GroupSelected), "211");
+        assert!(serde_json::from_str::<Kind>("999").is_err());
+        round_trip(&ResponseCode::from(999));
+
+        let wildmat = Wildmat::new("comp.*,!comp.os.*").unwrap();
+        assert_eq!(
+            serde_json::to_string(&wildmat).unwrap(),
+            r#""comp.*,!comp.os.*""#
+        );
+        let de: Wildmat = serde_json::from_str(r#""comp.*,!comp.os.*""#).unwrap();
+        assert!(de.matches("comp.lang.rust"));
+
+        let cmd = "GROUP alt.test".parse::<AnyCommand>().unwrap();
+        let json = serde_json::to_string(&cmd).unwrap();
+        let de: AnyCommand = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.to_string(), cmd.to_string());
+    }
+}
@@ -0,0 +1,777 @@
+use std::fmt;
+use std::str::{from_utf8, FromStr};
+
+use crate::types::command::*;
+use crate::types::prelude::{ArticleNumber, Kind, ResponseCode, Wildmat};
+
+/// The maximum length of a command line including the CRLF terminator
+///
+/// [RFC 3977 3.1](https://tools.ietf.org/html/rfc3977#section-3.1)
+const MAX_LINE_LEN: usize = 512;
+
+/// A type that can be parsed from a command line sent by a client
+///
+/// This is the inverse of [`Encode`], and is useful for implementing servers and proxies.
+///
+/// The line MAY include the CRLF terminator.
+pub trait Decode: Sized {
+    /// Parse a command line
+    fn decode(line: &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// An error returned when a command line cannot be decoded
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum DecodeError {
+    /// The command keyword is not recognized
+    #[error("Unknown command `{0}`")]
+    UnknownCommand(String),
+    /// The command is known but the arguments are invalid
+    #[error("Syntax error in `{command}` -- {msg}")]
+    Syntax {
+        /// The command keyword
+        command: String,
+        /// A description of the problem
+        msg: String,
+    },
+    /// The line decoded successfully but to a different command than requested
+    #[error("Expected `{expected}` but found `{found}`")]
+    UnexpectedCommand {
+        /// The command that was requested
+        expected: &'static str,
+        /// The command that was found
+        found: String,
+    },
+}
+
+impl DecodeError {
+    /// The response code a server should reply with
+    ///
+    /// * [`UnknownCommand`](Kind::UnknownCommand) (500) for unknown commands
+    /// * [`SyntaxError`](Kind::SyntaxError) (501) for everything else
+    pub fn response_code(&self) -> ResponseCode {
+        match self {
+            DecodeError::UnknownCommand(_) => Kind::UnknownCommand.into(),
+            DecodeError::Syntax { .. } | DecodeError::UnexpectedCommand { .. } => {
+                Kind::SyntaxError.into()
+            }
+        }
+    }
+
+    fn syntax(command: &str, msg: impl AsRef<str>) -> Self {
+        DecodeError::Syntax {
+            command: command.to_string(),
+            msg: msg.as_ref().to_string(),
+        }
+    }
+}
+
+/// Any of the commands provided by Brokaw
+///
+/// `AnyCommand` is returned when decoding a command line without knowing the command ahead of
+/// time, e.g. in a server's read loop.
+///
+/// # Example
+///
+/// ```
+/// use brokaw::types::command::{AnyCommand, Article, Decode};
+///
+/// let cmd = AnyCommand::decode(b"article <45223423@example.com>\r\n").unwrap();
+/// match &cmd {
+///     AnyCommand::Article(Article::MessageId(id)) => assert_eq!(id, "<45223423@example.com>"),
+///     _ => unreachable!(),
+/// }
+/// assert_eq!(cmd.to_string(), "ARTICLE <45223423@example.com>");
+///
+/// let err = AnyCommand::decode(b"GROUP").unwrap_err();
+/// assert_eq!(u16::from(err.response_code()), 501);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum AnyCommand {
+    Article(Article),
+    AuthInfo(AuthInfo),
+    Body(Body),
+    Capabilities(Capabilities),
+    Check(Check),
+    Date(Date),
+    Group(Group),
+    Hdr(Hdr),
+    Head(Head),
+    Help(Help),
+    IHave(IHave),
+    Last(Last),
+    List(List),
+    ListGroup(ListGroup),
+    ModeReader(ModeReader),
+    ModeStream(ModeStream),
+    Next(Next),
+    Over(Over),
+    Post(Post),
+    Quit(Quit),
+    Stat(Stat),
+    TakeThis(TakeThis),
+    XFeatureCompress(XFeatureCompress),
+    XFeatureCompressGzip(XFeatureCompressGzip),
+    XHdr(XHdr),
+    XOver(XOver),
+    XZHdr(XZHdr),
+    XZVer(XZVer),
+}
+
+impl AnyCommand {
+    /// The keyword of the command (e.g. `ARTICLE`, `MODE READER`)
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            AnyCommand::Article(_) => "ARTICLE",
+            AnyCommand::AuthInfo(_) => "AUTHINFO",
+            AnyCommand::Body(_) => "BODY",
+            AnyCommand::Capabilities(_) => "CAPABILITIES",
+            AnyCommand::Check(_) => "CHECK",
+            AnyCommand::Date(_) => "DATE",
+            AnyCommand::Group(_) => "GROUP",
+            AnyCommand::Hdr(_) => "HDR",
+            AnyCommand::Head(_) => "HEAD",
+            AnyCommand::Help(_) => "HELP",
+            AnyCommand::IHave(_) => "IHAVE",
+            AnyCommand::Last(_) => "LAST",
+            AnyCommand::List(_) => "LIST",
+            AnyCommand::ListGroup(_) => "LISTGROUP",
+            AnyCommand::ModeReader(_) => "MODE READER",
+            AnyCommand::ModeStream(_) => "MODE STREAM",
+            AnyCommand::Next(_) => "NEXT",
+            AnyCommand::Over(_) => "OVER",
+            AnyCommand::Post(_) => "POST",
+            AnyCommand::Quit(_) => "QUIT",
+            AnyCommand::Stat(_) => "STAT",
+            AnyCommand::TakeThis(_) => "TAKETHIS",
+            AnyCommand::XFeatureCompress(_) | AnyCommand::XFeatureCompressGzip(_) => "XFEATURE",
+            AnyCommand::XHdr(_) => "XHDR",
+            AnyCommand::XOver(_) => "XOVER",
+            AnyCommand::XZHdr(_) => "XZHDR",
+            AnyCommand::XZVer(_) => "XZVER",
+        }
+    }
+}
+
+impl fmt::Display for AnyCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyCommand::Article(c) => c.fmt(f),
+            AnyCommand::AuthInfo(c) => c.fmt(f),
+            AnyCommand::Body(c) => c.fmt(f),
+            AnyCommand::Capabilities(c) => c.fmt(f),
+            AnyCommand::Check(c) => c.fmt(f),
+            AnyCommand::Date(c) => c.fmt(f),
+            AnyCommand::Group(c) => c.fmt(f),
+            AnyCommand::Hdr(c) => c.fmt(f),
+            AnyCommand::Head(c) => c.fmt(f),
+            AnyCommand::Help(c) => c.fmt(f),
+            AnyCommand::IHave(c) => c.fmt(f),
+            AnyCommand::Last(c) => c.fmt(f),
+            AnyCommand::List(c) => c.fmt(f),
+            AnyCommand::ListGroup(c) => c.fmt(f),
+            AnyCommand::ModeReader(c) => c.fmt(f),
+            AnyCommand::ModeStream(c) => c.fmt(f),
+            AnyCommand::Next(c) => c.fmt(f),
+            AnyCommand::Over(c) => c.fmt(f),
+            AnyCommand::Post(c) => c.fmt(f),
+            AnyCommand::Quit(c) => c.fmt(f),
+            AnyCommand::Stat(c) => c.fmt(f),
+            AnyCommand::TakeThis(c) => c.fmt(f),
+            AnyCommand::XFeatureCompress(c) => c.fmt(f),
+            AnyCommand::XFeatureCompressGzip(c) => c.fmt(f),
+            AnyCommand::XHdr(c) => c.fmt(f),
+            AnyCommand::XOver(c) => c.fmt(f),
+            AnyCommand::XZHdr(c) => c.fmt(f),
+            AnyCommand::XZVer(c) => c.fmt(f),
+        }
+    }
+}
+
+impl NntpCommand for AnyCommand {}
+
+impl FromStr for AnyCommand {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode(s.as_bytes())
+    }
+}
+
+impl Decode for AnyCommand {
+    fn decode(line: &[u8]) -> Result<Self, DecodeError> {
+        if line.len() > MAX_LINE_LEN {
+            return Err(DecodeError::syntax("", "Command line is too long"));
+        }
+
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = from_utf8(line).map_err(|_| DecodeError::syntax("", "Invalid UTF-8"))?;
+
+        let mut args = Args::new(line);
+        let keyword = args
+            .next()
+            .ok_or_else(|| DecodeError::UnknownCommand(String::new()))?
+            .to_ascii_uppercase();
+
+        let cmd = match keyword.as_str() {
+            "ARTICLE" => AnyCommand::Article(article_like(
+                &mut args,
+                "ARTICLE",
+                Article::MessageId,
+                Article::Number,
+                Article::Current,
+            )?),
+            "AUTHINFO" => AnyCommand::AuthInfo(parse_authinfo(&mut args)?),
+            "BODY" => AnyCommand::Body(article_like(
+                &mut args,
+                "BODY",
+                Body::MessageId,
+                Body::Number,
+                Body::Current,
+            )?),
+            "CAPABILITIES" => {
+                // The optional keyword argument is permitted but ignored
+                args.next();
+                AnyCommand::Capabilities(Capabilities)
+            }
+            "CHECK" => AnyCommand::Check(Check(args.message_id("CHECK")?)),
+            "DATE" => AnyCommand::Date(Date),
+            "GROUP" => AnyCommand::Group(Group(args.newsgroup("GROUP")?)),
+            "HDR" => AnyCommand::Hdr(parse_hdr(&mut args)?),
+            "HEAD" => AnyCommand::Head(article_like(
+                &mut args,
+                "HEAD",
+                Head::MessageId,
+                Head::Number,
+                Head::Current,
+            )?),
+            "HELP" => AnyCommand::Help(Help),
+            "IHAVE" => AnyCommand::IHave(IHave(args.message_id("IHAVE")?)),
+            "LAST" => AnyCommand::Last(Last),
+            "LIST" => AnyCommand::List(parse_list(&mut args)?),
+            "LISTGROUP" => AnyCommand::ListGroup(parse_list_group(&mut args)?),
+            "MODE" => match args.next().map(str::to_ascii_uppercase).as_deref() {
+                Some("READER") => AnyCommand::ModeReader(ModeReader),
+                Some("STREAM") => AnyCommand::ModeStream(ModeStream),
+                _ => return Err(DecodeError::syntax("MODE", "Unknown mode")),
+            },
+            "NEXT" => AnyCommand::Next(Next),
+            "OVER" => AnyCommand::Over(parse_over(&mut args)?),
+            "POST" => AnyCommand::Post(Post),
+            "QUIT" => AnyCommand::Quit(Quit),
+            "STAT" => AnyCommand::Stat(article_like(
+                &mut args,
+                "STAT",
+                Stat::MessageId,
+                Stat::Number,
+                Stat::Current,
+            )?),
+            "TAKETHIS" => AnyCommand::TakeThis(TakeThis(args.message_id("TAKETHIS")?)),
+            "XFEATURE" => {
+                let feature = args.rest().to_ascii_uppercase();
+                match feature.as_str() {
+                    "COMPRESS GZIP" => AnyCommand::XFeatureCompressGzip(XFeatureCompressGzip),
+                    "COMPRESS GZIP TERMINATOR" => AnyCommand::XFeatureCompress(XFeatureCompress),
+                    _ => return Err(DecodeError::syntax("XFEATURE", "Unsupported feature")),
+                }
+            }
+            "XHDR" => AnyCommand::XHdr(parse_xhdr(&mut args, "XHDR")?),
+            "XOVER" => AnyCommand::XOver(match args.next() {
+                None => XOver::Current,
+                Some(range) => match parse_range("XOVER", range)? {
+                    (low, Some(high)) => XOver::Range { low, high },
+                    (low, None) => XOver::RangeFrom { low },
+                },
+            }),
+            "XZHDR" => AnyCommand::XZHdr(parse_xhdr(&mut args, "XZHDR")?.into()),
+            "XZVER" => AnyCommand::XZVer(match args.next() {
+                None => XZVer::Current,
+                Some(range) => match parse_range("XZVER", range)? {
+                    (low, Some(high)) => XZVer::Range { low, high },
+                    (low, None) => XZVer::RangeFrom { low },
+                },
+            }),
+            _ => return Err(DecodeError::UnknownCommand(keyword)),
+        };
+
+        args.finish(cmd.keyword())?;
+        Ok(cmd)
+    }
+}
+
+/// Implement [`Decode`] and [`FromStr`] for a command by decoding an [`AnyCommand`]
+macro_rules! impl_decode {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl Decode for $name {
+                fn decode(line: &[u8]) -> Result<Self, DecodeError> {
+                    match AnyCommand::decode(line)? {
+                        AnyCommand::$name(cmd) => Ok(cmd),
+                        other => Err(DecodeError::UnexpectedCommand {
+                            expected: stringify!($name),
+                            found: other.keyword().to_string(),
+                        }),
+                    }
+                }
+            }
+
+            impl FromStr for $name {
+                type Err = DecodeError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    Self::decode(s.as_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_decode!(
+    Article,
+    AuthInfo,
+    Body,
+    Capabilities,
+    Check,
+    Date,
+    Group,
+    Hdr,
+    Head,
+    Help,
+    IHave,
+    Last,
+    List,
+    ListGroup,
+    ModeReader,
+    ModeStream,
+    Next,
+    Over,
+    Post,
+    Quit,
+    Stat,
+    TakeThis,
+    XFeatureCompress,
+    XFeatureCompressGzip,
+    XHdr,
+    XOver,
+    XZHdr,
+    XZVer,
+);
+
+/// A whitespace separated argument iterator
+struct Args<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Args<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { rest: line }
+    }
+
+    /// The remainder of the line with surrounding whitespace removed
+    fn rest(&mut self) -> &'a str {
+        let rest = self.rest.trim_matches(is_ws);
+        self.rest = "";
+        rest
+    }
+
+    fn message_id(&mut self, command: &str) -> Result<String, DecodeError> {
+        let arg = self
+            .next()
+            .ok_or_else(|| DecodeError::syntax(command, "Missing message-id"))?;
+        if is_message_id(arg) {
+            Ok(arg.to_string())
+        } else {
+            Err(DecodeError::syntax(command, "Invalid message-id"))
+        }
+    }
+
+    fn newsgroup(&mut self, command: &str) -> Result<String, DecodeError> {
+        let arg = self
+            .next()
+            .ok_or_else(|| DecodeError::syntax(command, "Missing newsgroup"))?;
+        if is_newsgroup_name(arg) {
+            Ok(arg.to_string())
+        } else {
+            Err(DecodeError::syntax(command, "Invalid newsgroup name"))
+        }
+    }
+
+    fn finish(&mut self, command: &str) -> Result<(), DecodeError> {
+        match self.next() {
+            Some(_) => Err(DecodeError::syntax(command, "Too many arguments")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let trimmed = self.rest.trim_start_matches(is_ws);
+        if trimmed.is_empty() {
+            self.rest = "";
+            return None;
+        }
+        let end = trimmed.find(is_ws).unwrap_or(trimmed.len());
+        let (arg, rest) = trimmed.split_at(end);
+        self.rest = rest;
+        Some(arg)
+    }
+}
+
+/// Arguments are separated by one or more spaces or tabs
+fn is_ws(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// [message-id](https://tools.ietf.org/html/rfc3977#section-9.8)
+///
+/// ```abnf
+/// message-id = "<" 1*248A-NOTGT ">"
+/// ```
+fn is_message_id(s: &str) -> bool {
+    let b = s.as_bytes();
+    (3..=250).contains(&b.len())
+        && b[0] == b'<'
+        && b[b.len() - 1] == b'>'
+        && b[1..b.len() - 1]
+            .iter()
+            .all(|c| (0x21..=0x7e).contains(c) && *c != b'>')
+}
+
+/// [newsgroup-name](https://tools.ietf.org/html/rfc3977#section-9.8)
+///
+/// A newsgroup name is one or more printable characters excluding the wildmat specials
+fn is_newsgroup_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| {
+            !c.is_control()
+                && !c.is_whitespace()
+                && !matches!(c, '*' | '?' | '[' | ']' | '\\' | '!' | ',')
+        })
+}
+
+/// An article number is 1-16 digits
+fn parse_article_number(command: &str, s: &str) -> Result<ArticleNumber, DecodeError> {
+    if s.is_empty() || s.len() > 16 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DecodeError::syntax(command, "Invalid article number"));
+    }
+    s.parse()
+        .map_err(|_| DecodeError::syntax(command, "Invalid article number"))
+}
+
+/// Parse a [range](https://tools.ietf.org/html/rfc3977#section-9.8)
+///
+/// * `n` is equivalent to `n-n`
+/// * `n-` (all articles from `n` onwards) has no high number
+fn parse_range(
+    command: &str,
+    s: &str,
+) -> Result<(ArticleNumber, Option<ArticleNumber>), DecodeError> {
+    match s.find('-') {
+        None => {
+            let n = parse_article_number(command, s)?;
+            Ok((n, Some(n)))
+        }
+        Some(idx) => {
+            let low = parse_article_number(command, &s[..idx])?;
+            let high = &s[idx + 1..];
+            let high = if high.is_empty() {
+                None
+            } else {
+                Some(parse_article_number(command, high)?)
+            };
+            Ok((low, high))
+        }
+    }
+}
+
+/// Parse the argument to `ARTICLE`, `BODY`, `HEAD`, and `STAT`
+fn article_like<T>(
+    args: &mut Args<'_>,
+    command: &str,
+    message_id: impl FnOnce(String) -> T,
+    number: impl FnOnce(ArticleNumber) -> T,
+    current: T,
+) -> Result<T, DecodeError> {
+    match args.next() {
+        None => Ok(current),
+        Some(arg) if arg.starts_with('<') => {
+            if is_message_id(arg) {
+                Ok(message_id(arg.to_string()))
+            } else {
+                Err(DecodeError::syntax(command, "Invalid message-id"))
+            }
+        }
+        Some(arg) => parse_article_number(command, arg).map(number),
+    }
+}
+
+fn parse_authinfo(args: &mut Args<'_>) -> Result<AuthInfo, DecodeError> {
+    let subcommand = args
+        .next()
+        .ok_or_else(|| DecodeError::syntax("AUTHINFO", "Missing subcommand"))?
+        .to_ascii_uppercase();
+
+    // Usernames and passwords are taken verbatim as they may contain spaces
+    let value = args.rest();
+    if value.is_empty() {
+        return Err(DecodeError::syntax("AUTHINFO", "Missing argument"));
+    }
+
+    match subcommand.as_str() {
+        "USER" => Ok(AuthInfo::User(value.to_string())),
+        "PASS" => Ok(AuthInfo::Pass(value.to_string())),
+        _ => Err(DecodeError::syntax("AUTHINFO", "Unsupported subcommand")),
+    }
+}
+
+fn parse_hdr(args: &mut Args<'_>) -> Result<Hdr, DecodeError> {
+    let field = args
+        .next()
+        .ok_or_else(|| DecodeError::syntax("HDR", "Missing field"))?
+        .to_string();
+
+    match args.next() {
+        None => Ok(Hdr::Current { field }),
+        Some(id) if id.starts_with('<') => {
+            if is_message_id(id) {
+                Ok(Hdr::MessageId {
+                    field,
+                    id: id.to_string(),
+                })
+            } else {
+                Err(DecodeError::syntax("HDR", "Invalid message-id"))
+            }
+        }
+        Some(range) => match parse_range("HDR", range)? {
+            (low, Some(high)) => Ok(Hdr::Range { field, low, high }),
+            (low, None) => Ok(Hdr::RangeFrom { field, low }),
+        },
+    }
+}
+
+/// Parse the arguments to `XHDR` (or `XZHDR`, which takes the same arguments)
+fn parse_xhdr(args: &mut Args<'_>, command: &str) -> Result<XHdr, DecodeError> {
+    let header = args
+        .next()
+        .ok_or_else(|| DecodeError::syntax(command, "Missing header"))?
+        .to_string();
+
+    match args.next() {
+        None => Ok(XHdr::Current { header }),
+        Some(id) if id.starts_with('<') => {
+            if is_message_id(id) {
+                Ok(XHdr::MessageId {
+                    header,
+                    id: id.to_string(),
+                })
+            } else {
+                Err(DecodeError::syntax(command, "Invalid message-id"))
+            }
+        }
+        Some(range) => match parse_range(command, range)? {
+            (low, Some(high)) => Ok(XHdr::Range { header, low, high }),
+            (low, None) => Ok(XHdr::RangeFrom { header, low }),
+        },
+    }
+}
+
+fn parse_list(args: &mut Args<'_>) -> Result<List, DecodeError> {
+    let keyword = args.next().map(str::to_ascii_uppercase);
+    let wildmat = |args: &mut Args<'_>| {
+        args.next()
+            .map(Wildmat::new)
+            .transpose()
+            .map_err(|e| DecodeError::syntax("LIST", e.to_string()))
+    };
+
+    match keyword.as_deref() {
+        None => Ok(List::Active { wildmat: None }),
+        Some("ACTIVE") => Ok(List::Active {
+            wildmat: wildmat(args)?,
+        }),
+        Some("ACTIVE.TIMES") => Ok(List::ActiveTimes {
+            wildmat: wildmat(args)?,
+        }),
+        Some("NEWSGROUPS") => Ok(List::Newsgroups {
+            wildmat: wildmat(args)?,
+        }),
+        Some("DISTRIB.PATS") => Ok(List::DistribPats),
+        Some("OVERVIEW.FMT") => Ok(List::OverviewFmt),
+        Some(_) => Err(DecodeError::syntax("LIST", "Unsupported keyword")),
+    }
+}
+
+fn parse_list_group(args: &mut Args<'_>) -> Result<ListGroup, DecodeError> {
+    if args.rest.trim_matches(is_ws).is_empty() {
+        return Ok(ListGroup::Current);
+    }
+    let group = args.newsgroup("LISTGROUP")?;
+    match args.next() {
+        None => Ok(ListGroup::Group(group)),
+        Some(range) => match parse_range("LISTGROUP", range)? {
+            (low, Some(high)) => Ok(ListGroup::Range { group, low, high }),
+            (low, None) => Ok(ListGroup::RangeFrom { group, low }),
+        },
+    }
+}
+
+fn parse_over(args: &mut Args<'_>) -> Result<Over, DecodeError> {
+    match args.next() {
+        None => Ok(Over::Current),
+        Some(id) if id.starts_with('<') => {
+            if is_message_id(id) {
+                Ok(Over::MessageId(id.to_string()))
+            } else {
+                Err(DecodeError::syntax("OVER", "Invalid message-id"))
+            }
+        }
+        Some(range) => match parse_range("OVER", range)? {
+            (low, Some(high)) => Ok(Over::Range { low, high }),
+            (low, None) => Ok(Over::RangeFrom { low }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(line: &str) -> String {
+        AnyCommand::decode(line.as_bytes())
+            .unwrap_or_else(|e| panic!("Failed to decode `{}` -- {}", line, e))
+            .to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        [
+            "ARTICLE",
+            "ARTICLE 3000234",
+            "ARTICLE <45223423@example.com>",
+            "AUTHINFO USER newsreader",
+            "BODY 10",
+            "CAPABILITIES",
+            "CHECK <i.am.an.article@example.com>",
+            "DATE",
+            "GROUP misc.test",
+            "HDR Subject 3000234-3000238",
+            "HDR Subject 3000234-",
+            "HDR :lines <i.am.an.article@example.com>",
+            "HDR Subject",
+            "HEAD",
+            "HELP",
+            "IHAVE <i.am.an.article@example.com>",
+            "LAST",
+            "LIST ACTIVE",
+            "LIST ACTIVE comp.*,!comp.os.*",
+            "LIST ACTIVE.TIMES",
+            "LIST NEWSGROUPS misc.*",
+            "LIST DISTRIB.PATS",
+            "LIST OVERVIEW.FMT",
+            "LISTGROUP",
+            "LISTGROUP misc.test",
+            "LISTGROUP misc.test 3000238-3000248",
+            "LISTGROUP misc.test 3000238-",
+            "MODE READER",
+            "MODE STREAM",
+            "NEXT",
+            "OVER",
+            "OVER 3000234-3000240",
+            "OVER 3000234-",
+            "OVER <45223423@example.com>",
+            "POST",
+            "QUIT",
+            "STAT 3000234",
+            "TAKETHIS <i.am.an.article@example.com>",
+            "XFEATURE COMPRESS GZIP",
+            "XFEATURE COMPRESS GZIP TERMINATOR",
+            "XHDR Subject 1-5",
+            "XHDR Subject 1-",
+            "XHDR Subject <45223423@example.com>",
+            "XHDR Subject",
+            "XOVER",
+            "XOVER 1-5",
+            "XOVER 1-",
+            "XZHDR Subject 1-5",
+            "XZHDR Subject 1-",
+            "XZVER",
+            "XZVER 1-5",
+            "XZVER 1-",
+        ]
+        .iter()
+        .for_each(|line| assert_eq!(&round_trip(line), line));
+    }
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!(round_trip("article   42\r\n"), "ARTICLE 42");
+        assert_eq!(round_trip("mode\treader"), "MODE READER");
+        assert_eq!(round_trip("LIST"), "LIST ACTIVE");
+        assert_eq!(round_trip("OVER 7"), "OVER 7-7");
+        assert_eq!(
+            round_trip("AUTHINFO PASS correct horse"),
+            "AUTHINFO PASS correct horse"
+        );
+        assert_eq!(
+            round_trip("xfeature compress gzip"),
+            "XFEATURE COMPRESS GZIP"
+        );
+
+        let over = Over::decode(b"OVER 100-").unwrap();
+        assert!(matches!(over, Over::RangeFrom { low: 100 }));
+        assert!(XFeatureCompress::decode(b"XFEATURE COMPRESS GZIP").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let unknown = AnyCommand::decode(b"FROB 12").unwrap_err();
+        assert_eq!(unknown, DecodeError::UnknownCommand("FROB".to_string()));
+        assert_eq!(
+            unknown.response_code(),
+            ResponseCode::Known(Kind::UnknownCommand)
+        );
+
+        [
+            "GROUP",
+            "GROUP comp.*",
+            "ARTICLE abc",
+            "ARTICLE <no-closing-bracket",
+            "ARTICLE 12345678901234567",
+            "STAT 1 2",
+            "OVER 5-x",
+            "MODE WRITER",
+            "LIST FOOBAR",
+            "LIST ACTIVE !comp.*",
+            "IHAVE 123",
+            "AUTHINFO USER",
+            "AUTHINFO SASL PLAIN",
+        ]
+        .iter()
+        .for_each(|line| {
+            let err = AnyCommand::decode(line.as_bytes()).unwrap_err();
+            assert_eq!(
+                err.response_code(),
+                ResponseCode::Known(Kind::SyntaxError),
+                "{}",
+                line
+            );
+        });
+
+        let too_long = format!("GROUP {}", "a".repeat(MAX_LINE_LEN));
+        assert!(AnyCommand::decode(too_long.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_typed_decode() {
+        let group: Group = "GROUP misc.test".parse().unwrap();
+        assert_eq!(group.0, "misc.test");
+
+        let err = Group::decode(b"QUIT").unwrap_err();
+        assert!(matches!(err, DecodeError::UnexpectedCommand { .. }));
+    }
+}
@@ -7,40 +7,34 @@
 /// (directly or via [`fmt::Display`](std::fmt::Display) as [`Encode`] is automatically implemented for
 /// types that implement [`ToString`].
 ///
-/// # Example: Implementing LISTGROUP
+/// # Example: Implementing XPAT
 /// ```
 /// use std::fmt;
 /// use brokaw::types::command::NntpCommand;
 ///
 /// #[derive(Clone, Debug)]
-/// pub struct ListGroup {
-///     group: Option<String>,
-///     range: Option<(u32, u32)>,
+/// pub struct XPat {
+///     header: String,
+///     range: (u32, u32),
+///     pattern: String,
 /// }
 ///
-/// impl fmt::Display for ListGroup {
+/// impl fmt::Display for XPat {
 ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-///         write!(f, "LISTGROUP")?;
-///
-///         if let Some(group) = &self.group {
-///             write!(f, " {}", &group)?;
-///         }
-///
-///         if let Some((low, high)) = &self.range {
-///             write!(f, " {}-{}", low, high)?;
-///         }
-///         Ok(())
+///         let (low, high) = self.range;
+///         write!(f, "XPAT {} {}-{} {}", self.header, low, high, self.pattern)
 ///     }
 /// }
 ///
-/// impl NntpCommand for ListGroup {}
+/// impl NntpCommand for XPat {}
 ///
-/// let cmd = ListGroup {
-///     group: Some("misc.test".to_string()),
-/// range: Some((10, 20))
+/// let cmd = XPat {
+///     header: "Subject".to_string(),
+///     range: (10, 20),
+///     pattern: "*brokaw*".to_string(),
 /// };
 ///
-/// assert_eq!(cmd.to_string(), "LISTGROUP misc.test 10-20")
+/// assert_eq!(cmd.to_string(), "XPAT Subject 10-20 *brokaw*")
 /// ```
 pub trait NntpCommand: Encode {}
 
@@ -58,6 +52,12 @@ impl<T: ToString> Encode for T {
     }
 }
 
+/// Decoding command lines into typed commands
+mod decode;
+
+#[doc(inline)]
+pub use decode::{AnyCommand, Decode, DecodeError};
+
 /// Commands specified in [RFC 3977](https://tools.ietf.org/html/rfc3977#appendix-B)
 mod rfc3977;
 
@@ -76,7 +76,19 @@ mod rfc4643;
 #[doc(inline)]
 pub use rfc4643::*;
 
+/// Streaming commands specified in [RFC 4644](https://tools.ietf.org/html/rfc4644)
+mod rfc4644;
+
+#[doc(inline)]
+pub use rfc4644::*;
+
 mod xfeature;
 
 #[doc(inline)]
 pub use xfeature::*;
+
+/// Compressed overview commands supported by several providers
+mod xzver;
+
+#[doc(inline)]
+pub use xzver::*;
@@ -4,6 +4,7 @@ use crate::types::prelude::{ArticleNumber, NntpCommand};
 
 /// Retrieve a specific header from one or more articles
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XHdr {
     /// A single message
     MessageId {
@@ -21,6 +22,18 @@ pub enum XHdr {
         /// The high number of the article range
         high: ArticleNumber,
     },
+    /// Every message from an article number onwards
+    RangeFrom {
+        /// The name of the header to retrieve
+        header: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
+    /// The current message
+    Current {
+        /// The name of the header to retrieve
+        header: String,
+    },
 }
 
 impl fmt::Display for XHdr {
@@ -28,12 +41,17 @@ impl fmt::Display for XHdr {
         match self {
             XHdr::MessageId { header, id } => write!(f, "XHDR {} {}", header, id),
             XHdr::Range { header, low, high } => write!(f, "XHDR {} {}-{}", header, low, high),
+            XHdr::RangeFrom { header, low } => write!(f, "XHDR {} {}-", header, low),
+            XHdr::Current { header } => write!(f, "XHDR {}", header),
         }
     }
 }
 
+impl NntpCommand for XHdr {}
+
 /// Get the headers for one or more articles
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XOver {
     /// A range of messages
     Range {
@@ -42,6 +60,11 @@ pub enum XOver {
         /// The high number of the article range
         high: ArticleNumber,
     },
+    /// Every message from an article number onwards
+    RangeFrom {
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
     /// The current message
     Current,
 }
@@ -50,6 +73,7 @@ impl fmt::Display for XOver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             XOver::Range { low, high } => write!(f, "XOVER {}-{}", low, high),
+            XOver::RangeFrom { low } => write!(f, "XOVER {}-", low),
             XOver::Current => write!(f, "XOVER"),
         }
     }
@@ -1,9 +1,10 @@
 use std::fmt;
 
-use crate::types::prelude::{ArticleNumber, NntpCommand};
+use crate::types::prelude::{ArticleNumber, NntpCommand, Wildmat};
 
 /// Retrieve an article's header and body
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Article {
     /// Globally unique message ID
     MessageId(String),
@@ -27,6 +28,7 @@ impl NntpCommand for Article {}
 
 /// Retrieve the body for an Article
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Body {
     /// Globally unique message ID
     MessageId(String),
@@ -50,6 +52,7 @@ impl NntpCommand for Body {}
 
 /// Get the capabilities provided by the server
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Capabilities;
 
 impl fmt::Display for Capabilities {
@@ -62,7 +65,8 @@ impl NntpCommand for Capabilities {}
 
 /// Get the server time
 #[derive(Clone, Copy, Debug)]
-struct Date;
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Date;
 
 impl fmt::Display for Date {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -74,6 +78,7 @@ impl NntpCommand for Date {}
 
 /// Select a group
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group(pub String);
 
 impl fmt::Display for Group {
@@ -86,6 +91,7 @@ impl NntpCommand for Group {}
 
 /// Retrieve a specific header from one or more articles
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Hdr {
     /// A single article by message ID
     MessageId {
@@ -103,6 +109,13 @@ pub enum Hdr {
         /// The high number of the article range
         high: ArticleNumber,
     },
+    /// Every article from an article number onwards
+    RangeFrom {
+        /// The name of the header
+        field: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
     /// The current article
     Current {
         /// The name of the header
@@ -115,6 +128,7 @@ impl fmt::Display for Hdr {
         match self {
             Hdr::MessageId { field, id } => write!(f, "HDR {} {}", field, id),
             Hdr::Range { field, low, high } => write!(f, "HDR {} {}-{}", field, low, high),
+            Hdr::RangeFrom { field, low } => write!(f, "HDR {} {}-", field, low),
             Hdr::Current { field } => write!(f, "HDR {}", field),
         }
     }
@@ -124,6 +138,7 @@ impl NntpCommand for Hdr {}
 
 /// Retrieve the headers for an article
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Head {
     /// Globally unique message ID
     MessageId(String),
@@ -146,7 +161,9 @@ impl fmt::Display for Head {
 impl NntpCommand for Head {}
 
 /// Retrieve help text about the servers capabilities
-struct Help;
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Help;
 
 impl fmt::Display for Help {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -157,7 +174,15 @@ impl fmt::Display for Help {
 impl NntpCommand for Help {}
 
 /// Inform the server that you have an article for upload
-struct IHave(String);
+///
+/// If the server responds with [`IHaveSendArticle`](crate::types::response_code::Kind::IHaveSendArticle)
+/// the article should be sent with
+/// [`NntpConnection::send_data_blocks`](crate::raw::connection::NntpConnection::send_data_blocks).
+///
+/// [RFC 3977 6.3.2](https://tools.ietf.org/html/rfc3977#section-6.3.2)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IHave(pub String);
 
 impl fmt::Display for IHave {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -168,7 +193,9 @@ impl fmt::Display for IHave {
 impl NntpCommand for IHave {}
 
 /// Attempt to set the current article to the previous article number
-struct Last;
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Last;
 
 impl fmt::Display for Last {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -186,20 +213,21 @@ impl NntpCommand for Last {}
 ///
 /// If you want to send LIST without any keywords simply send [`List::Active`] as they are equivalent.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum List {
     /// Return a list of active newsgroups
     ///
     /// [RFC 3977 7.6.3](https://tools.ietf.org/html/rfc3977#section-7.6.3)
-    Active { wildmat: Option<String> },
+    Active { wildmat: Option<Wildmat> },
     /// Return information about when news groups were created
     ///
     /// [RFC 3977 7.6.4](https://tools.ietf.org/html/rfc3977#section-7.6.4)
-    ActiveTimes { wildmat: Option<String> },
+    ActiveTimes { wildmat: Option<Wildmat> },
     /// List descriptions of newsgroups available on the server
     ///
     /// [RFC 3977 7.6.6](https://tools.ietf.org/html/rfc3977#section-7.6.6)
-    Newsgroups { wildmat: Option<String> },
+    Newsgroups { wildmat: Option<Wildmat> },
     /// Retrieve information about the Distribution header for news articles
     ///
     /// [RFC 3977 7.6.5](https://tools.ietf.org/html/rfc3977#section-7.6.5)
@@ -212,7 +240,7 @@ pub enum List {
 
 impl fmt::Display for List {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fn print_wildmat(f: &mut fmt::Formatter<'_>, wildmat: Option<&String>) -> fmt::Result {
+        fn print_wildmat(f: &mut fmt::Formatter<'_>, wildmat: Option<&Wildmat>) -> fmt::Result {
             if let Some(w) = wildmat.as_ref() {
                 write!(f, " {}", w)
             } else {
@@ -228,11 +256,11 @@ impl fmt::Display for List {
             }
             List::OverviewFmt => write!(f, " OVERVIEW.FMT"),
             List::ActiveTimes { wildmat } => {
-                write!(f, " ACTIVE TIMES")?;
+                write!(f, " ACTIVE.TIMES")?;
                 print_wildmat(f, wildmat.as_ref())
             }
             List::Newsgroups { wildmat } => {
-                write!(f, " ACTIVE TIMES")?;
+                write!(f, " NEWSGROUPS")?;
                 print_wildmat(f, wildmat.as_ref())
             }
             List::DistribPats => write!(f, " DISTRIB.PATS"),
@@ -242,8 +270,55 @@ impl fmt::Display for List {
 
 impl NntpCommand for List {}
 
+/// List the article numbers in a newsgroup
+///
+/// Like [`Group`], a successful `LISTGROUP` selects the group and sets the current article
+/// to the first article in the group.
+///
+/// [RFC 3977 6.1.2](https://tools.ietf.org/html/rfc3977#section-6.1.2)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ListGroup {
+    /// The currently selected group
+    Current,
+    /// Every article in a group
+    Group(String),
+    /// A range of articles within a group
+    Range {
+        /// The name of the group
+        group: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+        /// The high number of the article range
+        high: ArticleNumber,
+    },
+    /// Every article in a group starting from an article number (e.g. `LISTGROUP misc.test 10-`)
+    RangeFrom {
+        /// The name of the group
+        group: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
+}
+
+impl fmt::Display for ListGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListGroup::Current => write!(f, "LISTGROUP"),
+            ListGroup::Group(group) => write!(f, "LISTGROUP {}", group),
+            ListGroup::Range { group, low, high } => {
+                write!(f, "LISTGROUP {} {}-{}", group, low, high)
+            }
+            ListGroup::RangeFrom { group, low } => write!(f, "LISTGROUP {} {}-", group, low),
+        }
+    }
+}
+
+impl NntpCommand for ListGroup {}
+
 /// Enable reader mode on a mode switching server
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModeReader;
 
 impl fmt::Display for ModeReader {
@@ -260,6 +335,7 @@ impl NntpCommand for ModeReader {}
 
 /// Attempt to set the current article to the next article number
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Next;
 
 impl fmt::Display for Next {
@@ -272,6 +348,7 @@ impl NntpCommand for Next {}
 
 /// Retrieve all of the fields (e.g. headers/metadata) for one or more articles
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Over {
     /// A single article by message ID
     MessageId(String),
@@ -282,6 +359,11 @@ pub enum Over {
         /// The high number of the article
         high: ArticleNumber,
     },
+    /// Every article from an article number onwards
+    RangeFrom {
+        /// The low number of the article
+        low: ArticleNumber,
+    },
     /// The current article
     Current,
 }
@@ -291,6 +373,7 @@ impl fmt::Display for Over {
         match self {
             Over::MessageId(id) => write!(f, "OVER {}", id),
             Over::Range { low, high } => write!(f, "OVER {}-{}", low, high),
+            Over::RangeFrom { low } => write!(f, "OVER {}-", low),
             Over::Current => write!(f, "OVER"),
         }
     }
@@ -298,39 +381,28 @@ impl fmt::Display for Over {
 
 impl NntpCommand for Over {}
 
-// TODO(commands) complete POST implementation
-/*
-/// Post an article to the news server
+/// Inform the server that you would like to post an article
 ///
-/// POSTING is a two part exchange. The [`Initial`](Post::Initial) variant is used
-/// to determine if the server will accept the article while the [`Article`](Post::Article) is
-/// used to send the data.
+/// If the server responds with [`PostSendArticle`](crate::types::response_code::Kind::PostSendArticle)
+/// the article should be sent with
+/// [`NntpConnection::send_data_blocks`](crate::raw::connection::NntpConnection::send_data_blocks).
 ///
-///
-/// For more information see [RFC 3977 6.3.1](https://tools.ietf.org/html/rfc3977#section-6.3.1)
-#[derive(Clone, Debug)]
-enum Post<'a> {
-    Initial,
-    /// The article body NOT including the terminating sequence
-    Article(&'a [u8])
-}
-
-impl NntpCommand for Post<'_> {}
+/// [RFC 3977 6.3.1](https://tools.ietf.org/html/rfc3977#section-6.3.1)
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Post;
 
-impl fmt::Display for Post<'_> {
+impl fmt::Display for Post {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Post::Initial => write!(f, "POST"),
-            Post::Article(body) => {
-                unimplemented!()
-            },
-        }
+        write!(f, "POST")
     }
 }
-*/
+
+impl NntpCommand for Post {}
 
 /// Close the connection
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quit;
 
 impl fmt::Display for Quit {
@@ -343,6 +415,7 @@ impl NntpCommand for Quit {}
 
 /// Check if an article exists in the newsgroup
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stat {
     /// Globally unique message ID
     MessageId(String),
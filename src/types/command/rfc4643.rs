@@ -8,6 +8,7 @@ use super::NntpCommand;
 ///
 /// * SASL is not currently implemented
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AuthInfo {
     /// Username
     User(String),
@@ -0,0 +1,51 @@
+use std::fmt;
+
+use super::NntpCommand;
+
+/// Ask the server to switch to streaming mode
+///
+/// [RFC 4644 2.3](https://tools.ietf.org/html/rfc4644#section-2.3)
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeStream;
+
+impl fmt::Display for ModeStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MODE STREAM")
+    }
+}
+
+impl NntpCommand for ModeStream {}
+
+/// Ask the server whether it wants an article
+///
+/// [RFC 4644 2.4](https://tools.ietf.org/html/rfc4644#section-2.4)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Check(pub String);
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CHECK {}", self.0)
+    }
+}
+
+impl NntpCommand for Check {}
+
+/// Send an article to the server without waiting for it to be requested
+///
+/// The article MUST be sent immediately after the command
+/// (e.g. with [`NntpConnection::send_data_blocks`](crate::raw::connection::NntpConnection::send_data_blocks)).
+///
+/// [RFC 4644 2.5](https://tools.ietf.org/html/rfc4644#section-2.5)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TakeThis(pub String);
+
+impl fmt::Display for TakeThis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TAKETHIS {}", self.0)
+    }
+}
+
+impl NntpCommand for TakeThis {}
@@ -4,6 +4,7 @@ use crate::types::NntpCommand;
 
 /// Enable Giganews style header compression
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XFeatureCompress;
 
 impl fmt::Display for XFeatureCompress {
@@ -13,3 +14,21 @@ impl fmt::Display for XFeatureCompress {
 }
 
 impl NntpCommand for XFeatureCompress {}
+
+/// Enable Giganews style header compression without a terminating line
+///
+/// This is the `XFEATURE COMPRESS GZIP` form of [`XFeatureCompress`], without the `TERMINATOR`
+/// argument. Servers may frame compressed responses differently for each form, so the two are
+/// kept distinct when decoding commands. [`Compression::XFeature`](crate::raw::Compression)
+/// expects responses in the `TERMINATOR` form.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XFeatureCompressGzip;
+
+impl fmt::Display for XFeatureCompressGzip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "XFEATURE COMPRESS GZIP")
+    }
+}
+
+impl NntpCommand for XFeatureCompressGzip {}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+use crate::types::command::XHdr;
+use crate::types::prelude::{ArticleNumber, NntpCommand};
+
+/// Get compressed overviews for one or more articles
+///
+/// The response is a yEnc encoded, deflate compressed [`XOver`](super::XOver) response.
+/// [`NntpConnection`](crate::raw::connection::NntpConnection) decompresses it automatically,
+/// so it can be parsed into [`Overviews`](crate::types::response::Overviews).
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XZVer {
+    /// A range of messages
+    Range {
+        /// The low number of the article range
+        low: ArticleNumber,
+        /// The high number of the article range
+        high: ArticleNumber,
+    },
+    /// Every message from an article number onwards
+    RangeFrom {
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
+    /// The current message
+    Current,
+}
+
+impl fmt::Display for XZVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XZVer::Range { low, high } => write!(f, "XZVER {}-{}", low, high),
+            XZVer::RangeFrom { low } => write!(f, "XZVER {}-", low),
+            XZVer::Current => write!(f, "XZVER"),
+        }
+    }
+}
+
+impl NntpCommand for XZVer {}
+
+/// Retrieve a specific header from one or more articles, compressed
+///
+/// The response is a yEnc encoded, deflate compressed [`XHdr`] response.
+/// [`NntpConnection`](crate::raw::connection::NntpConnection) decompresses it automatically.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XZHdr {
+    /// A single message
+    MessageId {
+        /// The name of the header to retrieve
+        header: String,
+        /// The message ID of the article
+        id: String,
+    },
+    /// A range of messages
+    Range {
+        /// The name of the header to retrieve
+        header: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+        /// The high number of the article range
+        high: ArticleNumber,
+    },
+    /// Every message from an article number onwards
+    RangeFrom {
+        /// The name of the header to retrieve
+        header: String,
+        /// The low number of the article range
+        low: ArticleNumber,
+    },
+    /// The current message
+    Current {
+        /// The name of the header to retrieve
+        header: String,
+    },
+}
+
+impl fmt::Display for XZHdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XZHdr::MessageId { header, id } => write!(f, "XZHDR {} {}", header, id),
+            XZHdr::Range { header, low, high } => write!(f, "XZHDR {} {}-{}", header, low, high),
+            XZHdr::RangeFrom { header, low } => write!(f, "XZHDR {} {}-", header, low),
+            XZHdr::Current { header } => write!(f, "XZHDR {}", header),
+        }
+    }
+}
+
+impl NntpCommand for XZHdr {}
+
+/// Request the same headers as an `XHDR` command, compressed
+impl From<XHdr> for XZHdr {
+    fn from(xhdr: XHdr) -> Self {
+        match xhdr {
+            XHdr::MessageId { header, id } => XZHdr::MessageId { header, id },
+            XHdr::Range { header, low, high } => XZHdr::Range { header, low, high },
+            XHdr::RangeFrom { header, low } => XZHdr::RangeFrom { header, low },
+            XHdr::Current { header } => XZHdr::Current { header },
+        }
+    }
+}
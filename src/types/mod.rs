@@ -5,10 +5,6 @@
 ///
 /// Brokaw provides implementations for most of the commands
 /// in [RFC 3977](https://tools.ietf.org/html/rfc3977).
-///
-/// One notable exception is the [`LISTGROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.2)
-/// command. This command is left unimplemented as it does not adhere to the response standards
-/// defined in the RFC.
 pub mod command;
 
 /// Typed NNTP responses for individual commands
@@ -17,6 +13,16 @@ pub mod response;
 /// NNTP response codes
 pub mod response_code;
 
+/// Compact sets of article numbers
+pub mod article_set;
+
+/// Wildmat pattern matching
+pub mod wildmat;
+
+/// Serde helpers for byte buffers
+#[cfg(feature = "serde")]
+pub(crate) mod bytes;
+
 /// The number of an article relative to a specific Newsgroup
 ///
 /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6) article numbers should fit within
@@ -25,17 +31,25 @@ pub type ArticleNumber = u64;
 
 /// Re-exports of traits and response types
 pub mod prelude {
-    pub use crate::raw::response::{DataBlocks, RawResponse};
+    pub use crate::raw::response::{DataBlocks, DataBlocksRef, RawResponse, RawResponseRef};
 
+    pub use super::article_set::ArticleSet;
     pub use super::command::NntpCommand;
     pub use super::response::*;
     pub use super::response_code::*;
+    pub use super::wildmat::Wildmat;
     pub use super::ArticleNumber;
 }
 
+#[doc(inline)]
+pub use article_set::ArticleSet;
+
 #[doc(inline)]
 pub use command::NntpCommand;
 
+#[doc(inline)]
+pub use wildmat::{Wildmat, WildmatError};
+
 #[doc(inline)]
 pub use response::*;
 
@@ -1,5 +1,7 @@
 use std::convert::TryFrom;
 use std::fmt;
+use std::io;
+use std::io::Write;
 use std::result::Result as StdResult;
 use std::str::from_utf8;
 
@@ -10,6 +12,7 @@ use crate::types::prelude::*;
 use crate::types::response::article::iter::{Lines, Unterminated};
 use crate::types::response::article::parse::take_headers;
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
+use crate::types::response::write::{write_headers, WriteResponse};
 
 /// A binary Netnews article
 ///
@@ -135,22 +138,30 @@ impl fmt::Display for BinaryArticle {
 
 impl TryFrom<&RawResponse> for BinaryArticle {
     type Error = Error;
+
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for BinaryArticle {
+    type Error = Error;
     /// Convert a raw response into an article
     ///
     /// For the specification see RFC 3977 sections:
     ///
     /// * [response-220-content](https://tools.ietf.org/html/rfc3977#section-9.4.2)
     /// * [article](https://tools.ietf.org/html/rfc3977#section-9.7)
-    fn try_from(resp: &RawResponse) -> Result<Self> {
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::Article)?;
-        let (number, message_id) = process_article_first_line(&resp)?;
+        let (number, message_id) = process_article_first_line(resp)?;
 
         let data_blocks = resp
             .data_blocks
             .as_ref()
             .ok_or_else(Error::missing_data_blocks)?;
 
-        let (body, headers) = take_headers(&data_blocks.payload()).map_err(|e| match e {
+        let (body, headers) = take_headers(data_blocks.payload()).map_err(|e| match e {
             nom::Err::Incomplete(n) => Error::Deserialization(format!("{:?}", n)),
             nom::Err::Error((_, kind)) | nom::Err::Failure((_, kind)) => {
                 Error::invalid_data_blocks(format!("{:?}", kind))
@@ -177,3 +188,73 @@ impl TryFrom<&RawResponse> for BinaryArticle {
         })
     }
 }
+
+impl WriteResponse for BinaryArticle {
+    /// Write the response
+    ///
+    /// Headers are written in the order they were received
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} {} {}\r\n",
+            ResponseCode::from(Kind::Article),
+            self.number,
+            self.message_id
+        )?;
+        write_headers(w, &self.headers)?;
+        w.write_all(b"\r\n")?;
+        // the body is kept in wire format
+        w.write_all(&self.body)
+    }
+}
+
+/// The body is serialized as bytes in wire format, see [`BinaryArticle::body`]
+#[cfg(feature = "serde")]
+impl serde::Serialize for BinaryArticle {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("BinaryArticle", 4)?;
+        s.serialize_field("number", &self.number)?;
+        s.serialize_field("message_id", &self.message_id)?;
+        s.serialize_field("headers", &self.headers)?;
+        s.serialize_field("body", &crate::types::bytes::Bytes(&self.body))?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BinaryArticle {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "BinaryArticle")]
+        struct Repr {
+            number: ArticleNumber,
+            message_id: String,
+            headers: Headers,
+            #[serde(deserialize_with = "crate::types::bytes::deserialize")]
+            body: Vec<u8>,
+        }
+
+        let Repr {
+            number,
+            message_id,
+            headers,
+            body,
+        } = Repr::deserialize(deserializer)?;
+        let DataBlocks {
+            payload: body,
+            mut line_boundaries,
+        } = DataBlocks::from_wire(body).map_err(serde::de::Error::custom)?;
+        // The terminating line is not part of the body's lines, from_wire ensures it is present
+        line_boundaries.pop();
+
+        Ok(Self {
+            number,
+            message_id,
+            headers,
+            body,
+            line_boundaries,
+        })
+    }
+}
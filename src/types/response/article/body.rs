@@ -1,9 +1,12 @@
 use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
 use crate::types::response::article::iter::*;
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
+use crate::types::response::write::WriteResponse;
 
 /// An article body returned by the [`BODY`](https://tools.ietf.org/html/rfc3977#section-6.2.3)
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -52,18 +55,83 @@ impl TryFrom<&RawResponse> for Body {
     type Error = Error;
 
     fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Body {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::Body)?;
 
-        let (number, message_id) = process_article_first_line(&resp)?;
+        let (number, message_id) = process_article_first_line(resp)?;
+
+        let data_blocks = resp.data_blocks.ok_or_else(Error::missing_data_blocks)?;
+
+        Ok(Self {
+            number,
+            message_id,
+            payload: data_blocks.payload.to_vec(),
+            line_boundaries: data_blocks.line_boundaries.to_vec(),
+        })
+    }
+}
+
+impl WriteResponse for Body {
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} {} {}\r\n",
+            ResponseCode::from(Kind::Body),
+            self.number,
+            self.message_id
+        )?;
+        // the payload is kept in wire format
+        w.write_all(&self.payload)
+    }
+}
+
+/// The body is serialized as bytes in wire format, see [`Body::body`]
+#[cfg(feature = "serde")]
+impl serde::Serialize for Body {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("Body", 3)?;
+        s.serialize_field("number", &self.number)?;
+        s.serialize_field("message_id", &self.message_id)?;
+        s.serialize_field("body", &crate::types::bytes::Bytes(&self.payload))?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Body {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Body")]
+        struct Repr {
+            number: ArticleNumber,
+            message_id: String,
+            #[serde(deserialize_with = "crate::types::bytes::deserialize")]
+            body: Vec<u8>,
+        }
 
+        let Repr {
+            number,
+            message_id,
+            body,
+        } = Repr::deserialize(deserializer)?;
         let DataBlocks {
             payload,
             line_boundaries,
-        } = resp
-            .data_blocks
-            .as_ref()
-            .ok_or_else(Error::missing_data_blocks)?
-            .clone();
+        } = DataBlocks::from_wire(body).map_err(serde::de::Error::custom)?;
 
         Ok(Self {
             number,
@@ -1,24 +1,34 @@
 use std::collections::{hash_map, HashMap};
 use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
 
 use crate::error::{Error, Result};
 use crate::raw::response::RawResponse;
 use crate::types::prelude::*;
 use crate::types::response::article::parse::take_headers;
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
+use crate::types::response::write::{write_headers, WriteResponse};
 
 /// Netnews article headers
 ///
 /// Note that per [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.6) headers
 /// may be repeated (a common example is X-Received for emails mirrored onto Newsgroups)
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// The order the headers appeared in is retained and can be retrieved with
+/// [`in_order`](Self::in_order).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Headers {
     pub(crate) inner: HashMap<String, Header>,
     pub(crate) len: u32,
+    /// The name of each header in the order they appeared, along with the index of the value
+    /// within [`Header::content`]
+    pub(crate) order: Vec<(String, usize)>,
 }
 
 /// An individual header within a [`Headers`] collection
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     /// The name of the header
     pub name: String,
@@ -50,6 +60,27 @@ impl Headers {
             inner: self.inner.values(),
         }
     }
+
+    /// An iterator over `(name, content)` pairs in the order they appeared in the article
+    ///
+    /// Unlike [`iter`](Self::iter), repeated headers are yielded once per occurrence.
+    pub fn in_order(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.order.iter().map(move |(name, idx)| {
+            let header = &self.inner[name];
+            (header.name.as_str(), header.content[*idx].as_str())
+        })
+    }
+
+    /// Append a header
+    pub(crate) fn push(&mut self, name: String, content: String) {
+        let header = self.inner.entry(name.clone()).or_insert(Header {
+            name: name.clone(),
+            content: vec![],
+        });
+        self.order.push((name, header.content.len()));
+        header.content.push(content);
+        self.len += 1;
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -67,6 +98,7 @@ impl<'a> Iterator for Iter<'a> {
 
 /// Article headers returned by [`HEAD`](https://tools.ietf.org/html/rfc3977#section-6.2.2)
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Head {
     /// The number of the article unique to a particular newsgroup
     pub number: ArticleNumber,
@@ -80,16 +112,24 @@ impl TryFrom<&RawResponse> for Head {
     type Error = Error;
 
     fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Head {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::Head)?;
 
-        let (number, message_id) = process_article_first_line(&resp)?;
+        let (number, message_id) = process_article_first_line(resp)?;
 
         let data_blocks = resp
             .data_blocks
             .as_ref()
             .ok_or_else(Error::missing_data_blocks)?;
 
-        let (_, headers) = take_headers(&data_blocks.payload())
+        let (_, headers) = take_headers(data_blocks.payload())
             .map_err(|e| Error::invalid_data_blocks(format!("{}", e)))?;
 
         Ok(Self {
@@ -99,3 +139,45 @@ impl TryFrom<&RawResponse> for Head {
         })
     }
 }
+
+impl WriteResponse for Head {
+    /// Write the response
+    ///
+    /// Headers are written in the order they were received
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} {} {}\r\n",
+            ResponseCode::from(Kind::Head),
+            self.number,
+            self.message_id
+        )?;
+        write_headers(w, &self.headers)?;
+        w.write_all(b".\r\n")
+    }
+}
+
+/// Headers are serialized as a sequence of `[name, content]` pairs in the order they appeared
+#[cfg(feature = "serde")]
+impl serde::Serialize for Headers {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.in_order())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Headers {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let pairs = Vec::<(String, String)>::deserialize(deserializer)?;
+        let mut headers = Headers::default();
+        for (name, content) in pairs {
+            headers.push(name, content);
+        }
+        Ok(headers)
+    }
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use log::*;
 use nom::branch::alt;
 use nom::bytes::complete::{take, take_while1};
@@ -10,13 +8,13 @@ use nom::multi::{fold_many1, many0};
 use nom::sequence::{terminated, tuple};
 use nom::IResult;
 
-use crate::types::prelude::{Header, Headers};
+use crate::types::prelude::Headers;
 
 /// Returns true if the character is any ASCII non-control character other than a colon
 ///
 /// [A-NOTCOLON](https://tools.ietf.org/html/rfc3977#section-9.8)
 fn is_a_notcolon(chr: u8) -> bool {
-    (chr >= 0x21 && chr <= 0x39) || (chr >= 0x3b && chr <= 0x7e)
+    (0x21..=0x39).contains(&chr) || (0x3b..=0x7e).contains(&chr)
 }
 
 /// Returns true if the slice is UTF-8 and contains no ascii characters
@@ -35,7 +33,7 @@ fn is_utf8_non_ascii(b: &[u8]) -> bool {
 ///
 /// [`A-CHAR`](https://tools.ietf.org/html/rfc3977#section-9.8)
 fn is_a_char(chr: u8) -> bool {
-    chr >= 0x21 && chr <= 0x7e
+    (0x21..=0x7e).contains(&chr)
 }
 
 /// Returns true if the byte slice is a *single* non ASCII non-control char
@@ -121,8 +119,8 @@ fn take_ascii_byte(b: &[u8]) -> IResult<&[u8], &[u8]> {
 /// # Non-Compliant Whitespace
 ///
 /// * All of the header RFCs I've come indicate there is no whitespace allowed between tokens and
-/// CLRF characters. Thankfully mail servers don't follow RFCs and violate this anyways so we
-/// do allow this *non-compliant* behavior to ease user suffering
+///   CLRF characters. Thankfully mail servers don't follow RFCs and violate this anyways so we
+///   do allow this *non-compliant* behavior to ease user suffering
 fn take_header_content(b: &[u8]) -> IResult<&[u8], &[u8]> {
     let (rest, (_ws, _token, _more_tokens)) = tuple((
         space0,
@@ -163,29 +161,20 @@ pub(crate) fn take_headers(b: &[u8]) -> IResult<&[u8], Headers> {
 
     let fold_headers = fold_many1(
         take_header,
-        (HashMap::new(), 0),
-        |(mut map, mut len), (name, content)| {
+        Headers::default(),
+        |mut headers, (name, content)| {
             let name = String::from_utf8_lossy(name).to_string();
             let content = String::from_utf8_lossy(content).to_string();
             trace!("Found header name `{}` -- `{}`", name, content);
 
-            let header = map.entry(name.clone()).or_insert(Header {
-                name,
-                content: vec![],
-            });
-            header.content.push(content);
-
-            len += 1;
-
-            (map, len)
+            headers.push(name, content);
+            headers
         },
     );
 
-    let (rest, (inner, len)) = terminated(fold_headers, crlf)(b)?;
-
-    let headers = Headers { inner, len };
-
-    Ok((rest, headers))
+    // HEAD responses end with the headers while articles separate headers from the body with an
+    // empty line
+    terminated(fold_headers, opt(crlf))(b)
 }
 
 #[cfg(test)]
@@ -207,13 +196,13 @@ mod tests {
                 .iter()
                 .for_each(|b| {
                     println!("Testing `{}` -- {:?}", from_utf8(b).unwrap(), b);
-                    assert_eq!(is_utf8_non_ascii(b), true)
+                    assert!(is_utf8_non_ascii(b))
                 });
         }
 
         #[test]
         fn fail_ascii() {
-            assert_eq!(is_utf8_non_ascii(b"1"), false)
+            assert!(!is_utf8_non_ascii(b"1"))
         }
     }
 
@@ -283,8 +272,8 @@ mod tests {
             Thu, 05 Jun 2020 10:13:41 -0700 (PDT)\r\n";
 
             let (rest, (header_name, parsed_content)) = take_header(FOLDED_HEADER).unwrap();
-            dbg!(from_utf8(&header_name).unwrap());
-            dbg!(from_utf8(&rest).unwrap());
+            dbg!(from_utf8(header_name).unwrap());
+            dbg!(from_utf8(rest).unwrap());
             assert_eq!(rest.len(), 0);
             assert_eq!(header_name, &b"X-Received"[..]);
             assert_eq!(parsed_content, &content[..content.len() - 2])
@@ -300,7 +289,11 @@ mod tests {
             assert_eq!(name, header.split(':').next().unwrap().as_bytes());
             assert_eq!(
                 from_utf8(content).unwrap(),
-                header.splitn(2, ':').nth(1).map(|s| s.trim()).unwrap()
+                header
+                    .split_once(':')
+                    .map(|x| x.1)
+                    .map(|s| s.trim())
+                    .unwrap()
             )
         }
 
@@ -327,7 +320,7 @@ mod tests {
     #[test]
     fn test_take_headers() {
         // strip the initial response line
-        let article = TEXT_ARTICLE.splitn(2, '\n').nth(1).unwrap();
+        let article = TEXT_ARTICLE.split_once('\n').unwrap().1;
         let (rest, headers) = take_headers(article.as_bytes()).unwrap();
 
         println!("{:#?}", headers);
@@ -335,5 +328,25 @@ mod tests {
         assert!(rest.starts_with(b"In bug 1630935 [1], I intend to deprecate support for drawing"));
         assert!(headers.inner.contains_key("X-Received"));
         assert_eq!(headers.get("X-Received").unwrap().content.len(), 2);
+        assert_eq!(headers.in_order().count(), headers.len());
+    }
+
+    #[test]
+    fn test_take_headers_order() {
+        let (rest, headers) =
+            take_headers(b"Path: a!b\r\nFrom: c\r\nPath: d\r\nAbc: e\r\n\r\nbody\r\n").unwrap();
+        assert_eq!(rest, b"body\r\n");
+        assert_eq!(
+            headers.in_order().collect::<Vec<_>>(),
+            vec![("Path", "a!b"), ("From", "c"), ("Path", "d"), ("Abc", "e")]
+        );
+    }
+
+    #[test]
+    fn test_take_headers_without_body() {
+        // The data blocks of a HEAD response end right after the last header
+        let (rest, headers) = take_headers(b"Subject: hi\r\nFrom: c\r\n.\r\n").unwrap();
+        assert_eq!(rest, b".\r\n");
+        assert_eq!(headers.len(), 2);
     }
 }
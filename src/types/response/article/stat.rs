@@ -1,11 +1,15 @@
 use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
+use crate::types::response::write::WriteResponse;
 
 /// Article metadata returned by [`STAT`](https://tools.ietf.org/html/rfc3977#section-6.2.4)
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stat {
     /// The number of the article unique to a particular newsgroup
     pub number: ArticleNumber,
@@ -17,6 +21,14 @@ impl TryFrom<&RawResponse> for Stat {
     type Error = Error;
 
     fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Stat {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::ArticleExists)?;
 
         let (number, message_id) = process_article_first_line(resp)?;
@@ -24,3 +36,15 @@ impl TryFrom<&RawResponse> for Stat {
         Ok(Self { number, message_id })
     }
 }
+
+impl WriteResponse for Stat {
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} {} {}\r\n",
+            ResponseCode::from(Kind::ArticleExists),
+            self.number,
+            self.message_id
+        )
+    }
+}
@@ -9,8 +9,9 @@ use crate::types::prelude::*;
 ///
 /// * [`from_binary`](`Self::from_binary`) is fallible as it performs UTF-8 checks
 /// * [`from_binary_lossy`](Self::from_binary_lossy) is infallible but will replace
-/// non UTF-8 characters with placeholders. Please see [`String::from_utf8_lossy`] for more info.
+///   non UTF-8 characters with placeholders. Please see [`String::from_utf8_lossy`] for more info.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextArticle {
     pub(crate) number: ArticleNumber,
     pub(crate) message_id: String,
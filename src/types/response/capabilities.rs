@@ -1,19 +1,26 @@
 use std::collections::{hash_map, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt;
+use std::io;
+use std::io::Write;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
 use crate::types::response::util::err_if_not_kind;
+use crate::types::response::write::{write_multiline, WriteResponse};
 
 /// Server capabilities
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Capabilities(HashMap<String, Capability>);
 
 /// A capability advertised by the server
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Capability {
+    /// The capability label (e.g. `READER`)
     pub name: String,
+    /// Any arguments following the label
     pub args: Option<HashSet<String>>,
 }
 
@@ -47,6 +54,16 @@ impl fmt::Display for Capability {
     }
 }
 
+impl std::iter::FromIterator<Capability> for Capabilities {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|cap| (cap.name.clone(), cap))
+                .collect(),
+        )
+    }
+}
+
 /// Created by [`Capabilities::iter`]
 #[derive(Clone, Debug)]
 pub struct Iter<'a> {
@@ -64,17 +81,25 @@ impl<'a> Iterator for Iter<'a> {
 impl TryFrom<&RawResponse> for Capabilities {
     type Error = Error;
 
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Capabilities {
+    type Error = Error;
+
     /// Parse capabilities from a response
     ///
     /// The specific format is taken from [RFC 3977](https://tools.ietf.org/html/rfc3977#section-9.5)
-    fn try_from(resp: &RawResponse) -> Result<Self> {
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::Capabilities)?;
 
         let db_iter = resp
             .data_blocks
             .as_ref()
             .ok_or_else(|| Error::de("Missing data blocks."))
-            .map(DataBlocks::unterminated)?;
+            .map(DataBlocksRef::unterminated)?;
 
         let capabilities: HashMap<String, Capability> = db_iter
             .map(String::from_utf8_lossy)
@@ -103,3 +128,39 @@ impl TryFrom<&RawResponse> for Capabilities {
         Ok(Self(capabilities))
     }
 }
+
+impl WriteResponse for Capabilities {
+    /// Write the response
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-5.2.2) the `VERSION`
+    /// capability is written first, the remaining capabilities and their arguments are sorted.
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} Capability list:\r\n",
+            ResponseCode::from(Kind::Capabilities)
+        )?;
+
+        let mut caps = self.iter().collect::<Vec<_>>();
+        caps.sort_by(|a, b| (a.name != "VERSION", &a.name).cmp(&(b.name != "VERSION", &b.name)));
+
+        let payload = caps
+            .into_iter()
+            .map(|cap| {
+                let mut line = cap.name.clone();
+                if let Some(args) = cap.args.as_ref() {
+                    let mut args = args.iter().collect::<Vec<_>>();
+                    args.sort();
+                    args.into_iter().for_each(|arg| {
+                        line.push(' ');
+                        line.push_str(arg);
+                    });
+                }
+                line.push_str("\r\n");
+                line
+            })
+            .collect::<String>();
+
+        write_multiline(w, payload.as_bytes())
+    }
+}
@@ -1,11 +1,15 @@
 use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
 use crate::types::response::util::{err_if_not_kind, parse_field};
+use crate::types::response::write::WriteResponse;
 
 /// Newsgroup metadata returned by [`GROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.1)
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
     /// The _estimated_ number of articles in the group
     pub number: ArticleNumber,
@@ -21,6 +25,14 @@ impl TryFrom<&RawResponse> for Group {
     type Error = Error;
 
     fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Group {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
         err_if_not_kind(resp, Kind::GroupSelected)?;
 
         let lossy = resp.first_line_to_utf8_lossy();
@@ -42,3 +54,17 @@ impl TryFrom<&RawResponse> for Group {
         })
     }
 }
+
+impl WriteResponse for Group {
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "{} {} {} {} {}\r\n",
+            ResponseCode::from(Kind::GroupSelected),
+            self.number,
+            self.low,
+            self.high,
+            self.name
+        )
+    }
+}
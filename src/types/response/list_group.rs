@@ -0,0 +1,87 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
+
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::write::{write_multiline, WriteResponse};
+
+/// The article numbers in a newsgroup returned by
+/// [`LISTGROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.2)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListGroup {
+    /// Metadata for the group, identical to what `GROUP` would return
+    pub group: Group,
+    /// The article numbers in the group (or the requested range)
+    pub articles: ArticleSet,
+}
+
+impl TryFrom<&RawResponse> for ListGroup {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for ListGroup {
+    type Error = Error;
+
+    /// Parse a `LISTGROUP` response
+    ///
+    /// Unlike `GROUP`, the 211 response to `LISTGROUP` contains a data block section with one
+    /// article number per line.
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
+        let group = Group::try_from(resp)?;
+
+        let articles = resp
+            .data_blocks
+            .as_ref()
+            .ok_or_else(Error::missing_data_blocks)?
+            .unterminated()
+            .map(|line| {
+                std::str::from_utf8(line)?
+                    .trim()
+                    .parse::<ArticleNumber>()
+                    .map_err(|_| Error::parse_error("article-number"))
+            })
+            .collect::<Result<ArticleSet>>()?;
+
+        Ok(Self { group, articles })
+    }
+}
+
+impl WriteResponse for ListGroup {
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.group.write_response(w)?;
+        let payload = self
+            .articles
+            .iter()
+            .map(|number| format!("{}\r\n", number))
+            .collect::<String>();
+        write_multiline(w, payload.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        let resp = RawResponse::with_data_blocks(
+            Kind::GroupSelected,
+            "2000 3000234 3002322 misc.test list follows",
+            "3000234\r\n3000237\r\n3000238\r\n3000239\r\n3000241\r\n",
+        );
+
+        let list_group = ListGroup::try_from(&resp).unwrap();
+        assert_eq!(list_group.group.name, "misc.test");
+        assert_eq!(list_group.group.high, 3002322);
+        assert_eq!(
+            list_group.articles.ranges().collect::<Vec<_>>(),
+            vec![3000234..=3000234, 3000237..=3000239, 3000241..=3000241]
+        );
+    }
+}
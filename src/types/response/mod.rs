@@ -1,10 +1,19 @@
 mod article;
 mod capabilities;
 mod group;
+mod list_group;
+mod overview;
 mod util;
+mod write;
 
 pub use article::*;
 
 pub use group::*;
 
-pub use capabilities::Capabilities;
+pub use list_group::ListGroup;
+
+pub use overview::{Overview, Overviews};
+
+pub use capabilities::{Capabilities, Capability};
+
+pub use write::WriteResponse;
@@ -0,0 +1,194 @@
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::util::err_if_not_kind;
+
+/// A single overview record returned by
+/// [`OVER`](https://tools.ietf.org/html/rfc3977#section-8.3) or
+/// [`XOVER`](https://tools.ietf.org/html/rfc2980#section-2.8)
+///
+/// Overview fields are lossily converted to UTF-8.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Overview {
+    /// The number of the article, or 0 if the record was requested by message-id
+    pub number: ArticleNumber,
+    /// The `Subject` header
+    pub subject: String,
+    /// The `From` header
+    pub from: String,
+    /// The `Date` header
+    pub date: String,
+    /// The `Message-ID` header
+    pub message_id: String,
+    /// The `References` header
+    pub references: String,
+    /// The size of the article in octets, if the server reported it
+    pub bytes: Option<u64>,
+    /// The number of lines in the article body, if the server reported it
+    pub lines: Option<u64>,
+    /// Any additional fields, in the order given by `LIST OVERVIEW.FMT` (e.g. `Xref: ...`)
+    pub extra: Vec<String>,
+}
+
+impl TryFrom<&[u8]> for Overview {
+    type Error = Error;
+
+    /// Parse a single tab separated overview line
+    ///
+    /// A trailing CRLF terminator is ignored
+    fn try_from(line: &[u8]) -> Result<Self> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let mut fields = line
+            .split(|b| *b == b'\t')
+            .map(|field| String::from_utf8_lossy(field).into_owned());
+
+        let mut next_field = |name: &str| fields.next().ok_or_else(|| Error::missing_field(name));
+
+        let number = next_field("number")?
+            .trim()
+            .parse()
+            .map_err(|_| Error::parse_error("number"))?;
+        let subject = next_field("subject")?;
+        let from = next_field("from")?;
+        let date = next_field("date")?;
+        let message_id = next_field("message-id")?;
+        let references = next_field("references")?;
+        let bytes = parse_metadata(next_field(":bytes")?, ":bytes")?;
+        let lines = parse_metadata(next_field(":lines")?, ":lines")?;
+        let extra = fields.collect();
+
+        Ok(Self {
+            number,
+            subject,
+            from,
+            date,
+            message_id,
+            references,
+            bytes,
+            lines,
+            extra,
+        })
+    }
+}
+
+/// Parse an optional numeric metadata field such as `:bytes` or `:lines`
+fn parse_metadata(field: String, name: &str) -> Result<Option<u64>> {
+    let field = field.trim();
+    if field.is_empty() {
+        Ok(None)
+    } else {
+        field
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::parse_error(name))
+    }
+}
+
+/// A collection of overview records
+///
+/// An `Overviews` is usually created by calling `try_from` with the [`RawResponse`] returned by
+/// `OVER` or `XOVER`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Overviews(Vec<Overview>);
+
+impl Overviews {
+    /// An iterator over the records
+    pub fn iter(&self) -> std::slice::Iter<'_, Overview> {
+        self.0.iter()
+    }
+
+    /// The number of records
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no records
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the records
+    pub fn into_vec(self) -> Vec<Overview> {
+        self.0
+    }
+}
+
+impl IntoIterator for Overviews {
+    type Item = Overview;
+    type IntoIter = std::vec::IntoIter<Overview>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl TryFrom<&RawResponse> for Overviews {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        Self::try_from(&resp.as_borrowed())
+    }
+}
+
+impl TryFrom<&RawResponseRef<'_>> for Overviews {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponseRef<'_>) -> Result<Self> {
+        err_if_not_kind(resp, Kind::Overview)?;
+
+        resp.data_blocks
+            .as_ref()
+            .ok_or_else(Error::missing_data_blocks)?
+            .unterminated()
+            .map(Overview::try_from)
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XOVER_RESP: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/xover_resp_plain_text"
+    ));
+
+    #[test]
+    fn test_try_from_line() {
+        let end = XOVER_RESP.iter().position(|b| *b == b'\n').unwrap();
+        let overview = Overview::try_from(&XOVER_RESP[..=end]).unwrap();
+
+        assert_eq!(overview.number, 461197);
+        assert_eq!(
+            overview.subject,
+            "Re: Weekly Statistics (21.6.2003 - 27.6.2003)"
+        );
+        assert_eq!(overview.from, "Peter J Ross <gadfly@meow.org>");
+        assert_eq!(overview.message_id, "<ht23eb.3ta.ln@gadfly.meow.org>");
+        assert_eq!(overview.bytes, Some(5165));
+        assert_eq!(overview.lines, Some(109));
+        assert_eq!(overview.extra.len(), 1);
+        assert!(overview.extra[0].starts_with("Xref: "));
+        assert!(!overview.extra[0].ends_with(|c: char| c.is_whitespace()));
+
+        let unterminated = Overview::try_from(&XOVER_RESP[..end - 1]).unwrap();
+        assert_eq!(unterminated, overview);
+    }
+
+    #[test]
+    fn test_missing_metadata() {
+        let line = b"42\tsubject\tfrom\tdate\t<id@example.com>\t\t\t";
+        let overview = Overview::try_from(&line[..]).unwrap();
+        assert_eq!(overview.bytes, None);
+        assert_eq!(overview.lines, None);
+        assert!(overview.extra.is_empty());
+
+        assert!(Overview::try_from(&b"42\tsubject\tfrom"[..]).is_err());
+    }
+}
@@ -20,7 +20,7 @@ pub(crate) fn parse_field<'a, T: FromStr>(
 }
 
 /// Return a deserialization error if the response does match the desired error code
-pub(crate) fn err_if_not_kind(resp: &RawResponse, desired: Kind) -> Result<()> {
+pub(crate) fn err_if_not_kind(resp: &RawResponseRef<'_>, desired: Kind) -> Result<()> {
     if resp.code != ResponseCode::Known(desired) {
         Err(Error::Deserialization(format!(
             "Invalid response code {}",
@@ -31,7 +31,9 @@ pub(crate) fn err_if_not_kind(resp: &RawResponse, desired: Kind) -> Result<()> {
     }
 }
 
-pub(crate) fn process_article_first_line(resp: &RawResponse) -> Result<(ArticleNumber, String)> {
+pub(crate) fn process_article_first_line(
+    resp: &RawResponseRef<'_>,
+) -> Result<(ArticleNumber, String)> {
     let lossy = resp.first_line_to_utf8_lossy();
     let mut iter = lossy.split_whitespace();
 
@@ -0,0 +1,195 @@
+use std::io;
+use std::io::Write;
+
+use crate::raw::encode::write_data_blocks;
+use crate::types::prelude::*;
+
+/// A response that can be written to the wire
+///
+/// This is the inverse of converting a [`RawResponse`] into a typed response and is intended for
+/// building servers and proxies.
+///
+/// Responses are written with their response code, CRLF terminators, dot-stuffing and, for
+/// multi-line responses, the terminating `.` line.
+///
+/// # Example
+///
+/// ```
+/// use brokaw::types::prelude::*;
+///
+/// let group = Group {
+///     number: 1234,
+///     low: 3000234,
+///     high: 3002322,
+///     name: "misc.test".to_string(),
+/// };
+///
+/// assert_eq!(group.to_wire(), b"211 1234 3000234 3002322 misc.test\r\n");
+/// ```
+pub trait WriteResponse {
+    /// Write the response to `w`
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()>;
+
+    /// Write the response into a new vector
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        // writes to a Vec are infallible
+        self.write_response(&mut buf)
+            .expect("Failed to write response to vec");
+        buf
+    }
+}
+
+impl WriteResponse for RawResponse {
+    /// Write the response exactly as it was received
+    ///
+    /// Data blocks are stored in wire format so they are written verbatim.
+    fn write_response<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.first_line)?;
+        if let Some(data_blocks) = self.data_blocks.as_ref() {
+            w.write_all(data_blocks.payload())?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a multi-line data block section
+pub(crate) fn write_multiline<W: Write + ?Sized>(w: &mut W, payload: &[u8]) -> io::Result<()> {
+    write_data_blocks(w, payload).map(drop)
+}
+
+/// Write headers in wire format, in the order they were received
+pub(crate) fn write_headers<W: Write + ?Sized>(w: &mut W, headers: &Headers) -> io::Result<()> {
+    for (name, content) in headers.in_order() {
+        if name.starts_with('.') {
+            w.write_all(b".")?;
+        }
+        write!(w, "{}: {}\r\n", name, content)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::raw::connection::read_raw_response;
+
+    const TEXT_ARTICLE: &[u8] =
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/text_article"));
+
+    fn reparse<T>(resp: &T, is_multiline: bool) -> T
+    where
+        T: WriteResponse + for<'a> TryFrom<&'a RawResponse, Error = crate::error::Error>,
+    {
+        let wire = resp.to_wire();
+        let raw = read_raw_response(&mut wire.as_slice(), is_multiline).unwrap();
+        assert_eq!(raw.to_wire(), wire);
+        T::try_from(&raw).unwrap_or_else(|e| panic!("{:?}\n{}", e, String::from_utf8_lossy(&wire)))
+    }
+
+    #[test]
+    fn test_raw_response() {
+        let raw = read_raw_response(&mut &TEXT_ARTICLE[..], true).unwrap();
+        assert_eq!(raw.to_wire(), TEXT_ARTICLE);
+
+        let resp = RawResponse::with_data_blocks(Kind::Head, "1 <a@b>", "Subject: hi\n.dot\n");
+        assert_eq!(
+            resp.to_wire(),
+            b"221 1 <a@b>\r\nSubject: hi\r\n..dot\r\n.\r\n"
+        );
+        assert_eq!(resp.data_blocks().unwrap().lines_len(), 3);
+    }
+
+    #[test]
+    fn test_article_round_trip() {
+        let raw = read_raw_response(&mut &TEXT_ARTICLE[..], true).unwrap();
+        let article = BinaryArticle::try_from(&raw).unwrap();
+        assert_eq!(reparse(&article, false), article);
+
+        let mut body = RawResponse::new(
+            Kind::Body,
+            format!("{} {}", article.number(), article.message_id()),
+        );
+        body.data_blocks = Some(DataBlocks::from_wire(article.body().to_vec()).unwrap());
+        let body = Body::try_from(&body).unwrap();
+        assert_eq!(reparse(&body, false), body);
+        assert_eq!(body.body(), article.body());
+
+        let head = Head {
+            number: article.number(),
+            message_id: article.message_id().to_string(),
+            headers: article.headers().clone(),
+        };
+        assert_eq!(reparse(&head, false), head);
+    }
+
+    #[test]
+    fn test_header_order() {
+        let raw = RawResponse::with_data_blocks(
+            Kind::Article,
+            "1 <a@example.com>",
+            "Path: b!a\r\nMessage-ID: <a@example.com>\r\nPath: c\r\nAbc: d\r\n\r\nbody\r\n",
+        );
+        let article = BinaryArticle::try_from(&raw).unwrap();
+        assert_eq!(article.to_wire(), raw.to_wire());
+
+        let raw = RawResponse::with_data_blocks(
+            Kind::Head,
+            "1 <a@example.com>",
+            "Received: 2\r\nReceived: 1\r\nFrom: a\r\n",
+        );
+        let head = Head::try_from(&raw).unwrap();
+        assert_eq!(head.to_wire(), raw.to_wire());
+    }
+
+    #[test]
+    fn test_single_line_round_trip() {
+        let group = Group {
+            number: 1234,
+            low: 3000234,
+            high: 3002322,
+            name: "misc.test".to_string(),
+        };
+        assert_eq!(reparse(&group, false), group);
+
+        let stat = Stat {
+            number: 3000234,
+            message_id: "<45223423@example.com>".to_string(),
+        };
+        assert_eq!(stat.to_wire(), b"223 3000234 <45223423@example.com>\r\n");
+        assert_eq!(reparse(&stat, false), stat);
+    }
+
+    #[test]
+    fn test_capabilities_round_trip() {
+        let raw = RawResponse::with_data_blocks(
+            Kind::Capabilities,
+            "Capability list:",
+            "VERSION 2\r\nREADER\r\nLIST ACTIVE NEWSGROUPS\r\nIHAVE\r\n",
+        );
+        let caps = Capabilities::try_from(&raw).unwrap();
+        assert_eq!(
+            caps.to_wire(),
+            b"101 Capability list:\r\nVERSION 2\r\nIHAVE\r\nLIST ACTIVE NEWSGROUPS\r\nREADER\r\n.\r\n"
+                .to_vec()
+        );
+        assert_eq!(reparse(&caps, false), caps);
+    }
+
+    #[test]
+    fn test_list_group_round_trip() {
+        let list_group = ListGroup {
+            group: Group {
+                number: 3,
+                low: 1,
+                high: 5,
+                name: "misc.test".to_string(),
+            },
+            articles: vec![1, 2, 5].into_iter().collect(),
+        };
+        assert_eq!(reparse(&list_group, true), list_group);
+    }
+}
@@ -22,31 +22,31 @@ impl ResponseCode {
     /// The response is a 1xx
     pub fn is_info(&self) -> bool {
         let code = u16::from(*self);
-        code >= 100 && code < 200
+        (100..200).contains(&code)
     }
 
     /// The response is a 2xx
     pub fn is_success(&self) -> bool {
         let code = u16::from(*self);
-        code >= 200 && code < 300
+        (200..300).contains(&code)
     }
 
     /// The response is a 3xx
     pub fn is_success_so_far(&self) -> bool {
         let code = u16::from(*self);
-        code >= 300 && code < 400
+        (300..400).contains(&code)
     }
 
     /// The response is a 4xx
     pub fn is_failure(&self) -> bool {
         let code = u16::from(*self);
-        code >= 400 && code < 500
+        (400..500).contains(&code)
     }
 
     /// The response is a 5xx
     pub fn is_error(&self) -> bool {
         let code = u16::from(*self);
-        code >= 500 && code < 600
+        (500..600).contains(&code)
     }
 
     /// Returns true if the response is a Known multiline response
@@ -120,6 +120,7 @@ pub enum Kind {
     Overview = 224,
 
     ArticleTransferredOk = 235,
+    ArticleReceived = 240,
 
     IHaveSendArticle = 335,
     PostSendArticle = 340,
@@ -153,6 +154,14 @@ pub enum Kind {
     PasswordRequired = 381,
     AuthenticationFailed = 481,
     AuthenticationOutOfSequence = 482,
+
+    // Streaming https://tools.ietf.org/html/rfc4644
+    StreamingPermitted = 203,
+    CheckSendArticle = 238,
+    TakeThisTransferredOk = 239,
+    CheckTryLater = 431,
+    CheckNotWanted = 438,
+    TakeThisRejected = 439,
 }
 
 impl Kind {
@@ -172,6 +181,8 @@ impl Kind {
     ///
     /// Rather than making the entire library more complex for this singular exception, the library
     /// assumes that code 211 DOES NOT correspond to a multi-line response.
+    /// `LISTGROUP` responses should be read with
+    /// [`NntpConnection::command_multiline`](crate::raw::connection::NntpConnection::command_multiline).
     pub fn is_multiline(&self) -> bool {
         matches!(*self as u16, 100..=101 | 215 | 220..=222 | 224..=225 | 230..=231)
     }
@@ -188,3 +199,35 @@ impl From<Kind> for ResponseCode {
         ResponseCode::Known(kind)
     }
 }
+
+/// Response codes are serialized as integers
+#[cfg(feature = "serde")]
+impl serde::Serialize for ResponseCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(u16::from(*self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ResponseCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u16::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Kinds are serialized as integers
+#[cfg(feature = "serde")]
+impl serde::Serialize for Kind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(*self as u16)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Kind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = u16::deserialize(deserializer)?;
+        Kind::try_from(code)
+            .map_err(|_| serde::de::Error::custom(format!("Unknown response code {}", code)))
+    }
+}
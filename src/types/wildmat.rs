@@ -0,0 +1,359 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A [wildmat](https://tools.ietf.org/html/rfc3977#section-4), the pattern syntax NNTP uses to
+/// select newsgroups
+///
+/// A wildmat is a comma separated list of patterns, each of which may be negated with a leading
+/// `!` (except the first). A string matches the wildmat if the *last* pattern that matches it is
+/// not negated.
+///
+/// Within a pattern:
+///
+/// * `*` matches any sequence of characters (including none)
+/// * `?` matches any single character
+/// * `[...]` matches any character in the class, e.g. `[abc]` or `[a-z]`.
+///   The class is negated if it starts with `^`.
+/// * `\` matches the next character literally
+/// * Any other character matches itself. Matching is done on characters rather than bytes, so
+///   non-ASCII UTF-8 is supported.
+///
+/// Character classes and `\` escapes are reserved but not defined by RFC 3977. They are
+/// supported for compatibility with INN and most other servers.
+///
+/// ```
+/// use brokaw::types::Wildmat;
+///
+/// let wildmat: Wildmat = "comp.*,!comp.os.*".parse().unwrap();
+/// assert!(wildmat.matches("comp.lang.rust"));
+/// assert!(!wildmat.matches("comp.os.linux"));
+/// assert!(!wildmat.matches("misc.test"));
+/// assert_eq!(wildmat.to_string(), "comp.*,!comp.os.*");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Wildmat {
+    source: String,
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Pattern {
+    negated: bool,
+    items: Vec<Item>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum Item {
+    Literal(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// An error parsing a [`Wildmat`]
+///
+/// Positions are byte offsets into the wildmat
+#[derive(Copy, Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum WildmatError {
+    /// A pattern (or the entire wildmat) is empty
+    #[error("Empty pattern at {0}")]
+    EmptyPattern(usize),
+    /// The first pattern is negated
+    #[error("The first pattern of a wildmat cannot be negated")]
+    LeadingNegation,
+    /// The wildmat contains whitespace, a control character, or a misplaced `!` or `]`
+    #[error("Invalid character {0:?} at {1}")]
+    InvalidCharacter(char, usize),
+    /// A character class or escape is not terminated
+    #[error("Unterminated class or escape at {0}")]
+    Unterminated(usize),
+}
+
+impl Wildmat {
+    /// Parse a wildmat
+    pub fn new(wildmat: impl AsRef<str>) -> Result<Self, WildmatError> {
+        let source = wildmat.as_ref();
+        let mut patterns = Vec::new();
+        let mut start = 0;
+
+        for (i, pattern) in source.split(',').enumerate() {
+            let (negated, body, offset) = match pattern.strip_prefix('!') {
+                Some(_) if i == 0 => return Err(WildmatError::LeadingNegation),
+                Some(body) => (true, body, start + 1),
+                None => (false, pattern, start),
+            };
+            if body.is_empty() {
+                return Err(WildmatError::EmptyPattern(start));
+            }
+
+            patterns.push(Pattern {
+                negated,
+                items: parse_pattern(body, offset)?,
+            });
+            start += pattern.len() + 1;
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            patterns,
+        })
+    }
+
+    /// A wildmat that matches everything
+    pub fn any() -> Self {
+        Self {
+            source: "*".to_string(),
+            patterns: vec![Pattern {
+                negated: false,
+                items: vec![Item::Star],
+            }],
+        }
+    }
+
+    /// Returns true if the string matches the wildmat
+    pub fn matches(&self, s: impl AsRef<str>) -> bool {
+        let s = s.as_ref();
+        self.patterns
+            .iter()
+            .rev()
+            .find(|p| matches_items(&p.items, s))
+            .is_some_and(|p| !p.negated)
+    }
+
+    /// The wildmat as a string
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+fn parse_pattern(pattern: &str, offset: usize) -> Result<Vec<Item>, WildmatError> {
+    let mut items = Vec::new();
+    let mut chars = pattern.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let item = match c {
+            '*' => Item::Star,
+            '?' => Item::Any,
+            '\\' => match chars.next() {
+                Some((_, c)) => Item::Literal(c),
+                None => return Err(WildmatError::Unterminated(offset + i)),
+            },
+            '[' => {
+                let negated = chars.next_if(|(_, c)| *c == '^').is_some();
+                let mut ranges = Vec::new();
+                loop {
+                    let low = match chars.next() {
+                        // A `]` at the start of the class is a literal
+                        Some((_, ']')) if !ranges.is_empty() => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => c,
+                            None => return Err(WildmatError::Unterminated(offset + i)),
+                        },
+                        Some((_, c)) => c,
+                        None => return Err(WildmatError::Unterminated(offset + i)),
+                    };
+                    let is_range = chars.next_if(|(_, c)| *c == '-').is_some();
+                    let high = match (is_range, chars.peek()) {
+                        // A `-` at the end of the class is a literal
+                        (true, Some((_, ']'))) => {
+                            ranges.push(('-', '-'));
+                            low
+                        }
+                        (true, Some(_)) => chars.next().map(|(_, c)| c).unwrap(),
+                        (true, None) => return Err(WildmatError::Unterminated(offset + i)),
+                        (false, _) => low,
+                    };
+                    ranges.push((low, high));
+                }
+                Item::Class { negated, ranges }
+            }
+            '!' | ']' => return Err(WildmatError::InvalidCharacter(c, offset + i)),
+            c if c.is_whitespace() || c.is_control() => {
+                return Err(WildmatError::InvalidCharacter(c, offset + i))
+            }
+            c => Item::Literal(c),
+        };
+        items.push(item);
+    }
+
+    Ok(items)
+}
+
+/// Match a pattern against a string
+///
+/// This backtracks to the most recent `*` on a mismatch, which keeps matching linear for
+/// patterns with a single `*` and avoids exponential blowup for patterns with several.
+///
+/// Positions in `s` are byte offsets so matching does not allocate.
+fn matches_items(items: &[Item], s: &str) -> bool {
+    let (mut p, mut i) = (0, 0);
+    let mut backtrack = None;
+
+    while let Some(c) = s[i..].chars().next() {
+        match items.get(p) {
+            Some(Item::Star) => {
+                backtrack = Some((p, i));
+                p += 1;
+            }
+            Some(item) if matches_char(item, c) => {
+                p += 1;
+                i += c.len_utf8();
+            }
+            _ => match backtrack {
+                // Let the last `*` consume one more character
+                Some((star, start)) => {
+                    let start = start + s[start..].chars().next().map_or(0, char::len_utf8);
+                    backtrack = Some((star, start));
+                    p = star + 1;
+                    i = start;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    items[p..].iter().all(|item| *item == Item::Star)
+}
+
+fn matches_char(item: &Item, c: char) -> bool {
+    match item {
+        Item::Literal(l) => *l == c,
+        Item::Any => true,
+        Item::Star => false,
+        Item::Class { negated, ranges } => {
+            ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated
+        }
+    }
+}
+
+impl fmt::Display for Wildmat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl FromStr for Wildmat {
+    type Err = WildmatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for Wildmat {
+    type Error = WildmatError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for Wildmat {
+    type Error = WildmatError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+/// Wildmats are serialized as strings
+#[cfg(feature = "serde")]
+impl serde::Serialize for Wildmat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Wildmat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::new(source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wildmat(s: &str) -> Wildmat {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        // RFC 3977 Section 4.4
+        let w = wildmat("a*,!*b,*c*");
+        assert!(w.matches("aaa"));
+        assert!(!w.matches("abb"));
+        assert!(w.matches("ccb"));
+        assert!(!w.matches("xxx"));
+
+        let w = wildmat("a*,!*b");
+        assert!(w.matches("abc"));
+        assert!(!w.matches("abb"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(wildmat("*").matches(""));
+        assert!(wildmat("a?c").matches("abc"));
+        assert!(!wildmat("a?c").matches("ac"));
+        assert!(wildmat("*.*.*").matches("comp.lang.rust"));
+        assert!(!wildmat("*.*.*").matches("comp.lang"));
+        assert!(wildmat("*rust*").matches("comp.lang.rust.announce"));
+        assert!(wildmat("a*b*c").matches("aXbXbXc"));
+        assert!(!wildmat("a*b*c").matches("aXbXbX"));
+        assert!(wildmat("alt.??").matches("alt.ελ"));
+        assert!(wildmat("*λ?").matches("αλλλβ"));
+        assert!(!wildmat("*λ").matches("αλβ"));
+    }
+
+    #[test]
+    fn test_classes() {
+        let w = wildmat("comp.[a-c]*");
+        assert!(w.matches("comp.arch"));
+        assert!(!w.matches("comp.os"));
+
+        let w = wildmat("[^a-c]*");
+        assert!(w.matches("dog"));
+        assert!(!w.matches("cat"));
+
+        assert!(wildmat("[]x]").matches("]"));
+        assert!(wildmat("[a-]").matches("-"));
+        assert!(wildmat("a\\*").matches("a*"));
+        assert!(!wildmat("a\\*").matches("ab"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(Wildmat::new(""), Err(WildmatError::EmptyPattern(0)));
+        assert_eq!(Wildmat::new("a,,b"), Err(WildmatError::EmptyPattern(2)));
+        assert_eq!(Wildmat::new("!a"), Err(WildmatError::LeadingNegation));
+        assert_eq!(
+            Wildmat::new("a,b c"),
+            Err(WildmatError::InvalidCharacter(' ', 3))
+        );
+        assert_eq!(
+            Wildmat::new("a!b"),
+            Err(WildmatError::InvalidCharacter('!', 1))
+        );
+        assert_eq!(Wildmat::new("a[bc"), Err(WildmatError::Unterminated(1)));
+        assert_eq!(Wildmat::new("a\\"), Err(WildmatError::Unterminated(1)));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for s in &[
+            "comp.*,!comp.os.*",
+            "alt.binaries.[a-z]?",
+            Wildmat::any().as_str(),
+        ] {
+            assert_eq!(wildmat(s).to_string(), *s);
+        }
+    }
+}